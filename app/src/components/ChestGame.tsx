@@ -122,7 +122,7 @@ export const ChestGame: FC = () => {
         try {
           const game = await program.account.gameAccount.fetchNullable(gamePda);
           if (!game) return false;
-          const status = game.status as number; // 0 None, 1 Pending, 2 Completed, 3 Cancelled
+          const status = game.status as number; // 0 None, 1 Pending, 2 Completed, 3 Cancelled, 4 Series, 5 Refunded
           if (status !== 1) return false;
           const createdAt = Number(game.createdAt);
           const now = Math.floor(Date.now() / 1000);
@@ -130,7 +130,7 @@ export const ChestGame: FC = () => {
 
           console.log("Cancelling stale pending game...", gamePda.toBase58());
           const cancelTx = await program.methods
-            .cancelGame()
+            .cancelGame(false)
             .accountsPartial({ player: wallet.publicKey!, gameAccount: gamePda })
             .rpc();
           console.log("Cancelled stale game:", cancelTx);
@@ -395,6 +395,8 @@ export const ChestGame: FC = () => {
             break;
           } else if (status === 3) { // Cancelled
             throw new Error("Game was cancelled by the network.");
+          } else if (status === 5) { // Refunded
+            throw new Error("The network couldn't settle this game fairly, so your bet was refunded.");
           } else if (status === 1) {
             // Still pending — check if a callback already failed (AbortedComputation)
             const elapsed = Date.now() - pollStartTime;
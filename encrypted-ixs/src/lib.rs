@@ -14,30 +14,171 @@ mod circuits {
     /// 
     /// The player picks a chest (0 to num_chests-1) and encrypts their choice.
     /// The MPC network generates a random winning chest and compares.
-    /// Returns: (player_won: bool, winning_chest: u8) as plaintext for verification.
-    
+    /// Returns: (outcome: u8, winning_chest: u8, sampled: bool, num_chests: u8,
+    /// raw_draw: u8, draw_revealed: bool) as plaintext for verification, where
+    /// outcome is 0 = lose, 1 = win, 2 = push (bet refunded), sampled is false
+    /// if the RNG ran out of attempts and fell back, and num_chests echoes the
+    /// input so the program can cross-check it. With reveal_draw set, raw_draw
+    /// is the RNG draw before the entropy shifted it, so verifiers can redo
+    /// the range reduction; otherwise it is 0 and draw_revealed is false.
+    /// With win_mode 1 (near-miss) the choice also wins on either neighbour of
+    /// the winning chest, wrapping around the ends. player_entropy, derived
+    /// from the player's seed, shifts the MPC draw (mod the draw range), so
+    /// neither the player nor the cluster alone picks the winning chest.
+    /// slot_entropy, from a recent slot hash, shifts it the same way on top
+    /// (0 when the program doesn't mix one in).
     #[instruction]
     pub fn play_chest_game(
         player_choice_ctxt: Enc<Shared, PlayerChoice>,  // Player's encrypted chest choice
         num_chests: u8,                                  // Number of chests (2-5, plaintext)
-    ) -> (bool, u8) {
+        allow_push: bool,                                // Whether the push outcome is in play
+        win_mode: u8,                                    // 0 = exact, 1 = within one chest
+        player_entropy: u64,                             // Player seed contribution, plaintext
+        slot_entropy: u64,                               // Recent slot hash contribution, plaintext
+        reveal_draw: bool,                               // Also reveal the unshifted draw
+    ) -> (u8, u8, bool, u8, u8, bool) {
         // Decrypt player's choice inside MPC
         let player_choice = player_choice_ctxt.to_arcis();
         
-        // Generate random winning chest (0 to num_chests-1)
-        // Using the same approach as Arcium's rock_paper_scissors/against-house:
-        // combine random bits. 3 bits give us 0-7; modulo maps into [0, num_chests).
-        let b0 = ArcisRNG::bool() as u8;
-        let b1 = ArcisRNG::bool() as u8;
-        let b2 = ArcisRNG::bool() as u8;
-        let random_3bit = b0 + (b1 * 2) + (b2 * 4); // 0..=7
-        let winning_chest = random_3bit % num_chests;
+        // Draw the winning chest uniformly by rejection sampling. With pushes
+        // enabled there is one extra "push chest" at index num_chests, so a
+        // game pays n x on 1/(n+1) and refunds on 1/(n+1): still fair.
+        // Each attempt succeeds with > 1/2 chance, so 8 attempts fail < 1/256.
+        let max_chest = if allow_push { num_chests } else { num_chests - 1 };
+        let (draw, sampled) = ArcisRNG::gen_integer_in_range(0, max_chest as u128, 8);
+        // Adding a fixed offset mod the range keeps a uniform draw uniform
+        let range = max_chest + 1;
+        let range_u64 = range as u64;
+        let offset = ((player_entropy % range_u64 + slot_entropy % range_u64) % range_u64) as u8;
+        let winning_chest = (draw as u8 + offset) % range;
+        let push = winning_chest == num_chests;
         
-        // Check if player won
-        let player_won = player_choice.choice == winning_chest;
+        // Check if player won. Near-miss compares the circular distance, so
+        // every pick has the same three winning chests and the odds are fixed
+        let choice = player_choice.choice;
+        let distance = choice.abs_diff(winning_chest);
+        let near = (distance <= 1) | (distance == num_chests - 1);
+        let hit = if win_mode == 1 { near } else { choice == winning_chest };
+        let player_won = !push & hit;
+        let outcome: u8 = if push { 2 } else if player_won { 1 } else { 0 };
         
-        // Return plaintext result - both values are revealed publicly
+        let raw_draw: u8 = if reveal_draw { draw as u8 } else { 0 };
+
+        // Return plaintext result - all values are revealed publicly
         // This proves fairness: winning chest was determined after player committed
-        (player_won.reveal(), winning_chest.reveal())
+        (
+            outcome.reveal(),
+            winning_chest.reveal(),
+            sampled.reveal(),
+            num_chests.reveal(),
+            raw_draw.reveal(),
+            reveal_draw.reveal(),
+        )
+    }
+
+    /// Player's encrypted chest choice together with the chest count, for
+    /// games that hide their difficulty until settlement.
+    pub struct HiddenGame {
+        pub choice: u8,     // Chest number (0 to num_chests-1)
+        pub num_chests: u8, // Number of chests (2-5)
+    }
+
+    /// Variant of `play_chest_game` where the chest count is encrypted too.
+    /// The bounds check happens here, since the program never sees the count.
+    /// Returns: (outcome, winning_chest, sampled, num_chests, valid), where
+    /// valid is false if the chest count or choice was out of range. An
+    /// invalid game always reports a loss and the program refunds it.
+    #[instruction]
+    pub fn play_hidden_chest_game(
+        game_ctxt: Enc<Shared, HiddenGame>, // Player's encrypted choice and chest count
+        allow_push: bool,                   // Whether the push outcome is in play
+    ) -> (u8, u8, bool, u8, bool) {
+        let game = game_ctxt.to_arcis();
+        let num_chests = game.num_chests;
+
+        // Mirrors MIN_CHESTS / MAX_CHESTS in the program. Arcis has no
+        // RangeInclusive::contains, so compare the bounds directly
+        #[allow(clippy::manual_range_contains)]
+        let valid = (num_chests >= 2) & (num_chests <= 5) & (game.choice < num_chests);
+
+        // The draw range is secret, so draw over 60 (a multiple of every
+        // possible range, 2 to 6 with the push chest) and reduce it, which
+        // keeps the winning chest uniform
+        let range = if allow_push { num_chests + 1 } else { num_chests };
+        let range = if valid { range } else { 2 };
+        let (draw, sampled) = ArcisRNG::gen_integer_in_range(0, 59, 8);
+        let winning_chest = (draw as u8) % range;
+        let push = allow_push & (winning_chest == num_chests);
+
+        let player_won = valid & !push & (game.choice == winning_chest);
+        let outcome: u8 = if push { 2 } else if player_won { 1 } else { 0 };
+
+        (
+            outcome.reveal(),
+            winning_chest.reveal(),
+            sampled.reveal(),
+            num_chests.reveal(),
+            valid.reveal(),
+        )
+    }
+
+    /// Odds game: instead of picking a chest, the game sets the win
+    /// probability directly. The encrypted choice picks the side of the roll:
+    /// 0 wins on a roll under win_probability_bps, 1 on a roll at or above
+    /// 10000 - win_probability_bps, so either side has the same odds.
+    /// Returns: (outcome, roll, sampled, win_probability_bps), where outcome
+    /// is 0 = lose, 1 = win, roll is in [0, 10000), and the probability is
+    /// echoed so the program can cross-check it.
+    #[instruction]
+    pub fn play_odds_game(
+        player_choice_ctxt: Enc<Shared, PlayerChoice>, // Encrypted side of the roll
+        win_probability_bps: u16,                      // Win probability in basis points
+    ) -> (u8, u16, bool, u16) {
+        let player_choice = player_choice_ctxt.to_arcis();
+
+        let (draw, sampled) = ArcisRNG::gen_integer_in_range(0, 9_999, 8);
+        let roll = draw as u16;
+        let roll_under = roll < win_probability_bps;
+        let roll_over = roll >= 10_000 - win_probability_bps;
+        let player_won = if player_choice.choice == 0 { roll_under } else { roll_over };
+        let outcome: u8 = if player_won { 1 } else { 0 };
+
+        (
+            outcome.reveal(),
+            roll.reveal(),
+            sampled.reveal(),
+            win_probability_bps.reveal(),
+        )
+    }
+
+    /// Shared-pot game: up to three players each encrypt a chest choice under
+    /// their own key, and a single draw decides every player at once.
+    /// Returns: (winning_chest, won_0, won_1, won_2, sampled, num_chests),
+    /// where won_i is whether player i picked the winning chest. Two-player
+    /// games pass player 0's choice again in the last slot and the program
+    /// ignores won_2.
+    #[instruction]
+    pub fn play_pot_game(
+        choice_0: Enc<Shared, PlayerChoice>, // Player 0's encrypted chest choice
+        choice_1: Enc<Shared, PlayerChoice>, // Player 1's encrypted chest choice
+        choice_2: Enc<Shared, PlayerChoice>, // Player 2's encrypted chest choice
+        num_chests: u8,                      // Number of chests (2-5, plaintext)
+    ) -> (u8, bool, bool, bool, bool, u8) {
+        let choice_0 = choice_0.to_arcis().choice;
+        let choice_1 = choice_1.to_arcis().choice;
+        let choice_2 = choice_2.to_arcis().choice;
+
+        // No push chest: someone may win or nobody does
+        let (draw, sampled) = ArcisRNG::gen_integer_in_range(0, (num_chests - 1) as u128, 8);
+        let winning_chest = draw as u8;
+
+        (
+            winning_chest.reveal(),
+            (choice_0 == winning_chest).reveal(),
+            (choice_1 == winning_chest).reveal(),
+            (choice_2 == winning_chest).reveal(),
+            sampled.reveal(),
+            num_chests.reveal(),
+        )
     }
 }
@@ -1,15 +1,236 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::program_pack::Pack;
 use anchor_lang::system_program;
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token::state::{Account as SplTokenAccount, AccountState, Mint as SplMint};
+use anchor_spl::token::{self, MintTo};
 use arcium_anchor::prelude::*;
 use arcium_anchor::LUT_PROGRAM_ID;
-use arcium_client::idl::arcium::types::{CallbackAccount, CircuitSource, OffChainCircuitSource};
+use arcium_client::idl::arcium::accounts::ComputationAccount;
+use arcium_client::idl::arcium::types::{
+    CallbackAccount, CircuitSource, ComputationStatus, OffChainCircuitSource,
+};
 use arcium_macros::circuit_hash;
+use solana_instructions_sysvar::{load_current_index_checked, load_instruction_at_checked};
+use solana_sdk_ids::ed25519_program;
+use solana_sha256_hasher::hash;
 
-const COMP_DEF_OFFSET_PLAY_CHEST_GAME: u32 = comp_def_offset("play_chest_game");
+/// Computation definition offsets of the circuits, exported so clients and
+/// tests reference them instead of recomputing `comp_def_offset`
+#[constant]
+pub const COMP_DEF_OFFSET_PLAY_CHEST_GAME: u32 = comp_def_offset("play_chest_game");
+#[constant]
+pub const COMP_DEF_OFFSET_PLAY_HIDDEN_CHEST_GAME: u32 = comp_def_offset("play_hidden_chest_game");
+#[constant]
+pub const COMP_DEF_OFFSET_PLAY_ODDS_GAME: u32 = comp_def_offset("play_odds_game");
+#[constant]
+pub const COMP_DEF_OFFSET_PLAY_POT_GAME: u32 = comp_def_offset("play_pot_game");
 
-// Seeds for PDAs
-pub const TREASURY_SEED: &[u8] = b"treasury";
-pub const GAME_SEED: &[u8] = b"game";
+/// PDA seeds and address helpers, shared with off-chain Rust clients
+pub mod seeds {
+    use anchor_lang::prelude::Pubkey;
+
+    pub const TREASURY_SEED: &[u8] = b"treasury";
+    pub const GAME_SEED: &[u8] = b"game";
+    pub const CONFIG_SEED: &[u8] = b"config";
+    pub const PROMO_SEED: &[u8] = b"promo";
+    pub const HOUSE_STATS_SEED: &[u8] = b"house_stats";
+    pub const FEE_SPLIT_SEED: &[u8] = b"fee_split";
+    pub const SESSION_SEED: &[u8] = b"session";
+    pub const PENDING_CLAIM_SEED: &[u8] = b"pending_claim";
+    pub const RESULT_LOG_SEED: &[u8] = b"result_log";
+    pub const PLAYER_STATS_SEED: &[u8] = b"player_stats";
+    pub const COLD_TREASURY_SEED: &[u8] = b"cold_treasury";
+    pub const STAKE_POOL_SEED: &[u8] = b"stake_pool";
+    pub const STAKE_SEED: &[u8] = b"stake";
+    pub const FAIRNESS_PROOF_SEED: &[u8] = b"fairness_proof";
+    pub const PLAYER_ACCESS_SEED: &[u8] = b"player_access";
+    pub const TIP_VAULT_SEED: &[u8] = b"tip_vault";
+    pub const POT_GAME_SEED: &[u8] = b"pot_game";
+    pub const EMERGENCY_DRAIN_SEED: &[u8] = b"emergency_drain";
+    pub const REWARD_AUTHORITY_SEED: &[u8] = b"reward_authority";
+
+    pub fn treasury_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[TREASURY_SEED], &crate::ID)
+    }
+
+    pub fn game_pda(player: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[GAME_SEED, player.as_ref()], &crate::ID)
+    }
+
+    pub fn config_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[CONFIG_SEED], &crate::ID)
+    }
+
+    pub fn promo_pda(code_id: &[u8; 16]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[PROMO_SEED, code_id], &crate::ID)
+    }
+
+    pub fn house_stats_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[HOUSE_STATS_SEED], &crate::ID)
+    }
+
+    pub fn fee_split_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[FEE_SPLIT_SEED], &crate::ID)
+    }
+
+    pub fn session_pda(player: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[SESSION_SEED, player.as_ref()], &crate::ID)
+    }
+
+    pub fn pending_claim_pda(player: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[PENDING_CLAIM_SEED, player.as_ref()], &crate::ID)
+    }
+
+    pub fn result_log_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[RESULT_LOG_SEED], &crate::ID)
+    }
+
+    pub fn player_stats_pda(player: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[PLAYER_STATS_SEED, player.as_ref()], &crate::ID)
+    }
+
+    pub fn cold_treasury_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[COLD_TREASURY_SEED], &crate::ID)
+    }
+
+    pub fn stake_pool_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[STAKE_POOL_SEED], &crate::ID)
+    }
+
+    pub fn stake_pda(staker: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[STAKE_SEED, staker.as_ref()], &crate::ID)
+    }
+
+    pub fn fairness_proof_pda(player: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[FAIRNESS_PROOF_SEED, player.as_ref()], &crate::ID)
+    }
+
+    pub fn player_access_pda(player: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[PLAYER_ACCESS_SEED, player.as_ref()], &crate::ID)
+    }
+
+    pub fn tip_vault_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[TIP_VAULT_SEED], &crate::ID)
+    }
+
+    pub fn emergency_drain_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[EMERGENCY_DRAIN_SEED], &crate::ID)
+    }
+
+    pub fn pot_game_pda(computation_offset: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[POT_GAME_SEED, &computation_offset.to_le_bytes()], &crate::ID)
+    }
+
+    /// Mint authority the loyalty reward mint must be created with; it holds no data
+    pub fn reward_authority_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[REWARD_AUTHORITY_SEED], &crate::ID)
+    }
+}
+use seeds::*;
+
+// Economic defaults below are `#[constant]`s, so clients read them from the IDL
+
+/// Version of the program's economics. Bumped on breaking changes, so
+/// clients built against an older one are turned away by `play_chest_game`.
+#[constant]
+pub const PROGRAM_VERSION: u16 = 1;
+
+/// Smallest accepted bet, 0.01 SOL
+#[constant]
+pub const MIN_BET_LAMPORTS: u64 = 10_000_000;
+
+/// Default smallest `fund_treasury` deposit, 0.001 SOL, when
+/// `GameConfig::min_funding` is 0
+#[constant]
+pub const MIN_FUNDING: u64 = 1_000_000;
+
+/// Bounds on `GameConfig::house_edge_bps`, so no config update can set an
+/// edge players couldn't reasonably expect
+#[constant]
+pub const MIN_HOUSE_EDGE_BPS: u16 = 0;
+
+#[constant]
+pub const MAX_HOUSE_EDGE_BPS: u16 = 1_000;
+
+/// Default seconds a game may stay pending before it can be cancelled and
+/// refunded, when `GameConfig::game_timeout_secs` is 0
+#[constant]
+pub const GAME_TIMEOUT_SECS: i64 = 60;
+
+// Seconds before a player may cancel while the MPC computation may still be queued
+pub const GAME_HARD_TIMEOUT_SECS: i64 = 600;
+
+/// Seconds between scheduling an emergency drain and being able to execute
+/// it, 48 hours, so a single leaked key can't empty the treasury unnoticed
+#[constant]
+pub const EMERGENCY_DRAIN_TIMELOCK_SECS: i64 = 48 * 60 * 60;
+
+/// Chest count bounds for a game. A single exact win pays num_chests x the bet.
+#[constant]
+pub const MIN_CHESTS: u8 = 2;
+#[constant]
+pub const MAX_CHESTS: u8 = 5;
+
+/// Fewest chests a near-miss game can use; with three or fewer, every chest is "close enough"
+#[constant]
+pub const NEAR_MISS_MIN_CHESTS: u8 = 4;
+
+/// Longest best-of-N series a player can open
+#[constant]
+pub const MAX_SERIES_ROUNDS: u8 = 9;
+
+// Most beneficiaries a losing bet can be split between
+pub const MAX_FEE_RECIPIENTS: usize = 4;
+
+// Settled games kept in the on-chain `ResultLog` ring buffer
+pub const RESULT_LOG_CAPACITY: usize = 16;
+
+// Loyalty tiers a config can define, see `GameConfig::loyalty_rebate_bps`
+pub const LOYALTY_TIERS: usize = 3;
+
+// Per-window win caps, indexed by chest count; slot 0 holds odds games
+pub const WIN_CAP_SLOTS: usize = 9;
+
+// Draw ranges of the hidden and odds circuits, which don't depend on the game
+pub const HIDDEN_DRAW_RANGE: u16 = 60;
+pub const ODDS_DRAW_RANGE: u16 = 10_000;
+
+// Layout and rules `FairnessProof::verify` checks against
+pub const FAIRNESS_PROOF_VERSION: u8 = 4;
+
+// Treasuries a kept losing bet is routed across: 0 = hot, 1 = cold
+pub const TREASURY_POOLS: usize = 2;
+
+// Layout of `PlayerStats` and the `PlayerStatsView` that `get_stats` returns
+pub const PLAYER_STATS_VERSION: u8 = 1;
+
+// Players in a shared-pot game; the circuit always takes MAX_POT_PLAYERS choices
+pub const MIN_POT_PLAYERS: usize = 2;
+pub const MAX_POT_PLAYERS: usize = 3;
+
+// Ceiling on `GameConfig::keeper_fee_bps`, so a keeper claim never takes much of a win
+pub const MAX_KEEPER_FEE_BPS: u16 = 500;
+
+// Ceiling on `GameConfig::min_config_update_interval`, so a bad value can't lock the config for good
+pub const MAX_CONFIG_UPDATE_INTERVAL_SECS: i64 = 7 * 24 * 60 * 60;
+
+// Ceiling on `GameConfig::first_game_bonus_bps`, a welcome nudge rather than free money
+pub const MAX_FIRST_GAME_BONUS_BPS: u16 = 1_000;
+
+// Ceiling on a signed promo's `bonus_bps`, so `max_exposure` has a bound to plan for
+pub const MAX_PROMO_BONUS_BPS: u16 = 10_000;
+
+// Fixed-point scale of `StakePool::profit_per_share`
+pub const PROFIT_PRECISION: u128 = 1_000_000_000_000;
+
+/// Odds game win probability bounds, in basis points. The floor keeps the fair
+/// multiplier at or under `MAX_CHESTS`, no more than a single chest round pays.
+#[constant]
+pub const MIN_WIN_PROBABILITY_BPS: u16 = 10_000 / MAX_CHESTS as u16;
+#[constant]
+pub const MAX_WIN_PROBABILITY_BPS: u16 = 9_999;
 
 declare_id!("BK7k8VuAAZ5Cw9MQNuGT4D7d6ampq3BFGrkdPwAaVfES");
 
@@ -34,16 +255,177 @@ pub mod veiled_chests {
         Ok(())
     }
 
+    /// Initialize the computation definition for play_hidden_chest_game
+    pub fn init_play_hidden_chest_game_comp_def(
+        ctx: Context<InitPlayHiddenChestGameCompDef>,
+    ) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: "https://raw.githubusercontent.com/0xPhantasm/Alloy/main/build/play_hidden_chest_game.arcis".to_string(),
+                hash: circuit_hash!("play_hidden_chest_game"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for play_odds_game
+    pub fn init_play_odds_game_comp_def(ctx: Context<InitPlayOddsGameCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: "https://raw.githubusercontent.com/0xPhantasm/Alloy/main/build/play_odds_game.arcis".to_string(),
+                hash: circuit_hash!("play_odds_game"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
+    /// Initialize the computation definition for play_pot_game
+    pub fn init_play_pot_game_comp_def(ctx: Context<InitPlayPotGameCompDef>) -> Result<()> {
+        init_comp_def(
+            ctx.accounts,
+            Some(CircuitSource::OffChain(OffChainCircuitSource {
+                source: "https://raw.githubusercontent.com/0xPhantasm/Alloy/main/build/play_pot_game.arcis".to_string(),
+                hash: circuit_hash!("play_pot_game"),
+            })),
+            None,
+        )?;
+        Ok(())
+    }
+
     /// Initialize the treasury PDA (only needs to be called once)
     pub fn init_treasury(ctx: Context<InitTreasury>) -> Result<()> {
+        // `init_if_needed` lets a second call reach this check instead of
+        // failing with the system program's generic "already in use"
+        require_keys_eq!(
+            ctx.accounts.treasury.authority,
+            Pubkey::default(),
+            ErrorCode::TreasuryAlreadyInitialized
+        );
         ctx.accounts.treasury.bump = ctx.bumps.treasury;
         ctx.accounts.treasury.authority = ctx.accounts.authority.key();
+        ctx.accounts.treasury.created_at = Clock::get()?.unix_timestamp;
         msg!("Treasury initialized with authority: {}", ctx.accounts.authority.key());
         Ok(())
     }
 
+    /// Initialize the game config PDA (treasury authority only)
+    pub fn init_config(ctx: Context<InitConfig>, params: ConfigParams) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require_keys_eq!(config.authority, Pubkey::default(), ErrorCode::ConfigAlreadyInitialized);
+        config.authority = ctx.accounts.authority.key();
+        config.bump = ctx.bumps.config;
+        params.validate()?;
+        config.apply(&params);
+        config.last_config_update = Clock::get()?.unix_timestamp;
+        msg!("Config initialized: max_payout={}", config.max_payout);
+        Ok(())
+    }
+
+    /// Update the game config (config authority only)
+    pub fn update_config(ctx: Context<UpdateConfig>, params: ConfigParams) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        params.validate()?;
+        config.record_update(Clock::get()?.unix_timestamp)?;
+        config.apply(&params);
+
+        // Refuse limits the current bankroll could not pay out even once
+        config.validate_solvency(treasury_liquid_lamports(&ctx.accounts.treasury)?)?;
+
+        msg!("Config updated: max_payout={}", config.max_payout);
+        Ok(())
+    }
+
+    /// Turn payout safe mode on or off (config authority only). Kept out of
+    /// `update_config` so a freeze is never held back by `min_config_update_interval`.
+    pub fn set_payouts_frozen(ctx: Context<SetPayoutsFrozen>, frozen: bool) -> Result<()> {
+        ctx.accounts.config.payouts_frozen = frozen;
+
+        emit!(PayoutsFrozenEvent {
+            authority: ctx.accounts.authority.key(),
+            frozen,
+        });
+
+        msg!("Payouts frozen: {}", frozen);
+        Ok(())
+    }
+
+    /// Block or unblock a wallet from starting new games (config authority
+    /// only). A blocked player's pending games still settle.
+    pub fn set_player_blocked(ctx: Context<SetPlayerAccess>, player: Pubkey, blocked: bool) -> Result<()> {
+        let access = &mut ctx.accounts.player_access;
+        access.player = player;
+        access.bump = ctx.bumps.player_access;
+        access.blocked = blocked;
+
+        emit!(PlayerAccessChangedEvent {
+            player,
+            blocked: access.blocked,
+            allowed: access.allowed,
+        });
+        msg!("Player {} blocked={}", player, blocked);
+        Ok(())
+    }
+
+    /// Add or remove a wallet from the allowlist that `allowlist_only` mode
+    /// plays against (config authority only)
+    pub fn set_player_allowed(ctx: Context<SetPlayerAccess>, player: Pubkey, allowed: bool) -> Result<()> {
+        let access = &mut ctx.accounts.player_access;
+        access.player = player;
+        access.bump = ctx.bumps.player_access;
+        access.allowed = allowed;
+
+        emit!(PlayerAccessChangedEvent {
+            player,
+            blocked: access.blocked,
+            allowed: access.allowed,
+        });
+        msg!("Player {} allowed={}", player, allowed);
+        Ok(())
+    }
+
+    /// Initialize the fee split PDA with no beneficiaries (config authority only)
+    pub fn init_fee_split(ctx: Context<InitFeeSplit>) -> Result<()> {
+        ctx.accounts.fee_split.bump = ctx.bumps.fee_split;
+        msg!("Fee split initialized");
+        Ok(())
+    }
+
+    /// Replace the beneficiaries taking a share of every losing bet (config authority only)
+    pub fn update_fee_split(ctx: Context<UpdateFeeSplit>, shares: Vec<FeeShare>) -> Result<()> {
+        FeeSplit::validate(&shares)?;
+        ctx.accounts.fee_split.shares = shares;
+        msg!("Fee split updated: {} recipients", ctx.accounts.fee_split.shares.len());
+        Ok(())
+    }
+
+    /// Initialize the global house stats PDA (treasury authority only)
+    pub fn init_house_stats(ctx: Context<InitHouseStats>) -> Result<()> {
+        ctx.accounts.house_stats.bump = ctx.bumps.house_stats;
+        msg!("House stats initialized");
+        Ok(())
+    }
+
+    /// Initialize the ring buffer of recent results (treasury authority only)
+    pub fn init_result_log(ctx: Context<InitResultLog>) -> Result<()> {
+        ctx.accounts.result_log.load_init()?.bump = ctx.bumps.result_log;
+        msg!("Result log initialized");
+        Ok(())
+    }
+
+    /// Empty the result log (config authority only)
+    pub fn clear_result_log(ctx: Context<ClearResultLog>) -> Result<()> {
+        ctx.accounts.result_log.load_mut()?.clear();
+        msg!("Result log cleared");
+        Ok(())
+    }
+
     /// Fund the treasury with SOL
     pub fn fund_treasury(ctx: Context<FundTreasury>, amount: u64) -> Result<()> {
+        ctx.accounts.config.validate_funding(amount)?;
         system_program::transfer(
             CpiContext::new(
                 ctx.accounts.system_program.to_account_info(),
@@ -54,442 +436,6673 @@ pub mod veiled_chests {
             ),
             amount,
         )?;
+
+        emit!(TreasuryFundedEvent {
+            funder: ctx.accounts.funder.key(),
+            amount,
+            new_balance: ctx.accounts.treasury.to_account_info().lamports(),
+        });
+
         msg!("Treasury funded with {} lamports", amount);
         Ok(())
     }
 
-    /// Play the chest game - player picks a chest and places a bet
-    pub fn play_chest_game(
-        ctx: Context<PlayChestGame>,
-        computation_offset: u64,
-        num_chests: u8,           // 2-5 chests
-        bet_amount: u64,          // Bet in lamports
-        player_choice: [u8; 32],  // Encrypted chest choice
-        pub_key: [u8; 32],        // Player's encryption pubkey
-        nonce: u128,              // Encryption nonce
-    ) -> Result<()> {
-        // Validate num_chests
-        require!(num_chests >= 2 && num_chests <= 5, ErrorCode::InvalidChestCount);
-        
-        // Validate bet amount (minimum 0.01 SOL = 10_000_000 lamports)
-        require!(bet_amount >= 10_000_000, ErrorCode::BetTooSmall);
-
-        // Get game account info early to avoid borrow issues
-        let game_account_key = ctx.accounts.game_account.key();
-        let treasury_key = ctx.accounts.treasury.key();
-        let player_key = ctx.accounts.player.key();
+    /// Sweep the hot treasury's balance above `hot_ceiling` into the cold
+    /// treasury PDA. Refuses to run while the hot balance is below `hot_floor`;
+    /// top it up with `fund_treasury` or `refill_treasury` first.
+    pub fn rebalance_treasury(ctx: Context<RebalanceTreasury>) -> Result<()> {
+        ctx.accounts.cold_treasury.bump = ctx.bumps.cold_treasury;
 
-        // Check if player already has an active game
-        {
-            let game = &ctx.accounts.game_account;
-            require!(
-                game.status == GameStatus::None as u8 
-                    || game.status == GameStatus::Completed as u8 
-                    || game.status == GameStatus::Cancelled as u8, 
-                ErrorCode::GameAlreadyActive
-            );
+        let treasury_info = ctx.accounts.treasury.to_account_info();
+        let swept = ctx.accounts.config.hot_excess(treasury_available_lamports(&treasury_info)?)?;
+        if swept > 0 {
+            transfer_lamports(&treasury_info, &ctx.accounts.cold_treasury.to_account_info(), swept)?;
         }
 
-        // Transfer bet from player to game account (held until result)
-        system_program::transfer(
-            CpiContext::new(
-                ctx.accounts.system_program.to_account_info(),
-                system_program::Transfer {
-                    from: ctx.accounts.player.to_account_info(),
-                    to: ctx.accounts.game_account.to_account_info(),
-                },
-            ),
-            bet_amount,
-        )?;
-
-        // Store game state
-        let game = &mut ctx.accounts.game_account;
-        game.player = player_key;
-        game.bet_amount = bet_amount;
-        game.num_chests = num_chests;
-        game.status = GameStatus::Pending as u8;
-        game.created_at = Clock::get()?.unix_timestamp;
-        game.computation_offset = computation_offset;
-        game.bump = ctx.bumps.game_account;
+        emit!(TreasuryRebalancedEvent {
+            swept,
+            hot_balance: treasury_info.lamports(),
+            cold_balance: ctx.accounts.cold_treasury.to_account_info().lamports(),
+        });
 
-        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        msg!("Treasury rebalanced, {} lamports swept to cold storage", swept);
+        Ok(())
+    }
 
-        // Build arguments for MPC computation using ArgBuilder (v0.5.1)
-        let args = ArgBuilder::new()
-            .x25519_pubkey(pub_key)
-            .plaintext_u128(nonce)
-            .encrypted_u8(player_choice)  // Encrypted player choice
-            .plaintext_u8(num_chests)     // Plaintext num_chests
-            .build();
+    /// Move `amount` lamports from the cold treasury back to the hot one
+    pub fn refill_treasury(ctx: Context<RefillTreasury>, amount: u64) -> Result<()> {
+        let cold_info = ctx.accounts.cold_treasury.to_account_info();
+        transfer_lamports(&cold_info, &ctx.accounts.treasury.to_account_info(), amount)?;
+        require_rent_exempt(&cold_info)?;
 
-        // Queue the MPC computation (v0.7.0 - callback_url removed)
-        queue_computation(
-            ctx.accounts, 
-            computation_offset, 
-            args, 
-            vec![PlayChestGameCallback::callback_ix(
-                computation_offset, 
-                &ctx.accounts.mxe_account, 
-                &[
-                    CallbackAccount {
-                        pubkey: game_account_key,
-                        is_writable: true,
-                    },
-                    CallbackAccount {
-                        pubkey: treasury_key,
-                        is_writable: true,
-                    },
-                    CallbackAccount {
-                        pubkey: player_key,
-                        is_writable: true,
-                    },
-                ]
-            )?], 
-            1,
-            0, // cu_price_micro
-        )?;
+        emit!(TreasuryFundedEvent {
+            funder: ctx.accounts.cold_treasury.key(),
+            amount,
+            new_balance: ctx.accounts.treasury.to_account_info().lamports(),
+        });
 
-        msg!("Game started: {} chests, {} lamports bet", num_chests, bet_amount);
+        msg!("Treasury refilled with {} lamports from cold storage", amount);
         Ok(())
     }
 
-    /// Callback from MPC computation with result
-    #[arcium_callback(encrypted_ix = "play_chest_game")]
-    pub fn play_chest_game_callback(
-        ctx: Context<PlayChestGameCallback>,
-        output: SignedComputationOutputs<PlayChestGameOutput>,
-    ) -> Result<()> {
-        // Verify BLS signature on output (v0.5.1 - takes 2 args)
-        // The circuit returns (bool, u8) which becomes PlayChestGameOutput { field_0: PlayChestGameOutputStruct0 { field_0: bool, field_1: u8 } }
-        let (player_won, winning_chest) = match output.verify_output(
-            &ctx.accounts.cluster_account,
-            &ctx.accounts.computation_account,
-        ) {
-            Ok(PlayChestGameOutput { 
-                field_0: PlayChestGameOutputStruct0 { field_0: won, field_1: chest }
-            }) => (won, chest),
-            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
-        };
+    /// Hold `reserve` lamports of the treasury back from covering new games
+    /// (treasury authority only). Like `update_config`, refuses a reserve that
+    /// would leave the config's worst-case game uncovered.
+    pub fn set_reserve(ctx: Context<SetReserve>, reserve: u64) -> Result<()> {
+        ctx.accounts.treasury.reserve = reserve;
+        let liquid = treasury_liquid_lamports(&ctx.accounts.treasury)?;
+        ctx.accounts.config.validate_solvency(liquid)?;
 
-        let game = &ctx.accounts.game_account;
-        require!(game.status == GameStatus::Pending as u8, ErrorCode::GameNotPending);
+        emit!(TreasuryReserveSetEvent {
+            authority: ctx.accounts.authority.key(),
+            reserve,
+            liquid,
+        });
 
-        let bet_amount = game.bet_amount;
-        let num_chests = game.num_chests;
-        let player_key = game.player;
+        msg!("Treasury reserve set to {} lamports, {} liquid", reserve, liquid);
+        Ok(())
+    }
 
-        if player_won {
-            // Player won! Calculate payout: bet * multiplier
-            // Multiplier equals number of chests
-            let payout = bet_amount.checked_mul(num_chests as u64)
-                .ok_or(ErrorCode::Overflow)?;
+    /// Move `amount` lamports of player tips from the tip vault to the treasury authority
+    pub fn withdraw_tips(ctx: Context<WithdrawTips>, amount: u64) -> Result<()> {
+        let vault_info = ctx.accounts.tip_vault.to_account_info();
+        transfer_lamports(&vault_info, &ctx.accounts.authority.to_account_info(), amount)?;
+        require_rent_exempt(&vault_info)?;
 
-            // First return the original bet from game account
-            **ctx.accounts.game_account.to_account_info().try_borrow_mut_lamports()? -= bet_amount;
-            **ctx.accounts.player.to_account_info().try_borrow_mut_lamports()? += bet_amount;
+        emit!(TipsWithdrawnEvent {
+            authority: ctx.accounts.authority.key(),
+            amount,
+            remaining: vault_info.lamports(),
+        });
 
-            // Then pay winnings from treasury (payout - bet = net winnings)
-            let winnings = payout.checked_sub(bet_amount).ok_or(ErrorCode::Overflow)?;
-            if winnings > 0 {
-                **ctx.accounts.treasury.to_account_info().try_borrow_mut_lamports()? -= winnings;
-                **ctx.accounts.player.to_account_info().try_borrow_mut_lamports()? += winnings;
-            }
+        msg!("Withdrew {} lamports of tips", amount);
+        Ok(())
+    }
 
-            // Update game status
-            ctx.accounts.game_account.status = GameStatus::Completed as u8;
-            
-            emit!(GameResultEvent {
-                player: player_key,
-                player_won: true,
-                winning_chest,
-                num_chests,
-                bet_amount,
-                payout,
-            });
+    /// Kill switch, step one: record `destination` as where the whole
+    /// bankroll goes once EMERGENCY_DRAIN_TIMELOCK_SECS have passed.
+    /// Scheduling again replaces the destination and restarts the timelock.
+    pub fn schedule_emergency_drain(ctx: Context<ScheduleEmergencyDrain>, destination: Pubkey) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let drain = &mut ctx.accounts.emergency_drain;
+        drain.destination = destination;
+        drain.scheduled_at = now;
+        drain.executable_at = now.checked_add(EMERGENCY_DRAIN_TIMELOCK_SECS).ok_or(ErrorCode::Overflow)?;
+        drain.bump = ctx.bumps.emergency_drain;
 
-            msg!("Player WON! Chest {} was correct. Paid out {} lamports", winning_chest, payout);
-        } else {
-            // Player lost - bet goes to treasury
-            **ctx.accounts.game_account.to_account_info().try_borrow_mut_lamports()? -= bet_amount;
-            **ctx.accounts.treasury.to_account_info().try_borrow_mut_lamports()? += bet_amount;
+        emit!(EmergencyDrainScheduledEvent {
+            authority: ctx.accounts.authority.key(),
+            destination,
+            executable_at: drain.executable_at,
+        });
 
-            // Update game status
-            ctx.accounts.game_account.status = GameStatus::Completed as u8;
+        msg!("Emergency drain to {} scheduled for {}", destination, drain.executable_at);
+        Ok(())
+    }
 
-            emit!(GameResultEvent {
-                player: player_key,
-                player_won: false,
-                winning_chest,
-                num_chests,
-                bet_amount,
-                payout: 0,
-            });
+    /// Kill switch, step two: once the timelock has passed, move everything
+    /// the hot and cold treasuries hold above rent to the scheduled destination
+    pub fn execute_emergency_drain(ctx: Context<ExecuteEmergencyDrain>) -> Result<()> {
+        ctx.accounts.emergency_drain.require_executable(Clock::get()?.unix_timestamp)?;
 
-            msg!("Player lost. Winning chest was {}. Bet kept by treasury.", winning_chest);
-        }
+        let destination = ctx.accounts.destination.to_account_info();
+        let treasury_info = ctx.accounts.treasury.to_account_info();
+        let hot = treasury_available_lamports(&treasury_info)?;
+        transfer_lamports(&treasury_info, &destination, hot)?;
+        let cold = match ctx.accounts.cold_treasury.as_ref() {
+            Some(cold_treasury) => {
+                let cold_info = cold_treasury.to_account_info();
+                let cold = treasury_available_lamports(&cold_info)?;
+                transfer_lamports(&cold_info, &destination, cold)?;
+                cold
+            }
+            None => 0,
+        };
 
+        let amount = hot.checked_add(cold).ok_or(ErrorCode::Overflow)?;
+        emit!(EmergencyDrainExecutedEvent {
+            authority: ctx.accounts.authority.key(),
+            destination: destination.key(),
+            amount,
+        });
+
+        msg!("Emergency drain moved {} lamports to {}", amount, destination.key());
         Ok(())
     }
 
-    /// Cancel a game and refund the player (for timeouts or failures)
-    pub fn cancel_game(ctx: Context<CancelGame>) -> Result<()> {
-        let game = &ctx.accounts.game_account;
-        
-        // Only allow cancellation of pending games
-        require!(game.status == GameStatus::Pending as u8, ErrorCode::GameNotPending);
-        
-        // Only allow cancellation after timeout (60 seconds)
-        let current_time = Clock::get()?.unix_timestamp;
-        require!(current_time - game.created_at > 60, ErrorCode::GameNotTimedOut);
+    /// Stake SOL into the treasury bankroll for a pro-rata share of the
+    /// profits paid out with `distribute_profits`
+    pub fn stake(ctx: Context<StakeTreasury>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidStakeAmount);
+        let pool = &mut ctx.accounts.stake_pool;
+        pool.bump = ctx.bumps.stake_pool;
+        let stake = &mut ctx.accounts.stake;
+        stake.staker = ctx.accounts.staker.key();
+        stake.bump = ctx.bumps.stake;
+        stake.accrue(pool)?;
 
-        // Refund the bet to player
-        let bet_amount = game.bet_amount;
-        let player_key = game.player;
-        
-        **ctx.accounts.game_account.to_account_info().try_borrow_mut_lamports()? -= bet_amount;
-        **ctx.accounts.player.to_account_info().try_borrow_mut_lamports()? += bet_amount;
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.staker.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
 
-        ctx.accounts.game_account.status = GameStatus::Cancelled as u8;
+        let pool = &mut ctx.accounts.stake_pool;
+        let stake = &mut ctx.accounts.stake;
+        stake.amount = stake.amount.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        pool.total_staked = pool.total_staked.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        stake.reset_profit_debt(pool)?;
 
-        emit!(GameCancelledEvent {
-            player: player_key,
-            bet_amount,
+        emit!(StakedEvent {
+            staker: stake.staker,
+            amount,
+            total_staked: pool.total_staked,
         });
 
-        msg!("Game cancelled, {} lamports refunded", bet_amount);
+        msg!("Staked {} lamports, {} in the pool", amount, pool.total_staked);
         Ok(())
     }
-}
 
-// ============= Account Structs =============
+    /// Withdraw `amount` of the stake from the treasury together with all
+    /// profit accrued so far. An `amount` of 0 only claims the profit.
+    pub fn unstake(ctx: Context<UnstakeTreasury>, amount: u64) -> Result<()> {
+        let pool = &mut ctx.accounts.stake_pool;
+        let stake = &mut ctx.accounts.stake;
+        require!(amount <= stake.amount, ErrorCode::InvalidStakeAmount);
+        stake.accrue(pool)?;
+        let profit = stake.unclaimed_profit;
 
-#[account]
-pub struct Treasury {
-    pub authority: Pubkey,
-    pub bump: u8,
-}
+        stake.amount = stake.amount.checked_sub(amount).ok_or(ErrorCode::Overflow)?;
+        stake.unclaimed_profit = 0;
+        pool.total_staked = pool.total_staked.checked_sub(amount).ok_or(ErrorCode::Overflow)?;
+        stake.reset_profit_debt(pool)?;
 
-#[account]
-pub struct GameAccount {
-    pub player: Pubkey,
-    pub bet_amount: u64,
-    pub num_chests: u8,
-    pub status: u8,
-    pub created_at: i64,
-    pub computation_offset: u64,
-    pub bump: u8,
-}
+        let pool_info = ctx.accounts.stake_pool.to_account_info();
+        let staker_info = ctx.accounts.staker.to_account_info();
+        if amount > 0 {
+            let treasury_info = ctx.accounts.treasury.to_account_info();
+            require!(
+                treasury_available_lamports(&treasury_info)? >= amount,
+                ErrorCode::InsufficientTreasury
+            );
+            transfer_lamports(&treasury_info, &staker_info, amount)?;
+        }
+        if profit > 0 {
+            transfer_lamports(&pool_info, &staker_info, profit)?;
+            require_rent_exempt(&pool_info)?;
+        }
 
-#[repr(u8)]
-#[derive(Clone, Copy, PartialEq)]
-pub enum GameStatus {
-    None = 0,
-    Pending = 1,
-    Completed = 2,
-    Cancelled = 3,
-}
+        emit!(UnstakedEvent {
+            staker: ctx.accounts.staker.key(),
+            amount,
+            profit,
+            total_staked: ctx.accounts.stake_pool.total_staked,
+        });
 
-// Space: 32 (player) + 8 (bet) + 1 (chests) + 1 (status) + 8 (created) + 8 (offset) + 1 (bump) + 8 (discriminator) = 67
-impl GameAccount {
-    pub const SPACE: usize = 8 + 32 + 8 + 1 + 1 + 8 + 8 + 1;
-}
+        msg!("Unstaked {} lamports with {} lamports of profit", amount, profit);
+        Ok(())
+    }
 
-impl Treasury {
-    pub const SPACE: usize = 8 + 32 + 1;
-}
+    /// Move `amount` lamports of house profit from the treasury to the stake
+    /// pool, split pro rata over the current stakes (treasury authority only).
+    /// The treasury must keep at least the staked total above rent.
+    pub fn distribute_profits(ctx: Context<DistributeProfits>, amount: u64) -> Result<()> {
+        let treasury_info = ctx.accounts.treasury.to_account_info();
+        let pool = &mut ctx.accounts.stake_pool;
+        require!(
+            treasury_available_lamports(&treasury_info)?.saturating_sub(amount) >= pool.total_staked,
+            ErrorCode::InsufficientTreasury
+        );
+        pool.distribute(amount)?;
 
-// ============= Context Structs =============
+        transfer_lamports(&treasury_info, &ctx.accounts.stake_pool.to_account_info(), amount)?;
 
-#[derive(Accounts)]
-pub struct InitTreasury<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    #[account(
-        init,
-        payer = authority,
-        space = Treasury::SPACE,
-        seeds = [TREASURY_SEED],
-        bump,
-    )]
-    pub treasury: Account<'info, Treasury>,
-    pub system_program: Program<'info, System>,
-}
+        emit!(ProfitsDistributedEvent {
+            amount,
+            total_staked: ctx.accounts.stake_pool.total_staked,
+        });
 
-#[derive(Accounts)]
-pub struct FundTreasury<'info> {
-    #[account(mut)]
-    pub funder: Signer<'info>,
-    #[account(
-        mut,
-        seeds = [TREASURY_SEED],
-        bump = treasury.bump,
-    )]
-    pub treasury: Account<'info, Treasury>,
-    pub system_program: Program<'info, System>,
-}
+        msg!("Distributed {} lamports of profit to stakers", amount);
+        Ok(())
+    }
 
-#[queue_computation_accounts("play_chest_game", player)]
-#[derive(Accounts)]
-#[instruction(computation_offset: u64, num_chests: u8, bet_amount: u64)]
-pub struct PlayChestGame<'info> {
-    #[account(mut)]
-    pub player: Signer<'info>,
-    
-    #[account(
-        init_if_needed,
-        payer = player,
-        space = GameAccount::SPACE,
-        seeds = [GAME_SEED, player.key().as_ref()],
-        bump,
-    )]
-    pub game_account: Box<Account<'info, GameAccount>>,
+    /// Preview a single-round game without touching any state. Clients call
+    /// this through simulation to read the returned `GameQuote`.
+    pub fn quote_game(ctx: Context<QuoteGame>, num_chests: u8, bet_amount: u64) -> Result<GameQuote> {
+        validate_chest_count(num_chests)?;
+        require!(bet_amount >= MIN_BET_LAMPORTS, ErrorCode::BetTooSmall);
+        ctx.accounts.config.validate_bet_cap(bet_amount)?;
 
-    #[account(
-        mut,
-        seeds = [TREASURY_SEED],
-        bump = treasury.bump,
-    )]
-    pub treasury: Box<Account<'info, Treasury>>,
+        let config = &ctx.accounts.config;
+        let payout = config.payout_for(bet_amount, num_chests, WinMode::Exact as u8, 1, 0)?;
+        let worst_case_winnings = config
+            .worst_case_payout_for(bet_amount, num_chests, WinMode::Exact as u8, 1, 0)?
+            .saturating_sub(bet_amount);
+        let available = treasury_liquid_lamports(&ctx.accounts.treasury)?;
 
-    #[account(
-        init_if_needed,
-        space = 9,
-        payer = player,
-        seeds = [b"ArciumSignerAccount"],
-        bump,
-        address = derive_sign_pda!(),
-    )]
-    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
-    
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Box<Account<'info, MXEAccount>>,
-    
-    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: mempool_account, checked by the arcium program.
-    pub mempool_account: UncheckedAccount<'info>,
-    
-    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: executing_pool, checked by the arcium program.
-    pub executing_pool: UncheckedAccount<'info>,
-    
-    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
-    /// CHECK: computation_account, checked by the arcium program.
-    pub computation_account: UncheckedAccount<'info>,
-    
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLAY_CHEST_GAME))]
-    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
-    
-    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Box<Account<'info, Cluster>>,
-    
-    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
-    pub pool_account: Box<Account<'info, FeePool>>,
-    
-    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
-    pub clock_account: Box<Account<'info, ClockAccount>>,
-    
-    pub system_program: Program<'info, System>,
-    pub arcium_program: Program<'info, Arcium>,
-}
+        Ok(GameQuote {
+            max_payout: payout,
+            entry_fee: config.entry_fee,
+            net_winnings: payout.saturating_sub(bet_amount).saturating_sub(config.entry_fee),
+            treasury_can_cover: available >= worst_case_winnings,
+        })
+    }
 
-#[callback_accounts("play_chest_game")]
-#[derive(Accounts)]
-pub struct PlayChestGameCallback<'info> {
-    pub arcium_program: Program<'info, Arcium>,
-    
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLAY_CHEST_GAME))]
-    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
-    
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Box<Account<'info, MXEAccount>>,
-    
-    /// CHECK: computation_account, checked by arcium program via constraints in the callback context.
-    pub computation_account: UncheckedAccount<'info>,
-    
-    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Box<Account<'info, Cluster>>,
-    
-    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
-    /// CHECK: instructions_sysvar, checked by the account constraint
-    pub instructions_sysvar: AccountInfo<'info>,
+    /// Theoretical return to player of a single exact-chest round, in basis
+    /// points of the bet, net of the config's house edge and its rake cap
+    /// (pass a zero bet to leave the cap out). Read-only, call it through
+    /// simulation.
+    pub fn rtp(ctx: Context<QuoteRtp>, num_chests: u8, bet_amount: u64) -> Result<u16> {
+        validate_chest_count(num_chests)?;
+        Ok(ctx.accounts.config.rtp_bps(num_chests, bet_amount))
+    }
 
-    // Custom accounts passed via CallbackAccount
-    #[account(mut)]
-    pub game_account: Box<Account<'info, GameAccount>>,
+    /// A player's lifetime stats as a versioned `PlayerStatsView`. Read-only,
+    /// call it through simulation.
+    pub fn get_stats(ctx: Context<GetStats>) -> Result<PlayerStatsView> {
+        Ok(ctx.accounts.player_stats.view())
+    }
 
-    #[account(mut)]
-    pub treasury: Box<Account<'info, Treasury>>,
+    /// A player's game and stats together with the config, read in one
+    /// simulation so they can't drift apart between fetches. Game and stats
+    /// are None until the player's first game. Read-only.
+    pub fn get_full_state(ctx: Context<GetFullState>) -> Result<FullStateView> {
+        Ok(FullStateView {
+            game: ctx.accounts.game_account.as_deref().cloned(),
+            stats: ctx.accounts.player_stats.as_ref().map(|stats| stats.view()),
+            config: (*ctx.accounts.config).clone(),
+        })
+    }
 
-    /// CHECK: player account for receiving winnings
-    #[account(mut)]
-    pub player: AccountInfo<'info>,
-}
+    /// Play the chest game - player picks a chest and places a bet
+    #[allow(clippy::too_many_arguments)]
+    pub fn play_chest_game(
+        ctx: Context<PlayChestGame>,
+        computation_offset: u64,
+        num_chests: u8,           // 2-5 chests
+        bet_amount: u64,          // Bet in lamports
+        player_choice: [u8; 32],  // Encrypted chest choice
+        pub_key: [u8; 32],        // Player's encryption pubkey
+        nonce: u128,              // Encryption nonce
+        auto_cancel: bool,        // Allow keepers to refund this game once it times out
+        promo_payload: Option<PromoPayload>, // Signed promo code granting a bonus multiplier
+        promo_sig: Option<[u8; 64]>,         // ed25519 signature over the borsh-encoded payload
+        rounds_total: u8,                    // 1 for a single game, odd N for best-of-N
+        idempotency_key: [u8; 16],           // Client retry key; all zeros disables dedupe
+        insurance: bool,                     // Pay a premium to get part of the bet back on a loss
+        metadata: [u8; 32],                  // Opaque caller tag (e.g. tournament id), all zeros for none
+        win_mode: u8,                        // WinMode: 0 = exact chest, 1 = within one chest
+        player_seed: [u8; 32],               // Player entropy mixed into the MPC draw
+        tip: u64,                            // Voluntary tip to the house, never refunded
+        bet_is_bps: bool,                    // Read bet_amount as bps of the player's spendable balance
+        auto_compound: bool,                 // Roll a win into the next round's bet instead of paying it
+        expected_program_version: u16,       // PROGRAM_VERSION the client was built against
+    ) -> Result<()> {
+        validate_program_version(expected_program_version)?;
 
-#[derive(Accounts)]
-pub struct CancelGame<'info> {
-    /// CHECK: player receiving refund
-    #[account(mut)]
-    pub player: AccountInfo<'info>,
+        // A percentage bet is sized against the balance when the transaction
+        // runs, so clients don't have to read it and race other transfers
+        let bet_amount = if bet_is_bps {
+            let player = ctx.accounts.player.to_account_info();
+            let rent_floor = Rent::get()?.minimum_balance(player.data_len());
+            bet_from_balance_bps(player.lamports(), rent_floor, bet_amount, ctx.accounts.config.max_bet)?
+        } else {
+            bet_amount
+        };
 
-    #[account(
-        mut,
-        seeds = [GAME_SEED, player.key().as_ref()],
-        bump = game_account.bump,
-        constraint = game_account.player == player.key() @ ErrorCode::NotGamePlayer,
-    )]
-    pub game_account: Account<'info, GameAccount>,
-}
+        // Validate num_chests
+        validate_chest_count(num_chests)?;
 
-#[init_computation_definition_accounts("play_chest_game", payer)]
-#[derive(Accounts)]
-pub struct InitPlayChestGameCompDef<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
-    pub mxe_account: Box<Account<'info, MXEAccount>>,
-    #[account(mut)]
-    /// CHECK: comp_def_account, checked by arcium program.
-    pub comp_def_account: UncheckedAccount<'info>,
-    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
-    /// CHECK: address_lookup_table, checked by arcium program.
-    pub address_lookup_table: UncheckedAccount<'info>,
-    #[account(address = LUT_PROGRAM_ID)]
-    /// CHECK: lut_program is the Address Lookup Table program.
-    pub lut_program: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
-    pub system_program: Program<'info, System>,
-}
+        // Validate series length: a single round, or an odd best-of-N
+        require!(rounds_total % 2 == 1, ErrorCode::RoundCountEven);
+        require!(rounds_total <= MAX_SERIES_ROUNDS, ErrorCode::RoundCountTooHigh);
+        
+        // Validate bet amount (minimum 0.01 SOL)
+        require!(bet_amount >= MIN_BET_LAMPORTS, ErrorCode::BetTooSmall);
+        ctx.accounts.config.validate_bet_cap(bet_amount)?;
 
-// ============= Events =============
+        // A low-order x25519 key can't have come from a real keypair
+        validate_x25519_pubkey(&pub_key)?;
 
-#[event]
-pub struct GameResultEvent {
-    pub player: Pubkey,
-    pub player_won: bool,
-    pub winning_chest: u8,
-    pub num_chests: u8,
-    pub bet_amount: u64,
-    pub payout: u64,
-}
+        let config = &ctx.accounts.config;
+        config.validate_rng_range(config.chest_draw_range(num_chests) as u16)?;
+        validate_comp_def_ready(&ctx.accounts.comp_def_account)?;
+        validate_win_mode(win_mode, num_chests)?;
 
-#[event]
-pub struct GameCancelledEvent {
-    pub player: Pubkey,
-    pub bet_amount: u64,
-}
+        // Get game account info early to avoid borrow issues
+        let game_account_key = ctx.accounts.game_account.key();
+        let treasury_key = ctx.accounts.treasury.key();
+        let player_key = ctx.accounts.player.key();
 
-// ============= Errors =============
+        // A retried submission of the last play is a no-op rather than a second bet
+        if check_new_game(
+            &ctx.accounts.config,
+            &ctx.accounts.treasury,
+            &player_key,
+            &ctx.accounts.player_access,
+            &ctx.accounts.game_account,
+            &idempotency_key,
+        )? {
+            msg!("Duplicate play submission ignored");
+            return Ok(());
+        }
 
-#[error_code]
-pub enum ErrorCode {
-    #[msg("The computation was aborted")]
-    AbortedComputation,
-    #[msg("Cluster not set")]
-    ClusterNotSet,
-    #[msg("Invalid chest count - must be 2-5")]
-    InvalidChestCount,
-    #[msg("Bet amount too small - minimum 0.01 SOL")]
-    BetTooSmall,
+        // Verify and redeem the promo code, if one was supplied
+        let promo_bonus_bps = match (promo_payload, promo_sig) {
+            (None, None) => 0,
+            (Some(payload), Some(sig)) => {
+                let promo_pubkey = ctx.accounts.config.promo_pubkey;
+                require_keys_neq!(promo_pubkey, Pubkey::default(), ErrorCode::PromosDisabled);
+                require!(payload.bonus_bps <= MAX_PROMO_BONUS_BPS, ErrorCode::PromoBonusTooHigh);
+                require_keys_eq!(payload.player, player_key, ErrorCode::PromoWrongPlayer);
+                require!(Clock::get()?.unix_timestamp <= payload.expires_at, ErrorCode::PromoExpired);
+                let message = payload.try_to_vec()?;
+                verify_ed25519_ix(&ctx.accounts.instructions_sysvar, &promo_pubkey, &message, &sig)?;
+                redeem_promo(
+                    ctx.accounts
+                        .promo_redemption
+                        .as_ref()
+                        .ok_or(ErrorCode::PromoRedemptionMissing)?,
+                    &ctx.accounts.player,
+                    &ctx.accounts.system_program,
+                    &payload,
+                )?;
+                payload.bonus_bps
+            }
+            _ => return err!(ErrorCode::PromoIncomplete),
+        };
+
+        let config = &ctx.accounts.config;
+        require!(!insurance || config.insurance_bps > 0, ErrorCode::InsuranceDisabled);
+        let promo_bonus_bps = promo_bonus_bps.saturating_add(config.first_game_bonus_for(&ctx.accounts.player_stats));
+
+        // Make sure the treasury can cover the worst-case (capped) winnings, or
+        // the insured refund on a loss, whichever is larger
+        let worst_case_payout =
+            config.worst_case_payout_for(bet_amount, num_chests, win_mode, rounds_total, promo_bonus_bps)?;
+        let worst_case_winnings = worst_case_payout.saturating_sub(bet_amount);
+        let insured_refund = if insurance { config.insured_refund(bet_amount) } else { 0 };
+
+        let mut fees = config.entry_fee_for(ctx.accounts.player_stats.total_wagered);
+        if insurance {
+            fees = fees.checked_add(config.insurance_premium(bet_amount)).ok_or(ErrorCode::Overflow)?;
+        }
+        let (entropy_slot, slot_hash) = config.draw_slot_hash(&ctx.accounts.slot_hashes)?;
+
+        let stats = &mut ctx.accounts.player_stats;
+        stats.player = player_key;
+        stats.bump = ctx.bumps.player_stats;
+        stats.stats_version = PLAYER_STATS_VERSION;
+        ctx.accounts.fairness_proof.bump = ctx.bumps.fairness_proof;
+        ctx.accounts.tip_vault.bump = ctx.bumps.tip_vault;
+
+        let player_info = ctx.accounts.player.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        start_game(
+            &mut StartAccounts {
+                payer: Payer::Wallet { player: &player_info, system_program: &system_program },
+                game_account: &mut ctx.accounts.game_account,
+                treasury: &mut ctx.accounts.treasury,
+                config: &ctx.accounts.config,
+                house_stats: &mut ctx.accounts.house_stats,
+                player_stats: &mut ctx.accounts.player_stats,
+                tip_vault: &ctx.accounts.tip_vault,
+            },
+            &NewGame {
+                player: player_key,
+                bump: ctx.bumps.game_account,
+                computation_offset,
+                bet_amount,
+                num_chests,
+                auto_cancel,
+                promo_bonus_bps,
+                rounds_total,
+                idempotency_key,
+                insured: insurance,
+                metadata,
+                win_mode,
+                hidden_chests: false,
+                win_probability_bps: 0,
+                player_seed,
+                auto_compound,
+                entropy_slot,
+                slot_hash,
+            },
+            fees,
+            tip,
+            worst_case_winnings.max(insured_refund),
+        )?;
+
+        let claim = &mut ctx.accounts.pending_claim;
+        claim.player = player_key;
+        claim.bump = ctx.bumps.pending_claim;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // Build arguments for MPC computation using ArgBuilder (v0.5.1)
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_u8(player_choice)  // Encrypted player choice
+            .plaintext_u8(num_chests)     // Plaintext num_chests
+            .plaintext_bool(ctx.accounts.config.push_enabled) // Whether a draw can push
+            .plaintext_u8(win_mode)       // Exact or near-miss win condition
+            .plaintext_u64(player_entropy(&player_seed)) // Player's share of the randomness
+            .plaintext_u64(slot_entropy(&slot_hash)) // Recent slot hash's share, 0 when not mixed
+            .plaintext_bool(ctx.accounts.config.reveal_raw_draw) // Also reveal the unshifted draw
+            .build();
+
+        let callback_accounts = CallbackAccounts::for_play_chest_game(
+            game_account_key,
+            treasury_key,
+            player_key,
+            ctx.accounts.config.key(),
+            ctx.accounts.house_stats.key(),
+            &ctx.accounts.fee_split,
+            ctx.accounts.pending_claim.key(),
+            result_log_pda().0,
+            player_stats_pda(&player_key).0,
+            fairness_proof_pda(&player_key).0,
+            &ctx.accounts.config.loss_route_accounts(),
+            &ctx.accounts.config.reward_accounts(&player_key),
+        )?;
+
+        // Queue the MPC computation (v0.7.0 - callback_url removed)
+        queue_computation(
+            ctx.accounts, 
+            computation_offset, 
+            args, 
+            vec![PlayChestGameCallback::callback_ix(
+                computation_offset, 
+                &ctx.accounts.mxe_account, 
+                &callback_accounts,
+            )?], 
+            1,
+            0, // cu_price_micro
+        )?;
+
+        emit!(GameStartedEvent {
+            player: player_key,
+            computation_offset,
+            num_chests,
+            bet_amount,
+            pub_key,
+            nonce,
+            metadata,
+            tip,
+            win_probability_bps: ctx.accounts.config.chest_win_probability_bps(num_chests, win_mode),
+        });
+
+        msg!("Game started: {} chests, {} lamports bet", num_chests, bet_amount);
+        Ok(())
+    }
+
+    /// Callback from MPC computation with result
+    #[arcium_callback(encrypted_ix = "play_chest_game")]
+    pub fn play_chest_game_callback(
+        ctx: Context<PlayChestGameCallback>,
+        output: SignedComputationOutputs<PlayChestGameOutput>,
+    ) -> Result<()> {
+        CallbackAccounts::require_distinct(
+            &ctx.accounts.game_account.key(),
+            &ctx.accounts.treasury.key(),
+            &ctx.accounts.player.key(),
+        )?;
+        CallbackAccounts::require_game_player(&ctx.accounts.player.key(), &ctx.accounts.game_account)?;
+
+        // Verify BLS signature on output (v0.5.1 - takes 2 args)
+        let ChestGameResult { outcome, winning_chest, sampled, num_chests: echoed_chests, raw_draw, draw_revealed } = match output
+            .verify_output(&ctx.accounts.cluster_account, &ctx.accounts.computation_account)
+        {
+            Ok(output) => decode_result(&output),
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let queued_computation = derive_comp_pda!(
+            ctx.accounts.game_account.computation_offset,
+            ctx.accounts.mxe_account,
+            ErrorCode::ClusterNotSet
+        );
+        if is_redelivered_callback(
+            &ctx.accounts.game_account,
+            &ctx.accounts.computation_account.key(),
+            &queued_computation,
+        ) {
+            msg!("Callback already processed for computation {}", ctx.accounts.game_account.computation_offset);
+            return Ok(());
+        }
+        let game = &mut ctx.accounts.game_account;
+        require!(game.status == GameStatus::Pending as u8, ErrorCode::GameNotPending);
+        require_echoed_chest_count(echoed_chests, game.num_chests)?;
+
+        let bet_amount = game.bet_amount;
+
+        // A fallback chest was not drawn fairly, so refund the whole bet instead of settling
+        if !sampled {
+            ctx.accounts.house_stats.record_rng_failure()?;
+            refund_pending_game(
+                &mut ctx.accounts.game_account,
+                &ctx.accounts.player,
+                &mut ctx.accounts.house_stats,
+                &mut ctx.accounts.player_stats,
+            )?;
+            msg!("RNG sampling failed, {} lamports refunded", bet_amount);
+            return Ok(());
+        }
+
+        // The unshifted draw has to reduce to the reported chest
+        if draw_revealed {
+            let game = &ctx.accounts.game_account;
+            let draw_range = ctx.accounts.config.chest_draw_range(game.num_chests);
+            let entropy_offset = game.entropy_offset(draw_range);
+            require!(
+                mix_player_entropy(raw_draw, entropy_offset as u64, draw_range) == winning_chest,
+                ErrorCode::ParameterMismatch
+            );
+            emit!(RawDrawRevealedEvent {
+                player: game.player,
+                raw_draw,
+                entropy_offset,
+                draw_range,
+                winning_chest,
+            });
+        }
+
+        settle_round(
+            &mut SettleAccounts {
+                game_account: &mut ctx.accounts.game_account,
+                treasury: &mut ctx.accounts.treasury,
+                player: &ctx.accounts.player,
+                config: &ctx.accounts.config,
+                house_stats: &mut ctx.accounts.house_stats,
+                fee_split: &ctx.accounts.fee_split,
+                pending_claim: &mut ctx.accounts.pending_claim,
+                result_log: &ctx.accounts.result_log,
+                player_stats: &mut ctx.accounts.player_stats,
+                fairness_proof: &mut ctx.accounts.fairness_proof,
+                remaining_accounts: ctx.remaining_accounts,
+            },
+            outcome,
+            winning_chest,
+        )
+    }
+
+    /// Play a single exact-match game with the chest count encrypted alongside
+    /// the choice, so observers only learn the difficulty at settlement. The
+    /// program can't see the count, so the treasury must cover a win on
+    /// `MAX_CHESTS`, and the circuit does the bounds check.
+    #[allow(clippy::too_many_arguments)]
+    pub fn play_hidden_chest_game(
+        ctx: Context<PlayHiddenChestGame>,
+        computation_offset: u64,
+        bet_amount: u64,
+        encrypted_game: [[u8; 32]; 2], // Encrypted choice, then encrypted chest count
+        pub_key: [u8; 32],
+        nonce: u128,
+        idempotency_key: [u8; 16], // Client retry key; all zeros disables dedupe
+        tip: u64,                  // Voluntary tip to the house, never refunded
+    ) -> Result<()> {
+        require!(bet_amount >= MIN_BET_LAMPORTS, ErrorCode::BetTooSmall);
+        ctx.accounts.config.validate_bet_cap(bet_amount)?;
+        ctx.accounts.config.validate_rng_range(HIDDEN_DRAW_RANGE)?;
+        validate_comp_def_ready(&ctx.accounts.comp_def_account)?;
+        validate_x25519_pubkey(&pub_key)?;
+
+        let game_account_key = ctx.accounts.game_account.key();
+        let treasury_key = ctx.accounts.treasury.key();
+        let player_key = ctx.accounts.player.key();
+
+        if check_new_game(
+            &ctx.accounts.config,
+            &ctx.accounts.treasury,
+            &player_key,
+            &ctx.accounts.player_access,
+            &ctx.accounts.game_account,
+            &idempotency_key,
+        )? {
+            msg!("Duplicate play submission ignored");
+            return Ok(());
+        }
+
+        let config = &ctx.accounts.config;
+        let first_game_bonus_bps = config.first_game_bonus_for(&ctx.accounts.player_stats);
+        let worst_case_winnings = config
+            .worst_case_payout_for(bet_amount, MAX_CHESTS, WinMode::Exact as u8, 1, first_game_bonus_bps)?
+            .saturating_sub(bet_amount);
+        let entry_fee = config.entry_fee_for(ctx.accounts.player_stats.total_wagered);
+
+        let stats = &mut ctx.accounts.player_stats;
+        stats.player = player_key;
+        stats.bump = ctx.bumps.player_stats;
+        stats.stats_version = PLAYER_STATS_VERSION;
+        ctx.accounts.fairness_proof.bump = ctx.bumps.fairness_proof;
+        ctx.accounts.tip_vault.bump = ctx.bumps.tip_vault;
+
+        // The chest count stays 0 until the callback reveals it
+        let player_info = ctx.accounts.player.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        start_game(
+            &mut StartAccounts {
+                payer: Payer::Wallet { player: &player_info, system_program: &system_program },
+                game_account: &mut ctx.accounts.game_account,
+                treasury: &mut ctx.accounts.treasury,
+                config: &ctx.accounts.config,
+                house_stats: &mut ctx.accounts.house_stats,
+                player_stats: &mut ctx.accounts.player_stats,
+                tip_vault: &ctx.accounts.tip_vault,
+            },
+            &NewGame {
+                player: player_key,
+                bump: ctx.bumps.game_account,
+                computation_offset,
+                bet_amount,
+                promo_bonus_bps: first_game_bonus_bps,
+                rounds_total: 1,
+                idempotency_key,
+                hidden_chests: true,
+                ..Default::default()
+            },
+            entry_fee,
+            tip,
+            worst_case_winnings,
+        )?;
+
+        let claim = &mut ctx.accounts.pending_claim;
+        claim.player = player_key;
+        claim.bump = ctx.bumps.pending_claim;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_u8(encrypted_game[0]) // Encrypted player choice
+            .encrypted_u8(encrypted_game[1]) // Encrypted num_chests
+            .plaintext_bool(ctx.accounts.config.push_enabled)
+            .build();
+
+        let callback_accounts = CallbackAccounts::for_play_chest_game(
+            game_account_key,
+            treasury_key,
+            player_key,
+            ctx.accounts.config.key(),
+            ctx.accounts.house_stats.key(),
+            &ctx.accounts.fee_split,
+            ctx.accounts.pending_claim.key(),
+            result_log_pda().0,
+            player_stats_pda(&player_key).0,
+            fairness_proof_pda(&player_key).0,
+            &ctx.accounts.config.loss_route_accounts(),
+            &ctx.accounts.config.reward_accounts(&player_key),
+        )?;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![PlayHiddenChestGameCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &callback_accounts,
+            )?],
+            1,
+            0, // cu_price_micro
+        )?;
+
+        emit!(GameStartedEvent {
+            player: player_key,
+            computation_offset,
+            num_chests: 0,
+            bet_amount,
+            pub_key,
+            nonce,
+            metadata: [0u8; 32],
+            tip,
+            win_probability_bps: 0,
+        });
+
+        msg!("Hidden chest game started: {} lamports bet", bet_amount);
+        Ok(())
+    }
+
+    /// Callback for `play_hidden_chest_game`: records the revealed chest count,
+    /// refunds the bet if the circuit rejected it, and otherwise settles as usual
+    #[arcium_callback(encrypted_ix = "play_hidden_chest_game")]
+    pub fn play_hidden_chest_game_callback(
+        ctx: Context<PlayHiddenChestGameCallback>,
+        output: SignedComputationOutputs<PlayHiddenChestGameOutput>,
+    ) -> Result<()> {
+        CallbackAccounts::require_distinct(
+            &ctx.accounts.game_account.key(),
+            &ctx.accounts.treasury.key(),
+            &ctx.accounts.player.key(),
+        )?;
+        CallbackAccounts::require_game_player(&ctx.accounts.player.key(), &ctx.accounts.game_account)?;
+        let (outcome, winning_chest, sampled, num_chests, valid) = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(PlayHiddenChestGameOutput {
+                field_0:
+                    PlayHiddenChestGameOutputStruct0 {
+                        field_0: outcome,
+                        field_1: chest,
+                        field_2: sampled,
+                        field_3: num_chests,
+                        field_4: valid,
+                    },
+            }) => (outcome, chest, sampled, num_chests, valid),
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let queued_computation = derive_comp_pda!(
+            ctx.accounts.game_account.computation_offset,
+            ctx.accounts.mxe_account,
+            ErrorCode::ClusterNotSet
+        );
+        if is_redelivered_callback(
+            &ctx.accounts.game_account,
+            &ctx.accounts.computation_account.key(),
+            &queued_computation,
+        ) {
+            msg!("Callback already processed for computation {}", ctx.accounts.game_account.computation_offset);
+            return Ok(());
+        }
+        let game = &mut ctx.accounts.game_account;
+        require!(game.status == GameStatus::Pending as u8, ErrorCode::GameNotPending);
+        require!(game.hidden_chests, ErrorCode::ParameterMismatch);
+        let bet_amount = game.bet_amount;
+
+        if !valid || !sampled {
+            if !sampled {
+                ctx.accounts.house_stats.record_rng_failure()?;
+            }
+            refund_pending_game(
+                &mut ctx.accounts.game_account,
+                &ctx.accounts.player,
+                &mut ctx.accounts.house_stats,
+                &mut ctx.accounts.player_stats,
+            )?;
+            msg!("Hidden chest game rejected or unsampled, {} lamports refunded", bet_amount);
+            return Ok(());
+        }
+        game.num_chests = num_chests;
+
+        settle_round(
+            &mut SettleAccounts {
+                game_account: &mut ctx.accounts.game_account,
+                treasury: &mut ctx.accounts.treasury,
+                player: &ctx.accounts.player,
+                config: &ctx.accounts.config,
+                house_stats: &mut ctx.accounts.house_stats,
+                fee_split: &ctx.accounts.fee_split,
+                pending_claim: &mut ctx.accounts.pending_claim,
+                result_log: &ctx.accounts.result_log,
+                player_stats: &mut ctx.accounts.player_stats,
+                fairness_proof: &mut ctx.accounts.fairness_proof,
+                remaining_accounts: ctx.remaining_accounts,
+            },
+            outcome,
+            winning_chest,
+        )
+    }
+
+    /// Play a single game at an arbitrary win probability instead of a chest
+    /// count. The circuit rolls in [0, 10000) against `win_probability_bps` on
+    /// the side the encrypted choice picks, and a win pays the fair multiplier
+    /// 10000 / `win_probability_bps`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn play_odds_game(
+        ctx: Context<PlayOddsGame>,
+        computation_offset: u64,
+        bet_amount: u64,
+        win_probability_bps: u16,
+        player_choice: [u8; 32], // Encrypted side of the roll
+        pub_key: [u8; 32],
+        nonce: u128,
+        idempotency_key: [u8; 16], // Client retry key; all zeros disables dedupe
+        tip: u64,                  // Voluntary tip to the house, never refunded
+    ) -> Result<()> {
+        require!(bet_amount >= MIN_BET_LAMPORTS, ErrorCode::BetTooSmall);
+        validate_win_probability(win_probability_bps)?;
+        ctx.accounts.config.validate_bet_cap(bet_amount)?;
+        ctx.accounts.config.validate_rng_range(ODDS_DRAW_RANGE)?;
+        validate_comp_def_ready(&ctx.accounts.comp_def_account)?;
+        validate_x25519_pubkey(&pub_key)?;
+
+        let game_account_key = ctx.accounts.game_account.key();
+        let treasury_key = ctx.accounts.treasury.key();
+        let player_key = ctx.accounts.player.key();
+
+        if check_new_game(
+            &ctx.accounts.config,
+            &ctx.accounts.treasury,
+            &player_key,
+            &ctx.accounts.player_access,
+            &ctx.accounts.game_account,
+            &idempotency_key,
+        )? {
+            msg!("Duplicate play submission ignored");
+            return Ok(());
+        }
+
+        let config = &ctx.accounts.config;
+        let worst_case_winnings = config
+            .worst_case_odds_payout_for(bet_amount, win_probability_bps)?
+            .saturating_sub(bet_amount);
+        let entry_fee = config.entry_fee_for(ctx.accounts.player_stats.total_wagered);
+
+        let stats = &mut ctx.accounts.player_stats;
+        stats.player = player_key;
+        stats.bump = ctx.bumps.player_stats;
+        stats.stats_version = PLAYER_STATS_VERSION;
+        ctx.accounts.fairness_proof.bump = ctx.bumps.fairness_proof;
+        ctx.accounts.tip_vault.bump = ctx.bumps.tip_vault;
+
+        // No chests: num_chests stays 0 and the probability drives the payout
+        let player_info = ctx.accounts.player.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        start_game(
+            &mut StartAccounts {
+                payer: Payer::Wallet { player: &player_info, system_program: &system_program },
+                game_account: &mut ctx.accounts.game_account,
+                treasury: &mut ctx.accounts.treasury,
+                config: &ctx.accounts.config,
+                house_stats: &mut ctx.accounts.house_stats,
+                player_stats: &mut ctx.accounts.player_stats,
+                tip_vault: &ctx.accounts.tip_vault,
+            },
+            &NewGame {
+                player: player_key,
+                bump: ctx.bumps.game_account,
+                computation_offset,
+                bet_amount,
+                rounds_total: 1,
+                idempotency_key,
+                win_probability_bps,
+                ..Default::default()
+            },
+            entry_fee,
+            tip,
+            worst_case_winnings,
+        )?;
+
+        let claim = &mut ctx.accounts.pending_claim;
+        claim.player = player_key;
+        claim.bump = ctx.bumps.pending_claim;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_u8(player_choice)
+            .plaintext_u16(win_probability_bps)
+            .build();
+
+        let callback_accounts = CallbackAccounts::for_play_chest_game(
+            game_account_key,
+            treasury_key,
+            player_key,
+            ctx.accounts.config.key(),
+            ctx.accounts.house_stats.key(),
+            &ctx.accounts.fee_split,
+            ctx.accounts.pending_claim.key(),
+            result_log_pda().0,
+            player_stats_pda(&player_key).0,
+            fairness_proof_pda(&player_key).0,
+            &ctx.accounts.config.loss_route_accounts(),
+            &ctx.accounts.config.reward_accounts(&player_key),
+        )?;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![PlayOddsGameCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &callback_accounts,
+            )?],
+            1,
+            0, // cu_price_micro
+        )?;
+
+        emit!(GameStartedEvent {
+            player: player_key,
+            computation_offset,
+            num_chests: 0,
+            bet_amount,
+            pub_key,
+            nonce,
+            metadata: [0u8; 32],
+            tip,
+            win_probability_bps,
+        });
+
+        msg!(
+            "Odds game started: {} lamports bet at {} bps",
+            bet_amount,
+            win_probability_bps
+        );
+        Ok(())
+    }
+
+    /// Callback for `play_odds_game`: checks the echoed probability, refunds
+    /// an unsampled roll, and otherwise settles as usual
+    #[arcium_callback(encrypted_ix = "play_odds_game")]
+    pub fn play_odds_game_callback(
+        ctx: Context<PlayOddsGameCallback>,
+        output: SignedComputationOutputs<PlayOddsGameOutput>,
+    ) -> Result<()> {
+        CallbackAccounts::require_distinct(
+            &ctx.accounts.game_account.key(),
+            &ctx.accounts.treasury.key(),
+            &ctx.accounts.player.key(),
+        )?;
+        CallbackAccounts::require_game_player(&ctx.accounts.player.key(), &ctx.accounts.game_account)?;
+        let (outcome, roll, sampled, win_probability_bps) = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(PlayOddsGameOutput {
+                field_0:
+                    PlayOddsGameOutputStruct0 {
+                        field_0: outcome,
+                        field_1: roll,
+                        field_2: sampled,
+                        field_3: win_probability_bps,
+                    },
+            }) => (outcome, roll, sampled, win_probability_bps),
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let queued_computation = derive_comp_pda!(
+            ctx.accounts.game_account.computation_offset,
+            ctx.accounts.mxe_account,
+            ErrorCode::ClusterNotSet
+        );
+        if is_redelivered_callback(
+            &ctx.accounts.game_account,
+            &ctx.accounts.computation_account.key(),
+            &queued_computation,
+        ) {
+            msg!("Callback already processed for computation {}", ctx.accounts.game_account.computation_offset);
+            return Ok(());
+        }
+        let game = &ctx.accounts.game_account;
+        require!(game.status == GameStatus::Pending as u8, ErrorCode::GameNotPending);
+        require!(
+            game.win_probability_bps > 0 && win_probability_bps == game.win_probability_bps,
+            ErrorCode::ParameterMismatch
+        );
+        let bet_amount = game.bet_amount;
+
+        if !sampled {
+            ctx.accounts.house_stats.record_rng_failure()?;
+            refund_pending_game(
+                &mut ctx.accounts.game_account,
+                &ctx.accounts.player,
+                &mut ctx.accounts.house_stats,
+                &mut ctx.accounts.player_stats,
+            )?;
+            msg!("Odds roll unsampled, {} lamports refunded", bet_amount);
+            return Ok(());
+        }
+
+        emit!(OddsGameRolledEvent {
+            player: game.player,
+            roll,
+            win_probability_bps,
+        });
+
+        // There's no chest to report, so the result entry carries chest 0
+        settle_round(
+            &mut SettleAccounts {
+                game_account: &mut ctx.accounts.game_account,
+                treasury: &mut ctx.accounts.treasury,
+                player: &ctx.accounts.player,
+                config: &ctx.accounts.config,
+                house_stats: &mut ctx.accounts.house_stats,
+                fee_split: &ctx.accounts.fee_split,
+                pending_claim: &mut ctx.accounts.pending_claim,
+                result_log: &ctx.accounts.result_log,
+                player_stats: &mut ctx.accounts.player_stats,
+                fairness_proof: &mut ctx.accounts.fairness_proof,
+                remaining_accounts: ctx.remaining_accounts,
+            },
+            outcome,
+            0,
+        )
+    }
+
+    /// Start a shared-pot game between 2 or 3 players, who all sign the
+    /// transaction and stake the same `bet_amount`. A single MPC draw picks
+    /// the winning chest; the winners split the pot, and if nobody wins every
+    /// bet is refunded. The treasury never pays or keeps anything.
+    pub fn play_pot_game(
+        ctx: Context<PlayPotGame>,
+        computation_offset: u64,
+        num_chests: u8,
+        bet_amount: u64,
+        entries: Vec<PotEntry>, // One encrypted choice per signing player, in account order
+    ) -> Result<()> {
+        validate_chest_count(num_chests)?;
+        require!(bet_amount >= MIN_BET_LAMPORTS, ErrorCode::BetTooSmall);
+        ctx.accounts.config.validate_bet_cap(bet_amount)?;
+        ctx.accounts.config.validate_rng_range(num_chests as u16)?;
+        validate_comp_def_ready(&ctx.accounts.comp_def_account)?;
+
+        let mut players = vec![ctx.accounts.player_0.key(), ctx.accounts.player_1.key()];
+        players.extend(ctx.accounts.player_2.as_ref().map(|player| player.key()));
+        validate_pot_players(&players, entries.len())?;
+        for entry in &entries {
+            validate_x25519_pubkey(&entry.pub_key)?;
+        }
+
+        let allowlist_only = ctx.accounts.config.allowlist_only;
+        validate_player_access(load_player_access(&ctx.accounts.player_access_0)?.as_ref(), allowlist_only)?;
+        validate_player_access(load_player_access(&ctx.accounts.player_access_1)?.as_ref(), allowlist_only)?;
+        if let Some(player_2) = &ctx.accounts.player_2 {
+            let access = ctx.accounts.player_access_2.as_ref().ok_or(ErrorCode::InvalidPotPlayers)?;
+            require_keys_eq!(access.key(), player_access_pda(&player_2.key()).0, ErrorCode::InvalidPotPlayers);
+            validate_player_access(load_player_access(access)?.as_ref(), allowlist_only)?;
+        }
+
+        // Every bet goes into the pot account until the draw
+        let pot_info = ctx.accounts.pot_game.to_account_info();
+        let mut stakers = vec![ctx.accounts.player_0.to_account_info(), ctx.accounts.player_1.to_account_info()];
+        stakers.extend(ctx.accounts.player_2.as_ref().map(|player| player.to_account_info()));
+        for staker in stakers {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer { from: staker, to: pot_info.clone() },
+                ),
+                bet_amount,
+            )?;
+        }
+
+        let pot = &mut ctx.accounts.pot_game;
+        pot.players = [Pubkey::default(); MAX_POT_PLAYERS];
+        pot.players[..players.len()].copy_from_slice(&players);
+        pot.num_players = players.len() as u8;
+        pot.num_chests = num_chests;
+        pot.bet_amount = bet_amount;
+        pot.computation_offset = computation_offset;
+        pot.status = GameStatus::Pending as u8;
+        pot.created_at = Clock::get()?.unix_timestamp;
+        pot.bump = ctx.bumps.pot_game;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // The circuit always takes MAX_POT_PLAYERS choices; a two-player game
+        // repeats player 0's in the last slot, and its result is ignored
+        let mut args = ArgBuilder::new();
+        for i in 0..MAX_POT_PLAYERS {
+            let entry = entries.get(i).unwrap_or(&entries[0]);
+            args = args
+                .x25519_pubkey(entry.pub_key)
+                .plaintext_u128(entry.nonce)
+                .encrypted_u8(entry.choice);
+        }
+        let args = args.plaintext_u8(num_chests).build();
+
+        let callback_accounts = CallbackAccounts::for_play_pot_game(ctx.accounts.pot_game.key(), &players);
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![PlayPotGameCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &callback_accounts,
+            )?],
+            1,
+            0, // cu_price_micro
+        )?;
+
+        emit!(PotGameStartedEvent {
+            pot: ctx.accounts.pot_game.key(),
+            players: ctx.accounts.pot_game.players,
+            num_chests,
+            bet_amount,
+            computation_offset,
+        });
+
+        msg!("Pot game started: {} players, {} chests, {} lamports each", players.len(), num_chests, bet_amount);
+        Ok(())
+    }
+
+    /// Callback for `play_pot_game`: splits the pot between the players who
+    /// picked the winning chest, or refunds everyone if nobody did or the draw
+    /// went unsampled, then closes the pot account to player 0
+    #[arcium_callback(encrypted_ix = "play_pot_game")]
+    pub fn play_pot_game_callback(
+        ctx: Context<PlayPotGameCallback>,
+        output: SignedComputationOutputs<PlayPotGameOutput>,
+    ) -> Result<()> {
+        let (winning_chest, won, sampled, num_chests) = match output.verify_output(
+            &ctx.accounts.cluster_account,
+            &ctx.accounts.computation_account,
+        ) {
+            Ok(PlayPotGameOutput {
+                field_0:
+                    PlayPotGameOutputStruct0 {
+                        field_0: winning_chest,
+                        field_1: won_0,
+                        field_2: won_1,
+                        field_3: won_2,
+                        field_4: sampled,
+                        field_5: num_chests,
+                    },
+            }) => (winning_chest, [won_0, won_1, won_2], sampled, num_chests),
+            Err(_) => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let pot = &ctx.accounts.pot_game;
+        require!(pot.status == GameStatus::Pending as u8, ErrorCode::GameNotPending);
+        require_echoed_chest_count(num_chests, pot.num_chests)?;
+        let num_players = pot.num_players as usize;
+        let players = [&ctx.accounts.player_0, &ctx.accounts.player_1, &ctx.accounts.player_2];
+        for (player, expected) in players.iter().zip(pot.players).take(num_players) {
+            require_keys_eq!(player.key(), expected, ErrorCode::InvalidPotPlayers);
+        }
+
+        // An unsampled draw pays like a round nobody won: every bet back
+        let winners: Vec<bool> = if sampled { won[..num_players].to_vec() } else { vec![false; num_players] };
+        let payouts = pot_payouts(pot.bet_amount, &winners)?;
+        let pot_info = ctx.accounts.pot_game.to_account_info();
+        for (player, payout) in players.iter().zip(&payouts) {
+            transfer_lamports(&pot_info, player, *payout)?;
+        }
+
+        let mut paid = [0u64; MAX_POT_PLAYERS];
+        paid[..num_players].copy_from_slice(&payouts);
+        let mut winners_out = [false; MAX_POT_PLAYERS];
+        winners_out[..num_players].copy_from_slice(&winners);
+        emit!(PotGameResultEvent {
+            pot: ctx.accounts.pot_game.key(),
+            winning_chest,
+            sampled,
+            winners: winners_out,
+            payouts: paid,
+        });
+
+        ctx.accounts.pot_game.status = GameStatus::Completed as u8;
+        ctx.accounts.pot_game.close(ctx.accounts.player_0.clone())?;
+        msg!("Pot game settled: chest {}, {} winners", winning_chest, winners.iter().filter(|won| **won).count());
+        Ok(())
+    }
+
+    /// Refund every player of a pot game that never settled (permissionless).
+    /// Only after GAME_HARD_TIMEOUT_SECS, so the callback can no longer land.
+    pub fn cancel_pot_game(ctx: Context<CancelPotGame>) -> Result<()> {
+        let pot = &ctx.accounts.pot_game;
+        require!(pot.status == GameStatus::Pending as u8, ErrorCode::GameNotPending);
+        require!(
+            Clock::get()?.unix_timestamp - pot.created_at > GAME_HARD_TIMEOUT_SECS,
+            ErrorCode::GameNotTimedOut
+        );
+        let num_players = pot.num_players as usize;
+        let bet_amount = pot.bet_amount;
+        let players = [&ctx.accounts.player_0, &ctx.accounts.player_1, &ctx.accounts.player_2];
+        for (player, expected) in players.iter().zip(pot.players).take(num_players) {
+            require_keys_eq!(player.key(), expected, ErrorCode::InvalidPotPlayers);
+        }
+
+        let pot_info = ctx.accounts.pot_game.to_account_info();
+        for player in players.iter().take(num_players) {
+            transfer_lamports(&pot_info, player, bet_amount)?;
+        }
+        ctx.accounts.pot_game.status = GameStatus::Cancelled as u8;
+        ctx.accounts.pot_game.close(ctx.accounts.player_0.clone())?;
+
+        msg!("Pot game cancelled, {} lamports refunded to each of {} players", bet_amount, num_players);
+        Ok(())
+    }
+
+    /// Queue the next round of a best-of-N series that is still undecided
+    pub fn play_next_round(
+        ctx: Context<PlayNextRound>,
+        computation_offset: u64,
+        player_choice: [u8; 32],  // Encrypted chest choice for this round
+        pub_key: [u8; 32],        // Player's encryption pubkey
+        nonce: u128,              // Encryption nonce
+    ) -> Result<()> {
+        validate_comp_def_ready(&ctx.accounts.comp_def_account)?;
+        let game_account_key = ctx.accounts.game_account.key();
+        let treasury_key = ctx.accounts.treasury.key();
+        let player_key = ctx.accounts.player.key();
+
+        let game = &mut ctx.accounts.game_account;
+        require!(
+            game.status == GameStatus::SeriesInProgress as u8,
+            ErrorCode::SeriesNotInProgress
+        );
+        game.status = GameStatus::Pending as u8;
+        ctx.accounts.house_stats.record_pending_start(ctx.accounts.config.max_pending_games)?;
+        game.created_at = Clock::get()?.unix_timestamp;
+        game.settle_by = ctx.accounts.config.settle_by(game.created_at);
+        game.computation_offset = computation_offset;
+        let num_chests = game.num_chests;
+        let bet_amount = game.bet_amount;
+        let metadata = game.metadata;
+        let win_mode = game.win_mode;
+        let player_seed = game.player_seed;
+        // Each round mixes in the slot hash current when it is queued
+        let (entropy_slot, slot_hash) = ctx.accounts.config.draw_slot_hash(&ctx.accounts.slot_hashes)?;
+        game.entropy_slot = entropy_slot;
+        game.slot_hash = slot_hash;
+
+        let claim = &mut ctx.accounts.pending_claim;
+        claim.player = player_key;
+        claim.bump = ctx.bumps.pending_claim;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_u8(player_choice)
+            .plaintext_u8(num_chests)
+            .plaintext_bool(ctx.accounts.config.push_enabled)
+            .plaintext_u8(win_mode)
+            .plaintext_u64(player_entropy(&player_seed))
+            .plaintext_u64(slot_entropy(&slot_hash))
+            .plaintext_bool(ctx.accounts.config.reveal_raw_draw)
+            .build();
+
+        let callback_accounts = CallbackAccounts::for_play_chest_game(
+            game_account_key,
+            treasury_key,
+            player_key,
+            ctx.accounts.config.key(),
+            ctx.accounts.house_stats.key(),
+            &ctx.accounts.fee_split,
+            ctx.accounts.pending_claim.key(),
+            result_log_pda().0,
+            player_stats_pda(&player_key).0,
+            fairness_proof_pda(&player_key).0,
+            &ctx.accounts.config.loss_route_accounts(),
+            &ctx.accounts.config.reward_accounts(&player_key),
+        )?;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![PlayChestGameCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &callback_accounts,
+            )?],
+            1,
+            0, // cu_price_micro
+        )?;
+
+        emit!(GameStartedEvent {
+            player: player_key,
+            computation_offset,
+            num_chests,
+            bet_amount,
+            pub_key,
+            nonce,
+            metadata,
+            tip: 0,
+            win_probability_bps: ctx.accounts.config.chest_win_probability_bps(num_chests, win_mode),
+        });
+
+        msg!("Next series round queued for {}", player_key);
+        Ok(())
+    }
+
+    /// Add to the bet of a game waiting on `play_next_round` before any of its
+    /// rounds is decided: a compounded win or a pushed opening round. Once a
+    /// computation is queued its result can be known before the callback
+    /// lands, so a pending game can't be topped up.
+    pub fn top_up_bet(ctx: Context<TopUpBet>, additional: u64) -> Result<()> {
+        require!(additional > 0, ErrorCode::BetTooSmall);
+        let game = &ctx.accounts.game_account;
+        require!(game.status != GameStatus::Pending as u8, ErrorCode::TopUpAfterQueue);
+        require!(
+            game.status == GameStatus::SeriesInProgress as u8 && game.rounds_played == 0,
+            ErrorCode::SeriesNotInProgress
+        );
+
+        let bet_amount = game.bet_amount.checked_add(additional).ok_or(ErrorCode::Overflow)?;
+        let config = &ctx.accounts.config;
+        config.validate_bet_cap(bet_amount)?;
+
+        // Re-run the exposure check against the larger bet
+        let worst_case_payout = if game.win_probability_bps > 0 {
+            config.worst_case_odds_payout_for(bet_amount, game.win_probability_bps)?
+        } else {
+            config.worst_case_payout_for(
+                bet_amount,
+                if game.hidden_chests { MAX_CHESTS } else { game.num_chests },
+                game.win_mode,
+                game.rounds_total,
+                game.promo_bonus_bps,
+            )?
+        };
+        let worst_case_winnings = worst_case_payout.saturating_sub(bet_amount);
+        let insured_refund = if game.insured { config.insured_refund(bet_amount) } else { 0 };
+        require!(
+            treasury_liquid_lamports(&ctx.accounts.treasury)?
+                >= worst_case_winnings.max(insured_refund),
+            ErrorCode::InsufficientTreasury
+        );
+
+        // The entry fee was paid when the game started; an insured bet pays
+        // the premium on the extra stake too
+        let fees = if game.insured { config.insurance_premium(additional) } else { 0 };
+        let spend = additional.checked_add(fees).ok_or(ErrorCode::Overflow)?;
+        validate_player_balance(&ctx.accounts.player, spend)?;
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.player.to_account_info(),
+                    to: ctx.accounts.game_account.to_account_info(),
+                },
+            ),
+            additional,
+        )?;
+
+        if fees > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.player.to_account_info(),
+                        to: ctx.accounts.treasury.to_account_info(),
+                    },
+                ),
+                fees,
+            )?;
+        }
+
+        ctx.accounts.game_account.bet_amount = bet_amount;
+        ctx.accounts.player_stats.record_wager(additional)?;
+        ctx.accounts.player_stats.reserve_exposure(
+            &mut ctx.accounts.game_account,
+            worst_case_winnings.max(insured_refund),
+            ctx.accounts.config.max_player_exposure,
+        )?;
+
+        emit!(BetToppedUpEvent {
+            player: ctx.accounts.player.key(),
+            additional,
+            bet_amount,
+        });
+
+        msg!("Bet topped up by {} to {} lamports", additional, bet_amount);
+        Ok(())
+    }
+
+    /// Take back the stake of a game waiting on `play_next_round` before any
+    /// of its rounds is decided, instead of playing it: a compounded win or a
+    /// pushed opening round. Nothing is queued, so nothing about the next
+    /// round is known yet. The stake is escrowed like a win when the config
+    /// would escrow or slot-lock a payout that size.
+    pub fn cash_out(ctx: Context<CashOut>) -> Result<()> {
+        let game = &ctx.accounts.game_account;
+        require!(
+            game.status == GameStatus::SeriesInProgress as u8 && game.rounds_played == 0,
+            ErrorCode::SeriesNotInProgress
+        );
+        let amount = game.bet_amount;
+        let config = &ctx.accounts.config;
+        let delayed = config.escrows_payout(amount);
+        let payee = if delayed {
+            ctx.accounts.pending_claim.to_account_info()
+        } else {
+            ctx.accounts.player.to_account_info()
+        };
+
+        transfer_lamports(&ctx.accounts.game_account.to_account_info(), &payee, amount)?;
+        require_rent_exempt(&ctx.accounts.game_account.to_account_info())?;
+
+        if delayed {
+            let clock = Clock::get()?;
+            let claimable_at = clock
+                .unix_timestamp
+                .checked_add(config.claim_delay_secs)
+                .ok_or(ErrorCode::Overflow)?;
+            let claimable_slot = clock.slot.checked_add(config.winnings_lock_slots).ok_or(ErrorCode::Overflow)?;
+            ctx.accounts.pending_claim.escrow(amount, 0, claimable_at, claimable_slot)?;
+
+            emit!(WinningsEscrowedEvent {
+                player: ctx.accounts.player.key(),
+                amount,
+                claimable_at,
+                owed: 0,
+                claimable_slot,
+            });
+        }
+
+        ctx.accounts.game_account.status = GameStatus::Completed as u8;
+        ctx.accounts.player_stats.record_payout(amount)?;
+        ctx.accounts.player_stats.release_exposure(&mut ctx.accounts.game_account);
+
+        emit!(GameCashedOutEvent {
+            player: ctx.accounts.player.key(),
+            amount,
+        });
+
+        msg!("Cashed out {} lamports", amount);
+        Ok(())
+    }
+
+    /// Authorize an ephemeral key to place bets for the player until `expires_at`.
+    /// The player escrows `spend_cap` into the session PDA and bets are paid from it.
+    pub fn create_session(
+        ctx: Context<CreateSession>,
+        session_key: Pubkey,
+        expires_at: i64,
+        spend_cap: u64,
+    ) -> Result<()> {
+        require!(expires_at > Clock::get()?.unix_timestamp, ErrorCode::SessionExpired);
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.player.to_account_info(),
+                    to: ctx.accounts.session.to_account_info(),
+                },
+            ),
+            spend_cap,
+        )?;
+
+        let session = &mut ctx.accounts.session;
+        session.player = ctx.accounts.player.key();
+        session.session_key = session_key;
+        session.expires_at = expires_at;
+        session.spend_cap = spend_cap;
+        session.spent = 0;
+        session.bump = ctx.bumps.session;
+
+        msg!("Session {} created, cap {} lamports", session_key, spend_cap);
+        Ok(())
+    }
+
+    /// End a session and return the unspent escrow plus rent to the player
+    pub fn close_session(_ctx: Context<CloseSession>) -> Result<()> {
+        msg!("Session closed");
+        Ok(())
+    }
+
+    /// Pay out winnings escrowed by a large win once the claim delay has passed,
+    /// plus as much of any `owed` winnings as the treasury can now cover
+    pub fn claim_winnings(ctx: Context<ClaimWinnings>) -> Result<()> {
+        let treasury_info = ctx.accounts.treasury.to_account_info();
+        let available = treasury_available_lamports(&treasury_info)?;
+        let claim = &mut ctx.accounts.pending_claim;
+        require!(claim.amount > 0 || claim.owed > 0, ErrorCode::NothingToClaim);
+        let clock = Clock::get()?;
+        claim.require_claimable(clock.unix_timestamp, clock.slot)?;
+        let amount = claim.amount;
+        claim.amount = 0;
+        let owed_paid = claim.take_owed(available);
+        let still_owed = claim.owed;
+        require!(amount > 0 || owed_paid > 0, ErrorCode::InsufficientTreasury);
+
+        let player_info = ctx.accounts.player.to_account_info();
+        if amount > 0 {
+            transfer_lamports(&ctx.accounts.pending_claim.to_account_info(), &player_info, amount)?;
+            require_rent_exempt(&ctx.accounts.pending_claim.to_account_info())?;
+        }
+        if owed_paid > 0 {
+            transfer_lamports(&treasury_info, &player_info, owed_paid)?;
+        }
+
+        emit!(WinningsClaimedEvent {
+            player: ctx.accounts.player.key(),
+            amount: amount + owed_paid,
+        });
+
+        msg!(
+            "Claimed {} lamports of escrowed winnings, {} still owed",
+            amount + owed_paid,
+            still_owed
+        );
+        Ok(())
+    }
+
+    /// Permissionless claim for escrowed winnings the player has left alone
+    /// for `keeper_claim_grace_secs` past the claim delay. The keeper keeps
+    /// `keeper_fee_bps` of the escrow and the rest goes to the player.
+    pub fn claim_winnings_for(ctx: Context<ClaimWinningsFor>, player: Pubkey) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let claim = &mut ctx.accounts.pending_claim;
+        require!(claim.amount > 0, ErrorCode::NothingToClaim);
+        let clock = Clock::get()?;
+        config.validate_keeper_claim(claim.claimable_at, clock.unix_timestamp)?;
+        claim.require_claimable(clock.unix_timestamp, clock.slot)?;
+        let amount = claim.amount;
+        claim.amount = 0;
+
+        let keeper_fee = config.keeper_fee(amount);
+        let claim_info = ctx.accounts.pending_claim.to_account_info();
+        transfer_lamports(&claim_info, &ctx.accounts.player_wallet, amount - keeper_fee)?;
+        if keeper_fee > 0 {
+            transfer_lamports(&claim_info, &ctx.accounts.keeper.to_account_info(), keeper_fee)?;
+        }
+        require_rent_exempt(&claim_info)?;
+
+        emit!(WinningsClaimedForEvent {
+            player,
+            keeper: ctx.accounts.keeper.key(),
+            amount: amount - keeper_fee,
+            keeper_fee,
+        });
+
+        msg!(
+            "Keeper {} claimed {} lamports of escrowed winnings, fee {}",
+            ctx.accounts.keeper.key(),
+            amount - keeper_fee,
+            keeper_fee
+        );
+        Ok(())
+    }
+
+    /// Single-round game signed by a session key, with the bet drawn from the session escrow
+    #[allow(clippy::too_many_arguments)]
+    pub fn play_chest_game_session(
+        ctx: Context<PlayChestGameSession>,
+        computation_offset: u64,
+        num_chests: u8,
+        bet_amount: u64,
+        player_choice: [u8; 32],
+        pub_key: [u8; 32],
+        nonce: u128,
+        idempotency_key: [u8; 16], // Client retry key; all zeros disables dedupe
+        tip: u64,                  // Voluntary tip to the house, paid from the escrow
+    ) -> Result<()> {
+        validate_chest_count(num_chests)?;
+        require!(bet_amount >= MIN_BET_LAMPORTS, ErrorCode::BetTooSmall);
+        ctx.accounts.config.validate_bet_cap(bet_amount)?;
+        validate_x25519_pubkey(&pub_key)?;
+        validate_comp_def_ready(&ctx.accounts.comp_def_account)?;
+        let config = &ctx.accounts.config;
+        config.validate_rng_range(config.chest_draw_range(num_chests) as u16)?;
+
+        require!(
+            Clock::get()?.unix_timestamp < ctx.accounts.session.expires_at,
+            ErrorCode::SessionExpired
+        );
+
+        let game_account_key = ctx.accounts.game_account.key();
+        let treasury_key = ctx.accounts.treasury.key();
+        let player_key = ctx.accounts.player.key();
+        if check_new_game(
+            &ctx.accounts.config,
+            &ctx.accounts.treasury,
+            &player_key,
+            &ctx.accounts.player_access,
+            &ctx.accounts.game_account,
+            &idempotency_key,
+        )? {
+            msg!("Duplicate play submission ignored");
+            return Ok(());
+        }
+
+        // The spend cap covers the bet and tip; fees only have to fit in the escrow
+        let session = &mut ctx.accounts.session;
+        let spent = session
+            .spent
+            .checked_add(bet_amount)
+            .and_then(|spent| spent.checked_add(tip))
+            .ok_or(ErrorCode::Overflow)?;
+        require!(spent <= session.spend_cap, ErrorCode::SessionCapExceeded);
+        session.spent = spent;
+
+        let config = &ctx.accounts.config;
+        let first_game_bonus_bps = config.first_game_bonus_for(&ctx.accounts.player_stats);
+        let worst_case_winnings = config
+            .worst_case_payout_for(bet_amount, num_chests, WinMode::Exact as u8, 1, first_game_bonus_bps)?
+            .saturating_sub(bet_amount);
+        let entry_fee = config.entry_fee_for(ctx.accounts.player_stats.total_wagered);
+        let (entropy_slot, slot_hash) = config.draw_slot_hash(&ctx.accounts.slot_hashes)?;
+
+        let stats = &mut ctx.accounts.player_stats;
+        stats.player = player_key;
+        stats.bump = ctx.bumps.player_stats;
+        stats.stats_version = PLAYER_STATS_VERSION;
+        ctx.accounts.fairness_proof.bump = ctx.bumps.fairness_proof;
+        ctx.accounts.tip_vault.bump = ctx.bumps.tip_vault;
+
+        // Everything comes out of the escrow, which must stay rent-exempt
+        let session_info = ctx.accounts.session.to_account_info();
+        start_game(
+            &mut StartAccounts {
+                payer: Payer::Escrow(&session_info),
+                game_account: &mut ctx.accounts.game_account,
+                treasury: &mut ctx.accounts.treasury,
+                config: &ctx.accounts.config,
+                house_stats: &mut ctx.accounts.house_stats,
+                player_stats: &mut ctx.accounts.player_stats,
+                tip_vault: &ctx.accounts.tip_vault,
+            },
+            &NewGame {
+                player: player_key,
+                bump: ctx.bumps.game_account,
+                computation_offset,
+                bet_amount,
+                num_chests,
+                promo_bonus_bps: first_game_bonus_bps,
+                rounds_total: 1,
+                idempotency_key,
+                entropy_slot,
+                slot_hash,
+                ..Default::default()
+            },
+            entry_fee,
+            tip,
+            worst_case_winnings,
+        )?;
+
+        let claim = &mut ctx.accounts.pending_claim;
+        claim.player = player_key;
+        claim.bump = ctx.bumps.pending_claim;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        let args = ArgBuilder::new()
+            .x25519_pubkey(pub_key)
+            .plaintext_u128(nonce)
+            .encrypted_u8(player_choice)
+            .plaintext_u8(num_chests)
+            .plaintext_bool(ctx.accounts.config.push_enabled)
+            .plaintext_u8(WinMode::Exact as u8)
+            .plaintext_u64(player_entropy(&[0u8; 32]))
+            .plaintext_u64(slot_entropy(&slot_hash))
+            .plaintext_bool(ctx.accounts.config.reveal_raw_draw)
+            .build();
+
+        let callback_accounts = CallbackAccounts::for_play_chest_game(
+            game_account_key,
+            treasury_key,
+            player_key,
+            ctx.accounts.config.key(),
+            ctx.accounts.house_stats.key(),
+            &ctx.accounts.fee_split,
+            ctx.accounts.pending_claim.key(),
+            result_log_pda().0,
+            player_stats_pda(&player_key).0,
+            fairness_proof_pda(&player_key).0,
+            &ctx.accounts.config.loss_route_accounts(),
+            &ctx.accounts.config.reward_accounts(&player_key),
+        )?;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            vec![PlayChestGameCallback::callback_ix(
+                computation_offset,
+                &ctx.accounts.mxe_account,
+                &callback_accounts,
+            )?],
+            1,
+            0, // cu_price_micro
+        )?;
+
+        emit!(GameStartedEvent {
+            player: player_key,
+            computation_offset,
+            num_chests,
+            bet_amount,
+            pub_key,
+            nonce,
+            metadata: [0u8; 32],
+            tip,
+            win_probability_bps: ctx.accounts.config.chest_win_probability_bps(num_chests, WinMode::Exact as u8),
+        });
+
+        msg!("Session bet of {} lamports queued for {}", bet_amount, player_key);
+        Ok(())
+    }
+
+    /// Grow a game account written by an older deployment to the current
+    /// `GameAccount` layout. Fields added since then start zeroed, except
+    /// `settle_by`, which is backfilled from `created_at`. The player or the
+    /// treasury authority can run it, and the signer pays any extra rent.
+    pub fn migrate_game_account(ctx: Context<MigrateGameAccount>) -> Result<()> {
+        let info = ctx.accounts.game_account.to_account_info();
+        require_keys_eq!(*info.owner, crate::ID, ErrorCode::InvalidGameAccount);
+
+        let old_len = info.data_len();
+        let migrated = migrated_game_account(&info.try_borrow_data()?)?;
+        require_keys_eq!(
+            info.key(),
+            game_pda(&migrated.player).0,
+            ErrorCode::InvalidGameAccount
+        );
+        let signer = ctx.accounts.signer.key();
+        require!(
+            signer == migrated.player || signer == ctx.accounts.treasury.authority,
+            ErrorCode::Unauthorized
+        );
+        if old_len >= GameAccount::SPACE {
+            msg!("Game account already uses the current layout");
+            return Ok(());
+        }
+
+        let rent_needed = Rent::get()?
+            .minimum_balance(GameAccount::SPACE)
+            .saturating_sub(info.lamports());
+        if rent_needed > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.signer.to_account_info(),
+                        to: info.clone(),
+                    },
+                ),
+                rent_needed,
+            )?;
+        }
+
+        info.resize(GameAccount::SPACE)?;
+        migrated.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        msg!("Game account migrated from {} to {} bytes", old_len, GameAccount::SPACE);
+        Ok(())
+    }
+
+    /// Whether `cancel_game` would cancel the player's game now, given the
+    /// same optional computation proof. Read-only; keepers simulate it and
+    /// read the bool from the return data before calling `cancel_game`.
+    pub fn is_cancellable(ctx: Context<IsCancellable>) -> Result<bool> {
+        let accounts = &ctx.accounts;
+        Ok(accounts
+            .game_account
+            .check_cancellable(
+                Clock::get()?.unix_timestamp,
+                accounts.mxe_account.as_deref().map(|mxe| &**mxe),
+                accounts.computation_account.as_deref(),
+            )
+            .is_ok())
+    }
+
+    /// Cancel a game and refund the player (for timeouts or failures).
+    /// `close_account` also closes the game account, returning its rent to
+    /// the player; that needs proof the computation has left the queue, so
+    /// no late callback can land on a re-created account.
+    pub fn cancel_game(ctx: Context<CancelGame>, close_account: bool) -> Result<()> {
+        let game = &ctx.accounts.game_account;
+        let computation_queued = game.check_cancellable(
+            Clock::get()?.unix_timestamp,
+            ctx.accounts.mxe_account.as_deref().map(|mxe| &**mxe),
+            ctx.accounts.computation_account.as_deref(),
+        )?;
+        require!(!(close_account && computation_queued), ErrorCode::ComputationStillQueued);
+
+        // Refund the bet to player
+        let bet_amount = game.bet_amount;
+        let player_key = game.player;
+        let computation_offset = game.computation_offset;
+        let created_at = game.created_at;
+        
+        transfer_lamports(
+            &ctx.accounts.game_account.to_account_info(),
+            &ctx.accounts.player,
+            bet_amount,
+        )?;
+        require_rent_exempt(&ctx.accounts.game_account.to_account_info())?;
+
+        ctx.accounts.game_account.status = GameStatus::Cancelled as u8;
+        ctx.accounts.house_stats.record_cancelled()?;
+        if let Some(stats) = ctx.accounts.player_stats.as_mut() {
+            stats.record_payout(bet_amount)?;
+            stats.release_exposure(&mut ctx.accounts.game_account);
+        }
+
+        emit!(GameCancelledEvent {
+            player: player_key,
+            bet_amount,
+            computation_offset,
+            created_at,
+        });
+
+        if close_account {
+            let rent = ctx.accounts.game_account.to_account_info().lamports();
+            ctx.accounts.game_account.close(ctx.accounts.player.clone())?;
+            msg!("Game cancelled, {} lamports refunded plus {} rent", bet_amount, rent);
+            return Ok(());
+        }
+
+        msg!("Game cancelled, {} lamports refunded", bet_amount);
+        Ok(())
+    }
+
+    /// Permissionless cancel for timed-out games that opted into auto-cancel.
+    /// Lets a keeper refund the player without the player signing.
+    pub fn cancel_expired_game(ctx: Context<CancelExpiredGame>) -> Result<()> {
+        let game = &ctx.accounts.game_account;
+
+        require!(game.auto_cancel, ErrorCode::AutoCancelNotEnabled);
+        require!(game.status == GameStatus::Pending as u8, ErrorCode::GameNotPending);
+
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(current_time > game.settle_by, ErrorCode::GameNotTimedOut);
+
+        let bet_amount = game.bet_amount;
+        let player_key = game.player;
+        let computation_offset = game.computation_offset;
+        let created_at = game.created_at;
+
+        transfer_lamports(
+            &ctx.accounts.game_account.to_account_info(),
+            &ctx.accounts.player,
+            bet_amount,
+        )?;
+        require_rent_exempt(&ctx.accounts.game_account.to_account_info())?;
+
+        ctx.accounts.game_account.status = GameStatus::Cancelled as u8;
+        ctx.accounts.house_stats.record_cancelled()?;
+        if let Some(stats) = ctx.accounts.player_stats.as_mut() {
+            stats.record_payout(bet_amount)?;
+            stats.release_exposure(&mut ctx.accounts.game_account);
+        }
+
+        emit!(GameCancelledEvent {
+            player: player_key,
+            bet_amount,
+            computation_offset,
+            created_at,
+        });
+
+        msg!(
+            "Expired game cancelled by keeper {}, {} lamports refunded",
+            ctx.accounts.keeper.key(),
+            bet_amount
+        );
+        Ok(())
+    }
+
+    /// Keeper variant of `cancel_expired_game` for many games at once. Remaining
+    /// accounts are `(game_account, player, player_stats)` triples in the order
+    /// of `players`; entries that are not eligible are skipped rather than
+    /// failing the batch. Each refund is recorded in the player's `PlayerStats`
+    /// and releases the game's exposure, as in `cancel_expired_game`.
+    pub fn cancel_expired_games_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CancelExpiredGamesBatch<'info>>,
+        players: Vec<Pubkey>,
+    ) -> Result<()> {
+        require!(
+            ctx.remaining_accounts.len() == players.len() * 3,
+            ErrorCode::BatchAccountsMismatch
+        );
+
+        let current_time = Clock::get()?.unix_timestamp;
+        let mut cancelled = 0u32;
+        for (player_key, entry) in players.iter().zip(ctx.remaining_accounts.chunks(3)) {
+            let (game_info, player_info, stats_info) = (&entry[0], &entry[1], &entry[2]);
+            if player_info.key() != *player_key
+                || !game_info.is_writable
+                || !player_info.is_writable
+                || !stats_info.is_writable
+                || game_info.owner != &crate::ID
+                || stats_info.owner != &crate::ID
+            {
+                continue;
+            }
+            let Ok(mut game) = Account::<GameAccount>::try_from(game_info) else {
+                continue;
+            };
+            let Ok(mut stats) = Account::<PlayerStats>::try_from(stats_info) else {
+                continue;
+            };
+            let expected_key =
+                Pubkey::create_program_address(&[GAME_SEED, player_key.as_ref(), &[game.bump]], &crate::ID);
+            let expected_stats_key =
+                Pubkey::create_program_address(&[PLAYER_STATS_SEED, player_key.as_ref(), &[stats.bump]], &crate::ID);
+            if expected_key != Ok(game_info.key())
+                || expected_stats_key != Ok(stats_info.key())
+                || game.player != *player_key
+                || !game.auto_cancel
+                || game.status != GameStatus::Pending as u8
+                || current_time <= game.settle_by
+            {
+                continue;
+            }
+
+            let bet_amount = game.bet_amount;
+            transfer_lamports(game_info, player_info, bet_amount)?;
+            require_rent_exempt(game_info)?;
+
+            game.status = GameStatus::Cancelled as u8;
+            stats.record_payout(bet_amount)?;
+            stats.release_exposure(&mut game);
+            game.exit(&crate::ID)?;
+            stats.exit(&crate::ID)?;
+            ctx.accounts.house_stats.record_cancelled()?;
+
+            emit!(GameCancelledEvent {
+                player: *player_key,
+                bet_amount,
+                computation_offset: game.computation_offset,
+                created_at: game.created_at,
+            });
+            cancelled += 1;
+        }
+
+        msg!(
+            "Keeper {} cancelled {} of {} expired games",
+            ctx.accounts.keeper.key(),
+            cancelled,
+            players.len()
+        );
+        Ok(())
+    }
+
+    /// Flag a game that is still pending past its settlement deadline, so an
+    /// operator can triage it. Only the player can raise it, once per game.
+    pub fn flag_dispute(ctx: Context<FlagDispute>, reason_code: u8) -> Result<()> {
+        let game = &mut ctx.accounts.game_account;
+        game.raise_dispute(reason_code, Clock::get()?.unix_timestamp)?;
+
+        emit!(DisputeRaisedEvent {
+            player: game.player,
+            reason_code,
+            computation_offset: game.computation_offset,
+            created_at: game.created_at,
+        });
+
+        msg!("Dispute raised on game {} with reason {}", game.computation_offset, reason_code);
+        Ok(())
+    }
+
+    /// Close a disputed game (config authority only). `refund` returns the
+    /// whole bet to the player; otherwise the game is force-settled as a loss
+    /// and the bet goes to the treasury, with no fee split or insured refund.
+    /// The game's computation must have left the queue, or the game be past
+    /// GAME_HARD_TIMEOUT_SECS, so its callback can't settle it as well.
+    pub fn resolve_dispute(ctx: Context<ResolveDispute>, refund: bool) -> Result<()> {
+        let game = &ctx.accounts.game_account;
+        require!(game.disputed, ErrorCode::GameNotDisputed);
+        require!(game.status == GameStatus::Pending as u8, ErrorCode::GameNotPending);
+        require_keys_eq!(
+            ctx.accounts.computation_account.key(),
+            derive_comp_pda!(game.computation_offset, ctx.accounts.mxe_account, ErrorCode::ClusterNotSet),
+            ErrorCode::InvalidComputationAccount
+        );
+        let elapsed = Clock::get()?.unix_timestamp - game.created_at;
+        require!(
+            !computation_is_queued(&ctx.accounts.computation_account)? || elapsed > GAME_HARD_TIMEOUT_SECS,
+            ErrorCode::ComputationStillQueued
+        );
+
+        let bet_amount = game.bet_amount;
+        let player_key = game.player;
+        let payee = if refund {
+            ctx.accounts.player.to_account_info()
+        } else {
+            ctx.accounts.treasury.to_account_info()
+        };
+        transfer_lamports(&ctx.accounts.game_account.to_account_info(), &payee, bet_amount)?;
+        require_rent_exempt(&ctx.accounts.game_account.to_account_info())?;
+
+        let game = &mut ctx.accounts.game_account;
+        game.disputed = false;
+        if refund {
+            game.status = GameStatus::Cancelled as u8;
+            ctx.accounts.house_stats.record_cancelled()?;
+            if let Some(stats) = ctx.accounts.player_stats.as_mut() {
+                stats.record_payout(bet_amount)?;
+            }
+        } else {
+            game.status = GameStatus::Completed as u8;
+            ctx.accounts.house_stats.record_game(bet_amount, 0)?;
+            ctx.accounts.house_stats.record_pending_end();
+        }
+        if let Some(stats) = ctx.accounts.player_stats.as_mut() {
+            stats.release_exposure(game);
+        }
+
+        emit!(DisputeResolvedEvent {
+            player: player_key,
+            refunded: refund,
+            bet_amount,
+            computation_offset: game.computation_offset,
+        });
+
+        msg!("Dispute resolved for {}: refunded={}", player_key, refund);
+        Ok(())
+    }
+}
+
+// ============= Account Structs =============
+
+#[account]
+pub struct Treasury {
+    pub authority: Pubkey,
+    pub bump: u8,
+    pub window_start: i64,     // Start of the current circuit breaker window
+    pub window_net_loss: u64,  // Winnings paid minus bets kept this window, floored at 0
+    pub created_at: i64,       // Set by `init_treasury`; 0 on treasuries created before it was tracked
+    pub last_slot: u64,        // Slot of the most recent new game
+    pub games_this_slot: u32,  // New games started in `last_slot`
+    pub window_wins_by_chests: [u64; WIN_CAP_SLOTS], // Winnings paid this window, per `win_cap_slot`
+    pub reserve: u64,          // Lamports held back from covering new games, set by `set_reserve`
+}
+
+#[account]
+pub struct GameAccount {
+    pub player: Pubkey,
+    pub bet_amount: u64,
+    pub num_chests: u8,
+    pub status: u8,
+    pub created_at: i64,
+    pub computation_offset: u64,
+    pub bump: u8,
+    pub auto_cancel: bool,
+    pub promo_bonus_bps: u16,
+    pub rounds_total: u8,  // 1 for a single game, N for best-of-N
+    pub rounds_played: u8,
+    pub rounds_won: u8,
+    pub last_idempotency_key: [u8; 16],
+    pub insured: bool, // Premium paid; a loss refunds `GameConfig::insurance_bps` of the bet
+    pub metadata: [u8; 32],         // Opaque tag set by the caller; the program never reads it
+    pub win_mode: u8,
+    pub hidden_chests: bool, // num_chests was encrypted and is 0 until the callback reveals it
+    pub settle_by: i64,      // Once past this, the pending game can be cancelled and refunded
+    pub win_probability_bps: u16, // Odds games only; 0 for chest games
+    pub player_seed: [u8; 32],    // Player entropy mixed into the MPC draw, all zeros for none
+    pub disputed: bool,           // Raised by `flag_dispute`, cleared by `resolve_dispute`
+    pub dispute_reason: u8,       // Player-chosen code for operator triage; the program never reads it
+    pub exposure: u64,            // Worst-case winnings reserved in `PlayerStats::outstanding_exposure`
+    pub auto_compound: bool,      // A win becomes the next round's bet, see `GameConfig::compounded_bet`
+    pub entropy_slot: u64,        // Slot whose hash was mixed into the MPC draw, 0 for none
+    pub slot_hash: [u8; 32],      // That slot's hash, all zeros for none
+    pub settled_by_callback: bool, // Set once the callback for `computation_offset` completed the game
+}
+
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum GameStatus {
+    None = 0,
+    Pending = 1,
+    Completed = 2,
+    Cancelled = 3,
+    SeriesInProgress = 4, // Between rounds of an undecided best-of-N series
+    Refunded = 5,         // Bet returned because the result couldn't be settled fairly
+}
+
+/// Which chests count as a win for the player's pick
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum WinMode {
+    Exact = 0,    // Only the drawn chest wins
+    NearMiss = 1, // The drawn chest or either neighbour, wrapping around the ends
+}
+
+/// Round result as encoded by the `play_chest_game` circuit
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum RoundOutcome {
+    Lose = 0,
+    Win = 1,
+    Push = 2, // Only produced when `GameConfig::push_enabled`
+}
+
+// Space: 32 (player) + 8 (bet) + 1 (chests) + 1 (status) + 8 (created) + 8 (offset) + 1 (bump) + 1 (auto_cancel) + 2 (promo bonus) + 3 (rounds) + 16 (idempotency key) + 1 (insured) + 32 (metadata) + 1 (win mode) + 1 (hidden chests) + 8 (settle by) + 2 (win probability) + 32 (player seed) + 1 (disputed) + 1 (dispute reason) + 8 (exposure) + 1 (auto compound) + 8 (entropy slot) + 32 (slot hash) + 1 (settled by callback) + 8 (discriminator) = 218
+impl GameAccount {
+    pub const SPACE: usize = 8 + 32 + 8 + 1 + 1 + 8 + 8 + 1 + 1 + 2 + 3 + 16 + 1 + 32 + 1 + 1 + 8 + 2 + 32 + 1 + 1 + 8 + 1 + 8 + 32 + 1;
+
+    /// The shift the chest circuit added to its draw, mod `range`: the
+    /// player's entropy plus the mixed-in slot hash's
+    pub fn entropy_offset(&self, range: u8) -> u8 {
+        let range = range as u64;
+        ((player_entropy(&self.player_seed) % range + slot_entropy(&self.slot_hash) % range) % range) as u8
+    }
+
+    /// Whether the game is over (or never started), so the player can queue a new one
+    pub fn accepts_new_game(&self) -> bool {
+        [GameStatus::None, GameStatus::Completed, GameStatus::Cancelled, GameStatus::Refunded]
+            .iter()
+            .any(|status| self.status == *status as u8)
+    }
+
+    /// Reset every field for `new_game`, queued at `created_at`. The exposure
+    /// reservation is left to `PlayerStats::reserve_exposure`.
+    pub fn start(&mut self, new_game: &NewGame, created_at: i64, settle_by: i64) {
+        self.player = new_game.player;
+        self.bet_amount = new_game.bet_amount;
+        self.num_chests = new_game.num_chests;
+        self.status = GameStatus::Pending as u8;
+        self.created_at = created_at;
+        self.settle_by = settle_by;
+        self.computation_offset = new_game.computation_offset;
+        self.bump = new_game.bump;
+        self.auto_cancel = new_game.auto_cancel;
+        self.promo_bonus_bps = new_game.promo_bonus_bps;
+        self.rounds_total = new_game.rounds_total;
+        self.rounds_played = 0;
+        self.rounds_won = 0;
+        self.last_idempotency_key = new_game.idempotency_key;
+        self.insured = new_game.insured;
+        self.metadata = new_game.metadata;
+        self.win_mode = new_game.win_mode;
+        self.hidden_chests = new_game.hidden_chests;
+        self.win_probability_bps = new_game.win_probability_bps;
+        self.player_seed = new_game.player_seed;
+        self.disputed = false;
+        self.dispute_reason = 0;
+        self.auto_compound = new_game.auto_compound;
+        self.entropy_slot = new_game.entropy_slot;
+        self.slot_hash = new_game.slot_hash;
+        self.settled_by_callback = false;
+    }
+
+    /// Whether a play carrying `idempotency_key` retries the submission that
+    /// started this game, while it is still `Pending` or `SeriesInProgress`.
+    /// Once the game is over the key no longer dedupes, so reusing it starts a
+    /// new game rather than being silently dropped.
+    pub fn is_retried_play(&self, idempotency_key: &[u8; 16]) -> bool {
+        let in_play = self.status == GameStatus::Pending as u8 || self.status == GameStatus::SeriesInProgress as u8;
+        *idempotency_key != [0u8; 16] && in_play && self.last_idempotency_key == *idempotency_key
+    }
+
+    /// Refuses cancelling a game unless it is pending past `settle_by` and its
+    /// computation has left the queue or it is older than
+    /// GAME_HARD_TIMEOUT_SECS. Without the MXE and computation accounts as
+    /// proof, the computation is assumed still queued. Returns whether it may be.
+    pub fn check_cancellable(
+        &self,
+        now: i64,
+        mxe_account: Option<&MXEAccount>,
+        computation_account: Option<&AccountInfo>,
+    ) -> Result<bool> {
+        require!(self.status == GameStatus::Pending as u8, ErrorCode::GameNotPending);
+        require!(now > self.settle_by, ErrorCode::GameNotTimedOut);
+        let computation_queued = match (mxe_account, computation_account) {
+            (Some(mxe_account), Some(computation_account)) => {
+                require_keys_eq!(
+                    computation_account.key(),
+                    derive_comp_pda!(self.computation_offset, mxe_account, ErrorCode::ClusterNotSet),
+                    ErrorCode::InvalidComputationAccount
+                );
+                computation_is_queued(computation_account)?
+            }
+            _ => true,
+        };
+        require!(
+            !computation_queued || now.saturating_sub(self.created_at) > GAME_HARD_TIMEOUT_SECS,
+            ErrorCode::ComputationStillQueued
+        );
+        Ok(computation_queued)
+    }
+
+    /// Flags a game still pending past `settle_by`; a game can only carry one dispute
+    pub fn raise_dispute(&mut self, reason_code: u8, now: i64) -> Result<()> {
+        require!(self.status == GameStatus::Pending as u8, ErrorCode::GameNotPending);
+        require!(now > self.settle_by, ErrorCode::GameNotTimedOut);
+        require!(!self.disputed, ErrorCode::GameAlreadyDisputed);
+        self.disputed = true;
+        self.dispute_reason = reason_code;
+        Ok(())
+    }
+}
+
+// Space: 8 (discriminator) + 32 (authority) + 1 (bump) + 8 (window_start) + 8 (window_net_loss) + 8 (created_at) + 8 (last_slot) + 4 (games_this_slot) + 72 (window wins by chests) + 8 (reserve) = 157
+impl Treasury {
+    pub const SPACE: usize = 8 + 32 + 1 + 8 + 8 + 8 + 8 + 4 + 8 * WIN_CAP_SLOTS + 8;
+
+    /// Global throttle on new games: counts one in `slot`, failing once
+    /// `max_per_slot` have already started in it (0 = no limit)
+    pub fn record_play(&mut self, slot: u64, max_per_slot: u16) -> Result<()> {
+        if slot != self.last_slot {
+            self.last_slot = slot;
+            self.games_this_slot = 0;
+        }
+        require!(
+            max_per_slot == 0 || self.games_this_slot < max_per_slot as u32,
+            ErrorCode::RateLimited
+        );
+        self.games_this_slot = self.games_this_slot.saturating_add(1);
+        Ok(())
+    }
+
+    /// Start a new loss window once `window_secs` have passed since the last one began
+    pub fn roll_loss_window(&mut self, now: i64, window_secs: i64) {
+        if now.saturating_sub(self.window_start) >= window_secs {
+            self.window_start = now;
+            self.window_net_loss = 0;
+            self.window_wins_by_chests = [0; WIN_CAP_SLOTS];
+        }
+    }
+
+    /// Whether paying `winnings` keeps this window's wins in `slot` within `cap` (0 = no cap)
+    pub fn chest_window_allows(&self, slot: usize, winnings: u64, cap: u64) -> bool {
+        cap == 0 || self.window_wins_by_chests[slot].saturating_add(winnings) <= cap
+    }
+
+    pub fn record_chest_win(&mut self, slot: usize, winnings: u64) {
+        self.window_wins_by_chests[slot] = self.window_wins_by_chests[slot].saturating_add(winnings);
+    }
+
+    /// Whether paying `winnings` keeps this window's net loss within `max_loss` (0 = no cap)
+    pub fn window_allows(&self, winnings: u64, max_loss: u64) -> bool {
+        max_loss == 0 || self.window_net_loss.saturating_add(winnings) <= max_loss
+    }
+
+    pub fn record_window_result(&mut self, paid: u64, kept: u64) {
+        self.window_net_loss = self.window_net_loss.saturating_add(paid).saturating_sub(kept);
+    }
+}
+
+/// Cold storage for bankroll swept out of the hot treasury. Only
+/// `refill_treasury` moves lamports back out.
+#[account]
+pub struct ColdTreasury {
+    pub bump: u8,
+}
+
+// Space: 8 (discriminator) + 1 (bump) = 9
+impl ColdTreasury {
+    pub const SPACE: usize = 8 + 1;
+}
+
+/// Pending kill-switch drain of the treasuries; PDA closed when executed
+#[account]
+pub struct EmergencyDrain {
+    pub destination: Pubkey,
+    pub scheduled_at: i64,
+    pub executable_at: i64, // scheduled_at + EMERGENCY_DRAIN_TIMELOCK_SECS
+    pub bump: u8,
+}
+
+// Space: 8 (discriminator) + 32 (destination) + 8 (scheduled_at) + 8 (executable_at) + 1 (bump) = 57
+impl EmergencyDrain {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 1;
+
+    pub fn require_executable(&self, now: i64) -> Result<()> {
+        require!(now >= self.executable_at, ErrorCode::DrainTimelocked);
+        Ok(())
+    }
+}
+
+/// Voluntary player tips, kept apart from the treasury's bankroll
+#[account]
+pub struct TipVault {
+    pub bump: u8,
+}
+
+// Space: 8 (discriminator) + 1 (bump) = 9
+impl TipVault {
+    pub const SPACE: usize = 8 + 1;
+}
+
+/// Inputs of a player's latest settled round, for auditors to check without
+/// trusting the program; PDA seeded by the player. Draws are attested by the
+/// cluster's signature on the output, so they can't be recomputed from here.
+#[account]
+pub struct FairnessProof {
+    pub version: u8, // FAIRNESS_PROOF_VERSION
+    pub player: Pubkey,
+    pub computation_offset: u64, // Identifies the game, as in the game account
+    pub num_chests: u8,    // 0 for odds games
+    pub draw_range: u8,    // Chests drawn over, including a push chest; 0 for odds games
+    pub winning_chest: u8,
+    pub player_seed: [u8; 32],
+    pub entropy_slot: u64,   // Slot whose hash the circuit mixed in, 0 for none
+    pub slot_hash: [u8; 32], // All zeros unless `mix_slot_hash` was set
+    pub bump: u8,
+}
+
+// Space: 8 (discriminator) + 1 (version) + 32 (player) + 8 (offset) + 1 (chests) + 1 (draw range) + 1 (winning chest) + 32 (player seed) + 8 (entropy slot) + 32 (slot hash) + 1 (bump) = 125
+impl FairnessProof {
+    pub const SPACE: usize = 8 + 1 + 32 + 8 + 1 + 1 + 1 + 32 + 8 + 32 + 1;
+
+    pub fn record(&mut self, game: &GameAccount, draw_range: u8, winning_chest: u8) {
+        self.version = FAIRNESS_PROOF_VERSION;
+        self.player = game.player;
+        self.computation_offset = game.computation_offset;
+        self.num_chests = game.num_chests;
+        self.draw_range = draw_range;
+        self.winning_chest = winning_chest;
+        self.player_seed = game.player_seed;
+        self.entropy_slot = game.entropy_slot;
+        self.slot_hash = game.slot_hash;
+    }
+
+    /// Recomputes what the proof allows: the chest is in range
+    pub fn verify(&self) -> Result<()> {
+        require!(self.version == FAIRNESS_PROOF_VERSION, ErrorCode::FairnessProofMismatch);
+        if self.draw_range > 0 {
+            require!(self.winning_chest < self.draw_range, ErrorCode::FairnessProofMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Totals for the treasury staking pool. The PDA also holds distributed
+/// profit until stakers claim it with `unstake`.
+#[account]
+pub struct StakePool {
+    pub total_staked: u64,
+    pub profit_per_share: u128, // Profit per staked lamport so far, scaled by PROFIT_PRECISION
+    pub bump: u8,
+}
+
+// Space: 8 (discriminator) + 8 (total_staked) + 16 (profit_per_share) + 1 (bump) = 33
+impl StakePool {
+    pub const SPACE: usize = 8 + 8 + 16 + 1;
+
+    /// Splits `profit` over the current stakes
+    pub fn distribute(&mut self, profit: u64) -> Result<()> {
+        require!(self.total_staked > 0, ErrorCode::NoStakers);
+        let per_share = (profit as u128)
+            .checked_mul(PROFIT_PRECISION)
+            .ok_or(ErrorCode::Overflow)?
+            / self.total_staked as u128;
+        self.profit_per_share = self.profit_per_share.checked_add(per_share).ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+}
+
+/// One staker's contribution to the treasury; PDA seeded by the staker.
+/// Stakers share in distributed profits but not in house losses.
+#[account]
+pub struct Stake {
+    pub staker: Pubkey,
+    pub amount: u64,
+    pub profit_debt: u128,      // `amount * profit_per_share` already accounted for
+    pub unclaimed_profit: u64,  // Accrued but not yet paid out
+    pub bump: u8,
+}
+
+// Space: 8 (discriminator) + 32 (staker) + 8 (amount) + 16 (profit_debt) + 8 (unclaimed_profit) + 1 (bump) = 73
+impl Stake {
+    pub const SPACE: usize = 8 + 32 + 8 + 16 + 8 + 1;
+
+    fn accrued(&self, pool: &StakePool) -> Result<u128> {
+        Ok((self.amount as u128)
+            .checked_mul(pool.profit_per_share)
+            .ok_or(ErrorCode::Overflow)?)
+    }
+
+    /// Moves the profit distributed since the last update into `unclaimed_profit`.
+    /// Call before changing `amount`, then `reset_profit_debt` after.
+    pub fn accrue(&mut self, pool: &StakePool) -> Result<()> {
+        let earned = (self.accrued(pool)?.saturating_sub(self.profit_debt) / PROFIT_PRECISION) as u64;
+        self.unclaimed_profit = self.unclaimed_profit.checked_add(earned).ok_or(ErrorCode::Overflow)?;
+        self.reset_profit_debt(pool)
+    }
+
+    pub fn reset_profit_debt(&mut self, pool: &StakePool) -> Result<()> {
+        self.profit_debt = self.accrued(pool)?;
+        Ok(())
+    }
+}
+
+#[account]
+pub struct GameConfig {
+    pub authority: Pubkey,
+    pub max_payout: u64,      // Hard ceiling on a single payout in lamports (0 = uncapped)
+    pub promo_pubkey: Pubkey, // ed25519 key that signs promo codes (default = promos disabled)
+    pub round_in_favor_of_player: bool, // Round fractional lamports up (default false = down, in the house's favor)
+    pub entry_fee: u64,       // Flat, non-refundable lamports charged to the treasury on every play
+    pub push_enabled: bool,   // Adds a push chest to the draw that refunds the bet
+    pub insurance_bps: u16,   // Share of an insured bet refunded on a loss (0 = insurance off)
+    pub insurance_premium_bps: u16, // Premium charged on an insured bet, as a share of the bet
+    pub instant_payout_ceiling: u64, // Wins paying more are escrowed for `claim_delay_secs` (0 = always instant)
+    pub claim_delay_secs: i64,
+    pub block_authority_play: bool, // Reject plays signed by the treasury authority
+    pub max_bet: u64,         // Largest accepted bet in lamports (0 = unlimited)
+    pub max_loss_per_window: u64, // Circuit breaker: most net winnings paid per window (0 = off)
+    pub window_secs: i64,
+    pub tier_thresholds: [u64; LOYALTY_TIERS], // Lifetime wagered lamports to reach each tier (0 = tier unused)
+    pub tier_rebate_bps: [u16; LOYALTY_TIERS], // Entry fee rebate per tier
+    pub game_timeout_secs: i64, // Pending time before a game can be cancelled (0 = GAME_TIMEOUT_SECS)
+    pub hot_ceiling: u64,     // `rebalance_treasury` sweeps the hot balance above this to cold (0 = off)
+    pub hot_floor: u64,       // `rebalance_treasury` refuses to run with the hot balance below this
+    pub min_treasury_age_secs: i64, // New games wait until the treasury is this old (0 = off)
+    pub min_treasury_balance: u64,  // New games need the treasury to hold at least this (0 = off)
+    pub max_rng_range: u16,   // Largest MPC draw range a new game may need, bounding its cost (0 = off)
+    pub max_games_per_slot: u16, // New games accepted per slot across all players (0 = unlimited)
+    pub min_funding: u64,     // Smallest `fund_treasury` deposit in lamports (0 = MIN_FUNDING)
+    pub keeper_claim_grace_secs: i64, // Escrowed wins unclaimed this long past the delay can be claimed by a keeper (0 = off)
+    pub keeper_fee_bps: u16,  // Share of the escrow a keeper claim keeps, at most MAX_KEEPER_FEE_BPS
+    pub allowlist_only: bool, // Only allowlisted wallets may start games
+    pub win_cap_by_chests: [u64; WIN_CAP_SLOTS], // Most winnings paid per window for each `win_cap_slot` (0 = uncapped)
+    pub winnings_lock_slots: u64, // Every win is escrowed until this many slots after settlement (0 = off)
+    pub max_player_exposure: u64, // Most worst-case winnings one player may have unsettled (0 = uncapped)
+    pub payouts_frozen: bool, // Safe mode: wins are refunded as `Refunded` while losses still settle (`set_payouts_frozen`)
+    pub first_game_bonus_bps: u16, // Payout bonus on a player's first chest game, at most MAX_FIRST_GAME_BONUS_BPS
+    pub reveal_raw_draw: bool, // Chest circuits also reveal the draw before the player entropy shift
+    pub credit_wins_to_claim: bool, // Credit every win to the player's `PendingClaim` for `claim_winnings`
+    pub loss_route_bps: [u16; TREASURY_POOLS], // Weights of the hot and cold treasury in kept losing bets; all zeros keeps them hot
+    pub min_config_update_interval: i64, // Seconds `update_config` must wait after the previous update (0 = none)
+    pub last_config_update: i64,         // When the config was last initialized or updated
+    pub mix_slot_hash: bool, // Chest circuits also shift the draw by the latest slot hash, see `latest_slot_hash`
+    pub max_pending_games: u64, // Most games `Pending` at once across the program (0 = uncapped)
+    pub reward_mint: Pubkey, // Loyalty SPL token minted on each winning round, Pubkey::default() for none
+    pub reward_amount: u64,  // Reward per winning round, in the mint's base units
+    pub house_edge_bps: u16, // Taken off every win multiplier, between MIN_HOUSE_EDGE_BPS and MAX_HOUSE_EDGE_BPS
+    pub max_rake_per_game: u64, // Most lamports the house edge takes off one win (0 = uncapped)
+    pub bump: u8,
+}
+
+// Space: 8 (discriminator) + 32 (authority) + 8 (max_payout) + 32 (promo_pubkey) + 1 (rounding) + 8 (entry_fee) + 1 (push_enabled) + 2 (insurance) + 2 (premium) + 8 (payout ceiling) + 8 (claim delay) + 1 (block authority) + 8 (max_bet) + 8 (max loss) + 8 (window) + 24 (tier thresholds) + 6 (tier rebates) + 8 (game timeout) + 8 (hot ceiling) + 8 (hot floor) + 8 (min treasury age) + 8 (min treasury balance) + 2 (max rng range) + 2 (max games per slot) + 8 (min funding) + 8 (keeper claim grace) + 2 (keeper fee) + 1 (allowlist only) + 72 (win caps) + 8 (winnings lock slots) + 8 (max player exposure) + 1 (payouts frozen) + 2 (first game bonus) + 1 (reveal raw draw) + 1 (credit wins) + 4 (loss routing) + 8 (min update interval) + 8 (last update) + 1 (mix slot hash) + 8 (max pending games) + 32 (reward mint) + 8 (reward amount) + 2 (house edge) + 8 (max rake) + 1 (bump) = 401
+impl GameConfig {
+    pub const SPACE: usize =
+        8 + 32 + 8 + 32 + 1 + 8 + 1 + 2 + 2 + 8 + 8 + 1 + 8 + 8 + 8 + 8 * LOYALTY_TIERS + 2 * LOYALTY_TIERS + 8 + 8 + 8 + 8 + 8 + 2 + 2 + 8 + 8 + 2 + 1 + 8 * WIN_CAP_SLOTS + 8 + 8 + 1 + 2 + 1 + 1 + 2 * TREASURY_POOLS + 8 + 8 + 1 + 8 + 32 + 8 + 2 + 8 + 1;
+
+    /// Deadline for a game queued at `now` to settle before it can be cancelled
+    pub fn settle_by(&self, now: i64) -> i64 {
+        let timeout = if self.game_timeout_secs > 0 { self.game_timeout_secs } else { GAME_TIMEOUT_SECS };
+        now.saturating_add(timeout)
+    }
+
+    /// Rebate of the highest loyalty tier `total_wagered` has reached
+    pub fn loyalty_rebate_bps(&self, total_wagered: u64) -> u16 {
+        self.tier_thresholds
+            .iter()
+            .zip(self.tier_rebate_bps.iter())
+            .filter(|(threshold, _)| **threshold > 0 && total_wagered >= **threshold)
+            .map(|(_, rebate)| *rebate)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Entry fee for a player who has wagered `total_wagered` so far
+    pub fn entry_fee_for(&self, total_wagered: u64) -> u64 {
+        let rebate = self.loyalty_rebate_bps(total_wagered) as u128;
+        (self.entry_fee as u128 * (10_000 - rebate) / 10_000) as u64
+    }
+
+    pub fn validate_bet_cap(&self, bet_amount: u64) -> Result<()> {
+        require!(self.max_bet == 0 || bet_amount <= self.max_bet, ErrorCode::BetTooLarge);
+        Ok(())
+    }
+
+    /// Next bet of an auto-compounding game that just won `payout`, with that
+    /// bet's worst-case winnings. None, so the win is paid out as usual, if
+    /// the bet would break `max_bet` or the treasury's `available` lamports
+    /// couldn't cover both this win and the next round's worst case.
+    pub fn compounded_bet(&self, game: &GameAccount, payout: u64, available: u64) -> Result<Option<(u64, u64)>> {
+        if !game.auto_compound || self.validate_bet_cap(payout).is_err() {
+            return Ok(None);
+        }
+        // Promos and insurance were bought for the first round only
+        let worst_case = self
+            .worst_case_payout_for(payout, game.num_chests, game.win_mode, game.rounds_total, 0)?
+            .saturating_sub(payout);
+        let winnings = payout.saturating_sub(game.bet_amount);
+        let covered = available.checked_sub(winnings).is_some_and(|left| left >= worst_case);
+        Ok(covered.then_some((payout, worst_case)))
+    }
+
+    /// Worst-case treasury outlay of a single game the limits allow: a max
+    /// bet won as a best-of-`MAX_SERIES_ROUNDS` series on the most chests,
+    /// with the largest promo and first-game bonus, or its insured refund.
+    /// Unlimited bets are still bounded by `max_payout`; `None` when neither is set.
+    pub fn max_exposure(&self) -> Result<Option<u64>> {
+        if self.max_bet == 0 {
+            return Ok((self.max_payout > 0).then_some(self.max_payout));
+        }
+        let promo_bonus_bps = if self.promo_pubkey == Pubkey::default() { 0 } else { MAX_PROMO_BONUS_BPS };
+        let winnings = self
+            .worst_case_payout_for(
+                self.max_bet,
+                MAX_CHESTS,
+                WinMode::Exact as u8,
+                MAX_SERIES_ROUNDS,
+                promo_bonus_bps.saturating_add(self.first_game_bonus_bps),
+            )?
+            .saturating_sub(self.max_bet);
+        Ok(Some(winnings.max(self.insured_refund(self.max_bet))))
+    }
+
+    /// Rejects limits whose `max_exposure` the treasury's `liquid` lamports couldn't cover
+    pub fn validate_solvency(&self, liquid: u64) -> Result<()> {
+        if let Some(exposure) = self.max_exposure()? {
+            require!(liquid >= exposure, ErrorCode::ConfigWouldBeInsolvent);
+        }
+        Ok(())
+    }
+
+    /// Lamports refunded from the treasury when an insured bet loses
+    pub fn insured_refund(&self, bet_amount: u64) -> u64 {
+        (bet_amount as u128 * self.insurance_bps as u128 / 10_000) as u64
+    }
+
+    /// Lamports charged up front to insure a bet
+    pub fn insurance_premium(&self, bet_amount: u64) -> u64 {
+        (bet_amount as u128 * self.insurance_premium_bps as u128 / 10_000) as u64
+    }
+
+    /// Stamp an update at `now`, rejecting it within the interval the
+    /// current config sets, so a new config can't waive its own wait
+    pub fn record_update(&mut self, now: i64) -> Result<()> {
+        require!(
+            now >= self.last_config_update.saturating_add(self.min_config_update_interval),
+            ErrorCode::ConfigUpdateTooSoon
+        );
+        self.last_config_update = now;
+        Ok(())
+    }
+
+    pub fn apply(&mut self, params: &ConfigParams) {
+        self.max_payout = params.max_payout;
+        self.promo_pubkey = params.promo_pubkey;
+        self.round_in_favor_of_player = params.round_in_favor_of_player;
+        self.entry_fee = params.entry_fee;
+        self.push_enabled = params.push_enabled;
+        self.insurance_bps = params.insurance_bps;
+        self.insurance_premium_bps = params.insurance_premium_bps;
+        self.instant_payout_ceiling = params.instant_payout_ceiling;
+        self.claim_delay_secs = params.claim_delay_secs;
+        self.block_authority_play = params.block_authority_play;
+        self.max_bet = params.max_bet;
+        self.max_loss_per_window = params.max_loss_per_window;
+        self.window_secs = params.window_secs;
+        self.tier_thresholds = params.tier_thresholds;
+        self.tier_rebate_bps = params.tier_rebate_bps;
+        self.game_timeout_secs = params.game_timeout_secs;
+        self.hot_ceiling = params.hot_ceiling;
+        self.hot_floor = params.hot_floor;
+        self.min_treasury_age_secs = params.min_treasury_age_secs;
+        self.min_treasury_balance = params.min_treasury_balance;
+        self.max_rng_range = params.max_rng_range;
+        self.max_games_per_slot = params.max_games_per_slot;
+        self.min_funding = params.min_funding;
+        self.keeper_claim_grace_secs = params.keeper_claim_grace_secs;
+        self.keeper_fee_bps = params.keeper_fee_bps;
+        self.allowlist_only = params.allowlist_only;
+        self.win_cap_by_chests = params.win_cap_by_chests;
+        self.winnings_lock_slots = params.winnings_lock_slots;
+        self.max_player_exposure = params.max_player_exposure;
+        self.first_game_bonus_bps = params.first_game_bonus_bps;
+        self.reveal_raw_draw = params.reveal_raw_draw;
+        self.credit_wins_to_claim = params.credit_wins_to_claim;
+        self.loss_route_bps = params.loss_route_bps;
+        self.min_config_update_interval = params.min_config_update_interval;
+        self.mix_slot_hash = params.mix_slot_hash;
+        self.max_pending_games = params.max_pending_games;
+        self.reward_mint = params.reward_mint;
+        self.reward_amount = params.reward_amount;
+        self.house_edge_bps = params.house_edge_bps;
+        self.max_rake_per_game = params.max_rake_per_game;
+    }
+
+    /// The (slot, hash) a chest draw mixes in: the newest SlotHashes entry
+    /// with `mix_slot_hash` set, otherwise slot 0 and all zeros, which
+    /// leaves the draw unshifted
+    pub fn draw_slot_hash(&self, slot_hashes: &AccountInfo) -> Result<(u64, [u8; 32])> {
+        if !self.mix_slot_hash {
+            return Ok((0, [0u8; 32]));
+        }
+        latest_slot_hash(slot_hashes)
+    }
+
+    /// Whether the loss circuit breaker or any per-chest-count win cap needs the window
+    pub fn uses_loss_window(&self) -> bool {
+        self.max_loss_per_window > 0 || self.win_cap_by_chests.iter().any(|cap| *cap > 0)
+    }
+
+    /// Outcomes a chest game draws from: one per chest, plus the push chest when enabled
+    pub fn chest_draw_range(&self, num_chests: u8) -> u8 {
+        if self.push_enabled { num_chests + 1 } else { num_chests }
+    }
+
+    /// Whether a covered win of `payout` goes to the player's `PendingClaim`
+    /// rather than their wallet: every win under `credit_wins_to_claim` or a
+    /// slot lock, otherwise those over `instant_payout_ceiling`
+    pub fn escrows_payout(&self, payout: u64) -> bool {
+        self.credit_wins_to_claim
+            || self.winnings_lock_slots > 0
+            || (self.instant_payout_ceiling > 0 && payout > self.instant_payout_ceiling)
+    }
+
+    /// Part of a kept losing bet `loss_route_bps` sends to the cold treasury
+    pub fn cold_loss_share(&self, kept: u64) -> u64 {
+        (kept as u128 * self.loss_route_bps[1] as u128 / 10_000) as u64
+    }
+
+    /// Treasuries other than the hot one that settlement routes losses to,
+    /// passed to the callback after the fee split recipients
+    pub fn loss_route_accounts(&self) -> Vec<Pubkey> {
+        if self.loss_route_bps[1] > 0 { vec![cold_treasury_pda().0] } else { vec![] }
+    }
+
+    /// What `mint_win_reward` needs, passed to the callback after the loss
+    /// routes: the mint authority PDA, the mint, the player's associated
+    /// token account and the token program. Empty with no `reward_mint`.
+    pub fn reward_accounts(&self, player: &Pubkey) -> Vec<CallbackAccount> {
+        if self.reward_mint == Pubkey::default() {
+            return vec![];
+        }
+        let account = |pubkey: Pubkey, is_writable: bool| CallbackAccount { pubkey, is_writable };
+        vec![
+            account(reward_authority_pda().0, false),
+            account(self.reward_mint, true),
+            account(get_associated_token_address(player, &self.reward_mint), true),
+            account(token::ID, false),
+        ]
+    }
+
+    /// Bonus a player's next game gets: `first_game_bonus_bps` until their
+    /// first game has started, then nothing
+    pub fn first_game_bonus_for(&self, stats: &PlayerStats) -> u16 {
+        if stats.games_played == 0 { self.first_game_bonus_bps } else { 0 }
+    }
+
+    /// Chance a single chest round wins under `win_mode`, in basis points,
+    /// counting the push chest as a non-win when pushes are enabled
+    pub fn chest_win_probability_bps(&self, num_chests: u8, win_mode: u8) -> u16 {
+        let draw_range = self.chest_draw_range(num_chests) as u16;
+        winning_chests(win_mode) as u16 * 10_000 / draw_range
+    }
+
+    /// Expected share of `bet_amount` paid back by a single exact-chest round:
+    /// a win pays num_chests x less `house_edge_bps`, with the edge capped at
+    /// `max_rake_per_game`, and a push refunds the bet. A zero bet leaves the
+    /// cap out, giving the RTP of a bet too small to reach it. Ignores
+    /// `max_payout`, entry fees and insurance.
+    pub fn rtp_bps(&self, num_chests: u8, bet_amount: u64) -> u16 {
+        let draw_range = self.chest_draw_range(num_chests) as u64;
+        // The edge a win takes, in basis points of the bet
+        let mut edge = num_chests as u64 * self.house_edge_bps.min(10_000) as u64;
+        if self.max_rake_per_game > 0 && bet_amount > 0 {
+            edge = edge.min(self.max_rake_per_game.saturating_mul(10_000) / bet_amount);
+        }
+        let win = num_chests as u64 * 10_000 - edge;
+        let push = if self.push_enabled { 10_000 } else { 0 };
+        ((win + push) / draw_range) as u16
+    }
+
+    /// Rejects a game whose MPC draw would span more than `max_rng_range` outcomes
+    pub fn validate_rng_range(&self, draw_range: u16) -> Result<()> {
+        require!(
+            self.max_rng_range == 0 || draw_range <= self.max_rng_range,
+            ErrorCode::RngRangeTooLarge
+        );
+        Ok(())
+    }
+
+    /// Rejects zero and dust deposits, which only add noise to the funding ledger
+    pub fn validate_funding(&self, amount: u64) -> Result<()> {
+        let floor = if self.min_funding > 0 { self.min_funding } else { MIN_FUNDING };
+        require!(amount >= floor, ErrorCode::FundingTooSmall);
+        Ok(())
+    }
+
+    /// Keeper claims open `keeper_claim_grace_secs` after an escrow became
+    /// claimable, so the player always gets the first chance to claim fee-free
+    pub fn validate_keeper_claim(&self, claimable_at: i64, now: i64) -> Result<()> {
+        require!(self.keeper_claim_grace_secs > 0, ErrorCode::KeeperClaimsDisabled);
+        require!(
+            now >= claimable_at.saturating_add(self.keeper_claim_grace_secs),
+            ErrorCode::ClaimNotReady
+        );
+        Ok(())
+    }
+
+    /// Lamports a keeper keeps for claiming an `amount` escrow on a player's behalf
+    pub fn keeper_fee(&self, amount: u64) -> u64 {
+        (amount as u128 * self.keeper_fee_bps as u128 / 10_000) as u64
+    }
+
+    /// Blocks new games on a treasury younger than `min_treasury_age_secs`
+    /// or holding less than `min_treasury_balance` above rent, so a fresh
+    /// deployment can't be played before it has been properly funded
+    pub fn validate_treasury_ready(&self, treasury_created_at: i64, available: u64, now: i64) -> Result<()> {
+        require!(
+            now.saturating_sub(treasury_created_at) >= self.min_treasury_age_secs
+                && available >= self.min_treasury_balance,
+            ErrorCode::TreasuryNotReady
+        );
+        Ok(())
+    }
+
+    /// Lamports `rebalance_treasury` should sweep to cold storage from a hot
+    /// treasury holding `available` above rent. Fails below `hot_floor`, where
+    /// the treasury needs a manual refill instead.
+    pub fn hot_excess(&self, available: u64) -> Result<u64> {
+        require!(available >= self.hot_floor, ErrorCode::TreasuryBelowFloor);
+        if self.hot_ceiling == 0 {
+            return Ok(0);
+        }
+        Ok(available.saturating_sub(self.hot_ceiling))
+    }
+
+    /// Gross payout on a win: bet * fair multiplier (num_chests for a single
+    /// exact game, num_chests / 3 for near-miss, see `series_odds` for
+    /// best-of-N), plus any promo bonus, clamped to `max_payout`. A win never
+    /// returns less than the original bet.
+    /// Fractional lamports are rounded once, per `round_in_favor_of_player`.
+    pub fn payout_for(
+        &self,
+        bet_amount: u64,
+        num_chests: u8,
+        win_mode: u8,
+        rounds_total: u8,
+        bonus_bps: u16,
+    ) -> Result<u64> {
+        self.payout_rounded(
+            bet_amount,
+            num_chests,
+            win_mode,
+            rounds_total,
+            bonus_bps,
+            self.round_in_favor_of_player,
+        )
+    }
+
+    /// Largest payout a win could ever produce, i.e. always rounding in the
+    /// player's favor. Used for the treasury exposure check.
+    pub fn worst_case_payout_for(
+        &self,
+        bet_amount: u64,
+        num_chests: u8,
+        win_mode: u8,
+        rounds_total: u8,
+        bonus_bps: u16,
+    ) -> Result<u64> {
+        self.payout_rounded(bet_amount, num_chests, win_mode, rounds_total, bonus_bps, true)
+    }
+
+    /// Gross payout on an odds game win: bet * 10000 / `win_probability_bps`,
+    /// rounded and clamped like `payout_for`
+    pub fn odds_payout_for(&self, bet_amount: u64, win_probability_bps: u16) -> Result<u64> {
+        self.payout_at_odds(
+            bet_amount,
+            (10_000, win_probability_bps as u64),
+            0,
+            self.round_in_favor_of_player,
+        )
+    }
+
+    /// `worst_case_payout_for` for odds games
+    pub fn worst_case_odds_payout_for(&self, bet_amount: u64, win_probability_bps: u16) -> Result<u64> {
+        self.payout_at_odds(bet_amount, (10_000, win_probability_bps as u64), 0, true)
+    }
+
+    fn payout_rounded(
+        &self,
+        bet_amount: u64,
+        num_chests: u8,
+        win_mode: u8,
+        rounds_total: u8,
+        bonus_bps: u16,
+        round_up: bool,
+    ) -> Result<u64> {
+        let odds = series_odds(num_chests, winning_chests(win_mode), rounds_total);
+        self.payout_at_odds(bet_amount, odds, bonus_bps, round_up)
+    }
+
+    fn payout_at_odds(
+        &self,
+        bet_amount: u64,
+        (outcomes, winning_outcomes): (u64, u64),
+        bonus_bps: u16,
+        round_up: bool,
+    ) -> Result<u64> {
+        // bet * (outcomes / winning) * (1 - edge) * (1 + bonus), as one
+        // fraction so we only round once
+        let edge = 10_000 - self.house_edge_bps.min(10_000) as u128;
+        let stake_odds = bet_amount as u128 * outcomes as u128 * (10_000 + bonus_bps as u128);
+        let mut numerator = stake_odds * edge;
+        let denominator = winning_outcomes as u128 * 10_000 * 10_000;
+        if self.max_rake_per_game > 0 {
+            // The edge takes at most `max_rake_per_game` off the fair payout
+            let fair = stake_odds * 10_000;
+            numerator = numerator.max(fair.saturating_sub(self.max_rake_per_game as u128 * denominator));
+        }
+        let mut gross = numerator / denominator;
+        if round_up && numerator % denominator != 0 {
+            gross += 1;
+        }
+        // The edge never turns a win on long odds into less than the stake
+        let gross = u64::try_from(gross).map_err(|_| ErrorCode::Overflow)?.max(bet_amount);
+        if self.max_payout == 0 {
+            return Ok(gross);
+        }
+        Ok(gross.min(self.max_payout).max(bet_amount))
+    }
+}
+
+/// Global KPIs so operators can read one account instead of every game
+#[account]
+pub struct HouseStats {
+    pub total_games: u64,
+    pub total_wagered: u64,
+    pub total_paid_out: u64,
+    pub house_profit: i64, // total_wagered - total_paid_out, negative when the house is down
+    pub total_cancelled: u64, // Refunded pending games, a proxy for MPC cluster failures
+    pub rng_failures: u64,    // Draws whose rejection sampling ran out of attempts, for tuning them
+    pub active_pending_games: u64, // Games in `Pending` right now, capped by `GameConfig::max_pending_games`
+    pub bump: u8,
+}
+
+// Space: 8 (discriminator) + 8 (games) + 8 (wagered) + 8 (paid out) + 8 (profit) + 8 (cancelled) + 8 (rng failures) + 8 (pending games) + 1 (bump) = 65
+impl HouseStats {
+    pub const SPACE: usize = 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1;
+
+    /// Count a game entering `Pending`, unless `max_pending_games` (0 =
+    /// uncapped) are already waiting on a result
+    pub fn record_pending_start(&mut self, max_pending_games: u64) -> Result<()> {
+        require!(
+            max_pending_games == 0 || self.active_pending_games < max_pending_games,
+            ErrorCode::TooManyPendingGames
+        );
+        self.active_pending_games = self.active_pending_games.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Count a game leaving `Pending`. Saturates, since games queued before
+    /// the counter existed were never counted.
+    pub fn record_pending_end(&mut self) {
+        self.active_pending_games = self.active_pending_games.saturating_sub(1);
+    }
+
+    /// Record a circuit draw that fell back; the game is also refunded and
+    /// counted by `record_cancelled`
+    pub fn record_rng_failure(&mut self) -> Result<()> {
+        self.rng_failures = self.rng_failures.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Record a refunded pending game; it never counts towards wagered or
+    /// paid out
+    pub fn record_cancelled(&mut self) -> Result<()> {
+        self.total_cancelled = self.total_cancelled.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        self.record_pending_end();
+        Ok(())
+    }
+
+    /// Record a settled game; `payout` is the gross amount returned to the player
+    pub fn record_game(&mut self, bet_amount: u64, payout: u64) -> Result<()> {
+        self.total_games = self.total_games.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        self.total_wagered = self
+            .total_wagered
+            .checked_add(bet_amount)
+            .ok_or(ErrorCode::Overflow)?;
+        self.total_paid_out = self
+            .total_paid_out
+            .checked_add(payout)
+            .ok_or(ErrorCode::Overflow)?;
+        let net = i64::try_from(bet_amount as i128 - payout as i128).map_err(|_| ErrorCode::Overflow)?;
+        self.house_profit = self.house_profit.checked_add(net).ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+}
+
+/// Ring buffer of the last `RESULT_LOG_CAPACITY` settled games, so anyone can
+/// spot-check outcomes without an indexer. Zero-copy to keep the callback cheap.
+#[account(zero_copy)]
+pub struct ResultLog {
+    pub head: u32, // Slot the next entry is written to
+    pub len: u32,  // Filled slots, up to the capacity
+    pub bump: u8,
+    pub _padding: [u8; 7],
+    pub entries: [ResultEntry; RESULT_LOG_CAPACITY],
+}
+
+#[zero_copy]
+#[derive(Default)]
+pub struct ResultEntry {
+    pub player: Pubkey,
+    pub bet_amount: u64,
+    pub player_won: u8,
+    pub winning_chest: u8,
+    pub _padding: [u8; 6],
+}
+
+// Space: 8 (discriminator) + 4 (head) + 4 (len) + 1 (bump) + 7 (padding) + 16 * 48 (entries) = 792
+impl ResultLog {
+    pub const SPACE: usize = 8 + 4 + 4 + 1 + 7 + RESULT_LOG_CAPACITY * 48;
+
+    /// Append an entry, overwriting the oldest once full
+    pub fn push(&mut self, entry: ResultEntry) {
+        self.entries[self.head as usize] = entry;
+        self.head = (self.head + 1) % RESULT_LOG_CAPACITY as u32;
+        self.len = (self.len + 1).min(RESULT_LOG_CAPACITY as u32);
+    }
+
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+        self.entries = [ResultEntry::default(); RESULT_LOG_CAPACITY];
+    }
+}
+
+/// Operator-tunable settings, shared by `init_config` and `update_config`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ConfigParams {
+    pub max_payout: u64,
+    pub promo_pubkey: Pubkey,
+    pub round_in_favor_of_player: bool,
+    pub entry_fee: u64,
+    pub push_enabled: bool,
+    pub insurance_bps: u16,
+    pub insurance_premium_bps: u16,
+    pub instant_payout_ceiling: u64,
+    pub claim_delay_secs: i64,
+    pub block_authority_play: bool,
+    pub max_bet: u64,
+    pub max_loss_per_window: u64,
+    pub window_secs: i64,
+    pub tier_thresholds: [u64; LOYALTY_TIERS],
+    pub tier_rebate_bps: [u16; LOYALTY_TIERS],
+    pub game_timeout_secs: i64,
+    pub hot_ceiling: u64,
+    pub hot_floor: u64,
+    pub min_treasury_age_secs: i64,
+    pub min_treasury_balance: u64,
+    pub max_rng_range: u16,
+    pub max_games_per_slot: u16,
+    pub min_funding: u64,
+    pub keeper_claim_grace_secs: i64,
+    pub keeper_fee_bps: u16,
+    pub allowlist_only: bool,
+    pub win_cap_by_chests: [u64; WIN_CAP_SLOTS],
+    pub winnings_lock_slots: u64,
+    pub max_player_exposure: u64,
+    pub first_game_bonus_bps: u16,
+    pub reveal_raw_draw: bool,
+    pub credit_wins_to_claim: bool,
+    pub loss_route_bps: [u16; TREASURY_POOLS],
+    pub min_config_update_interval: i64,
+    pub mix_slot_hash: bool,
+    pub max_pending_games: u64,
+    pub reward_mint: Pubkey,
+    pub reward_amount: u64,
+    pub house_edge_bps: u16,
+    pub max_rake_per_game: u64,
+}
+
+impl ConfigParams {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.insurance_bps <= 10_000 && self.insurance_premium_bps <= 10_000,
+            ErrorCode::InvalidConfig
+        );
+        require!(
+            self.claim_delay_secs >= 0
+                && self.game_timeout_secs >= 0
+                && self.min_treasury_age_secs >= 0
+                && self.keeper_claim_grace_secs >= 0
+                && (0..=MAX_CONFIG_UPDATE_INTERVAL_SECS).contains(&self.min_config_update_interval),
+            ErrorCode::InvalidConfig
+        );
+        require!(self.keeper_fee_bps <= MAX_KEEPER_FEE_BPS, ErrorCode::InvalidConfig);
+        require!(self.first_game_bonus_bps <= MAX_FIRST_GAME_BONUS_BPS, ErrorCode::InvalidConfig);
+        require!(
+            (self.max_loss_per_window == 0 && self.win_cap_by_chests.iter().all(|cap| *cap == 0))
+                || self.window_secs > 0,
+            ErrorCode::InvalidConfig
+        );
+        require!(
+            self.tier_rebate_bps.iter().all(|bps| *bps <= 10_000),
+            ErrorCode::InvalidConfig
+        );
+        require!(
+            self.hot_ceiling == 0 || self.hot_floor <= self.hot_ceiling,
+            ErrorCode::InvalidConfig
+        );
+        let route_bps: u32 = self.loss_route_bps.iter().map(|bps| *bps as u32).sum();
+        require!(route_bps == 0 || route_bps == 10_000, ErrorCode::InvalidLossRouting);
+        require!(
+            (MIN_HOUSE_EDGE_BPS..=MAX_HOUSE_EDGE_BPS).contains(&self.house_edge_bps),
+            ErrorCode::HouseEdgeOutOfBounds
+        );
+        Ok(())
+    }
+}
+
+/// Returned by `quote_game`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct GameQuote {
+    pub max_payout: u64,          // Gross amount paid back on a win (stake included)
+    pub entry_fee: u64,           // Charged on top of the bet at play time
+    pub net_winnings: u64,        // Player profit on a win: payout - bet - entry_fee
+    pub treasury_can_cover: bool, // Whether the treasury could cover the win right now
+}
+
+/// Returned by `get_stats`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct PlayerStatsView {
+    pub stats_version: u8, // Account layout the fields were read from
+    pub player: Pubkey,
+    pub games_played: u64,
+    pub total_wagered: u64,
+    pub total_won: u64,
+    pub net_pnl: i64, // total_won - total_wagered, saturating
+    pub outstanding_exposure: u64,
+}
+
+/// Returned by `get_full_state`; fits in MAX_RETURN_DATA
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct FullStateView {
+    pub game: Option<GameAccount>, // None before the player's first game
+    pub stats: Option<PlayerStatsView>,
+    pub config: GameConfig,
+}
+
+/// Ephemeral key allowed to bet on a player's behalf; holds the escrowed spend cap
+#[account]
+pub struct SessionKey {
+    pub player: Pubkey,
+    pub session_key: Pubkey,
+    pub expires_at: i64,
+    pub spend_cap: u64, // Lamports escrowed at creation, the most the session can bet
+    pub spent: u64,
+    pub bump: u8,
+}
+
+// Space: 8 (discriminator) + 32 (player) + 32 (session_key) + 8 (expires_at) + 8 (spend_cap) + 8 (spent) + 1 (bump) = 97
+impl SessionKey {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 1;
+}
+
+/// Escrow for a player's large wins, paid out by `claim_winnings` after the claim delay
+#[account]
+pub struct PendingClaim {
+    pub player: Pubkey,
+    pub amount: u64,       // Escrowed lamports, on top of the account's rent
+    pub claimable_at: i64, // Pushed back by every new escrowed win
+    pub bump: u8,
+    pub owed: u64, // Winnings the treasury couldn't cover at settlement, not held here
+    pub claimable_slot: u64, // Also pushed back by every new escrowed win, under `winnings_lock_slots`
+}
+
+// Space: 8 (discriminator) + 32 (player) + 8 (amount) + 8 (claimable_at) + 1 (bump) + 8 (owed) + 8 (claimable_slot) = 73
+impl PendingClaim {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 1 + 8 + 8;
+
+    /// Escrow a win: `funded` lamports moved into this account now, plus
+    /// `owed` winnings the treasury still has to pay
+    pub fn escrow(&mut self, funded: u64, owed: u64, claimable_at: i64, claimable_slot: u64) -> Result<()> {
+        self.amount = self.amount.checked_add(funded).ok_or(ErrorCode::Overflow)?;
+        self.owed = self.owed.checked_add(owed).ok_or(ErrorCode::Overflow)?;
+        self.claimable_at = claimable_at;
+        self.claimable_slot = claimable_slot;
+        Ok(())
+    }
+
+    /// The escrow opens once both its time delay and its slot lock have passed
+    pub fn require_claimable(&self, now: i64, slot: u64) -> Result<()> {
+        require!(
+            now >= self.claimable_at && slot >= self.claimable_slot,
+            ErrorCode::ClaimNotReady
+        );
+        Ok(())
+    }
+
+    /// Settle as much of `owed` as a treasury with `available` lamports can, returning the amount
+    pub fn take_owed(&mut self, available: u64) -> u64 {
+        let paid = self.owed.min(available);
+        self.owed -= paid;
+        paid
+    }
+}
+
+/// Operator access flags for one wallet. A wallet without this PDA is
+/// neither blocked nor allowlisted.
+#[account]
+pub struct PlayerAccess {
+    pub player: Pubkey,
+    pub blocked: bool, // Rejected by every new-game instruction
+    pub allowed: bool, // Allowlisted for `GameConfig::allowlist_only` mode
+    pub bump: u8,
+}
+
+// Space: 8 (discriminator) + 32 (player) + 1 (blocked) + 1 (allowed) + 1 (bump) = 43
+impl PlayerAccess {
+    pub const SPACE: usize = 8 + 32 + 1 + 1 + 1;
+}
+
+/// A shared-pot game in flight; PDA seeded by its computation offset
+#[account]
+pub struct PotGame {
+    pub players: [Pubkey; MAX_POT_PLAYERS], // Unused slots are Pubkey::default()
+    pub num_players: u8,
+    pub num_chests: u8,
+    pub bet_amount: u64, // Staked by each player
+    pub computation_offset: u64,
+    pub status: u8, // GameStatus
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+// Space: 8 (discriminator) + 96 (players) + 1 (num_players) + 1 (num_chests) + 8 (bet) + 8 (offset) + 1 (status) + 8 (created) + 1 (bump) = 132
+impl PotGame {
+    pub const SPACE: usize = 8 + 32 * MAX_POT_PLAYERS + 1 + 1 + 8 + 8 + 1 + 8 + 1;
+}
+
+/// One player's encrypted chest choice for `play_pot_game`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PotEntry {
+    pub choice: [u8; 32], // Encrypted chest choice
+    pub pub_key: [u8; 32], // The player's encryption pubkey
+    pub nonce: u128,
+}
+
+/// Per-player volume, used to pick the player's loyalty tier
+#[account]
+pub struct PlayerStats {
+    pub player: Pubkey,
+    pub total_wagered: u64, // Lamports bet across all games, counted when the bet is placed
+    pub bump: u8,
+    pub total_won: u64, // Lamports paid back on settlement or refunded, bet included
+    pub outstanding_exposure: u64, // Worst-case winnings of the player's unsettled games
+    pub games_played: u64,         // Games started; series rounds and top-ups don't count
+    pub stats_version: u8,         // PLAYER_STATS_VERSION
+}
+
+// Space: 8 (discriminator) + 32 (player) + 8 (total_wagered) + 1 (bump) + 8 (total_won) + 8 (outstanding exposure) + 8 (games played) + 1 (stats version) = 74
+impl PlayerStats {
+    pub const SPACE: usize = 8 + 32 + 8 + 1 + 8 + 8 + 8 + 1;
+
+    /// Snapshot returned by `get_stats`
+    pub fn view(&self) -> PlayerStatsView {
+        PlayerStatsView {
+            stats_version: self.stats_version,
+            player: self.player,
+            games_played: self.games_played,
+            total_wagered: self.total_wagered,
+            total_won: self.total_won,
+            net_pnl: self.net_pnl(),
+            outstanding_exposure: self.outstanding_exposure,
+        }
+    }
+
+    /// Count a new game and its bet
+    pub fn record_game_start(&mut self, bet_amount: u64) -> Result<()> {
+        self.games_played = self.games_played.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        self.record_wager(bet_amount)
+    }
+
+    pub fn record_wager(&mut self, bet_amount: u64) -> Result<()> {
+        self.total_wagered = self.total_wagered.checked_add(bet_amount).ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    pub fn record_payout(&mut self, payout: u64) -> Result<()> {
+        self.total_won = self.total_won.checked_add(payout).ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Reserve `exposure` for `game`, replacing whatever it already held, and
+    /// reject it if the player's total would pass `cap` (0 = uncapped)
+    pub fn reserve_exposure(&mut self, game: &mut GameAccount, exposure: u64, cap: u64) -> Result<()> {
+        let outstanding = self
+            .outstanding_exposure
+            .saturating_sub(game.exposure)
+            .checked_add(exposure)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(cap == 0 || outstanding <= cap, ErrorCode::PlayerExposureExceeded);
+        self.outstanding_exposure = outstanding;
+        game.exposure = exposure;
+        Ok(())
+    }
+
+    /// Drop `game`'s reservation once it can no longer pay out
+    pub fn release_exposure(&mut self, game: &mut GameAccount) {
+        self.outstanding_exposure = self.outstanding_exposure.saturating_sub(game.exposure);
+        game.exposure = 0;
+    }
+
+    /// Lifetime profit (positive) or loss, saturating at the i64 bounds
+    pub fn net_pnl(&self) -> i64 {
+        let pnl = self.total_won as i128 - self.total_wagered as i128;
+        pnl.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+    }
+}
+
+/// Beneficiaries that each take a share of every losing bet from the treasury
+#[account]
+pub struct FeeSplit {
+    pub shares: Vec<FeeShare>, // At most MAX_FEE_RECIPIENTS, bps summing to <= 10000
+    pub bump: u8,
+}
+
+// Space: 8 (discriminator) + 4 (vec len) + MAX_FEE_RECIPIENTS * (32 (recipient) + 2 (bps)) + 1 (bump) = 149
+impl FeeSplit {
+    pub const SPACE: usize = 8 + 4 + MAX_FEE_RECIPIENTS * (32 + 2) + 1;
+
+    pub fn validate(shares: &[FeeShare]) -> Result<()> {
+        let total_bps: u32 = shares.iter().map(|share| share.bps as u32).sum();
+        require!(
+            shares.len() <= MAX_FEE_RECIPIENTS && total_bps <= 10_000,
+            ErrorCode::InvalidFeeSplit
+        );
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct FeeShare {
+    pub recipient: Pubkey, // Must already be a rent-exempt account to receive small shares
+    pub bps: u16,
+}
+
+impl FeeShare {
+    /// This recipient's cut of `lost` lamports, rounded down
+    pub fn amount_of(&self, lost: u64) -> u64 {
+        (lost as u128 * self.bps as u128 / 10_000) as u64
+    }
+}
+
+/// Off-chain promo code, signed by `GameConfig::promo_pubkey`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PromoPayload {
+    pub code_id: [u8; 16],
+    pub bonus_bps: u16,  // Bonus on top of the gross payout, e.g. 1000 = +10%
+    pub player: Pubkey,  // Only this wallet can redeem the code
+    pub expires_at: i64, // Unix time after which the code can't be redeemed
+}
+
+/// Marks a promo code as used; PDA seeded by the code id
+#[account]
+pub struct PromoRedemption {
+    pub code_id: [u8; 16],
+    pub player: Pubkey,
+    pub redeemed_at: i64,
+    pub bump: u8,
+}
+
+// Space: 8 (discriminator) + 16 (code_id) + 32 (player) + 8 (redeemed_at) + 1 (bump) = 65
+impl PromoRedemption {
+    pub const SPACE: usize = 8 + 16 + 32 + 8 + 1;
+}
+
+// ============= Context Structs =============
+
+#[derive(Accounts)]
+pub struct InitTreasury<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = Treasury::SPACE,
+        seeds = [TREASURY_SEED],
+        bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = GameConfig::SPACE,
+        seeds = [CONFIG_SEED],
+        bump,
+    )]
+    pub config: Account<'info, GameConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitHouseStats<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        init,
+        payer = authority,
+        space = HouseStats::SPACE,
+        seeds = [HOUSE_STATS_SEED],
+        bump,
+    )]
+    pub house_stats: Account<'info, HouseStats>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitResultLog<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        init,
+        payer = authority,
+        space = ResultLog::SPACE,
+        seeds = [RESULT_LOG_SEED],
+        bump,
+    )]
+    pub result_log: AccountLoader<'info, ResultLog>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClearResultLog<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub config: Account<'info, GameConfig>,
+    #[account(
+        mut,
+        seeds = [RESULT_LOG_SEED],
+        bump = result_log.load()?.bump,
+    )]
+    pub result_log: AccountLoader<'info, ResultLog>,
+}
+
+#[derive(Accounts)]
+#[instruction(player: Pubkey)]
+pub struct SetPlayerAccess<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub config: Account<'info, GameConfig>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = PlayerAccess::SPACE,
+        seeds = [PLAYER_ACCESS_SEED, player.as_ref()],
+        bump,
+    )]
+    pub player_access: Account<'info, PlayerAccess>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitFeeSplit<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub config: Account<'info, GameConfig>,
+    #[account(
+        init,
+        payer = authority,
+        space = FeeSplit::SPACE,
+        seeds = [FEE_SPLIT_SEED],
+        bump,
+    )]
+    pub fee_split: Account<'info, FeeSplit>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFeeSplit<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub config: Account<'info, GameConfig>,
+    #[account(
+        mut,
+        seeds = [FEE_SPLIT_SEED],
+        bump = fee_split.bump,
+    )]
+    pub fee_split: Account<'info, FeeSplit>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub config: Account<'info, GameConfig>,
+
+    #[account(
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+}
+
+#[derive(Accounts)]
+pub struct SetPayoutsFrozen<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub config: Account<'info, GameConfig>,
+}
+
+#[derive(Accounts)]
+pub struct FundTreasury<'info> {
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, GameConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RebalanceTreasury<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, GameConfig>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = ColdTreasury::SPACE,
+        seeds = [COLD_TREASURY_SEED],
+        bump,
+    )]
+    pub cold_treasury: Account<'info, ColdTreasury>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefillTreasury<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        mut,
+        seeds = [COLD_TREASURY_SEED],
+        bump = cold_treasury.bump,
+    )]
+    pub cold_treasury: Account<'info, ColdTreasury>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTips<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        mut,
+        seeds = [TIP_VAULT_SEED],
+        bump = tip_vault.bump,
+    )]
+    pub tip_vault: Account<'info, TipVault>,
+}
+
+#[derive(Accounts)]
+pub struct SetReserve<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, GameConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ScheduleEmergencyDrain<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = EmergencyDrain::SPACE,
+        seeds = [EMERGENCY_DRAIN_SEED],
+        bump,
+    )]
+    pub emergency_drain: Account<'info, EmergencyDrain>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteEmergencyDrain<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        mut,
+        seeds = [COLD_TREASURY_SEED],
+        bump = cold_treasury.bump,
+    )]
+    pub cold_treasury: Option<Account<'info, ColdTreasury>>,
+    #[account(
+        mut,
+        seeds = [EMERGENCY_DRAIN_SEED],
+        bump = emergency_drain.bump,
+        close = authority,
+    )]
+    pub emergency_drain: Account<'info, EmergencyDrain>,
+    /// CHECK: Only receives lamports; must be the scheduled destination
+    #[account(mut, address = emergency_drain.destination @ ErrorCode::Unauthorized)]
+    pub destination: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct StakeTreasury<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        init_if_needed,
+        payer = staker,
+        space = StakePool::SPACE,
+        seeds = [STAKE_POOL_SEED],
+        bump,
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+    #[account(
+        init_if_needed,
+        payer = staker,
+        space = Stake::SPACE,
+        seeds = [STAKE_SEED, staker.key().as_ref()],
+        bump,
+    )]
+    pub stake: Account<'info, Stake>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnstakeTreasury<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED],
+        bump = stake_pool.bump,
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+    #[account(
+        mut,
+        seeds = [STAKE_SEED, staker.key().as_ref()],
+        bump = stake.bump,
+    )]
+    pub stake: Account<'info, Stake>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeProfits<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED],
+        bump = stake_pool.bump,
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteGame<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, GameConfig>,
+    #[account(
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteRtp<'info> {
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, GameConfig>,
+}
+
+#[derive(Accounts)]
+pub struct GetFullState<'info> {
+    /// CHECK: only used to derive the player's PDAs
+    pub player: AccountInfo<'info>,
+
+    #[account(
+        seeds = [GAME_SEED, player.key().as_ref()],
+        bump = game_account.bump,
+    )]
+    pub game_account: Option<Account<'info, GameAccount>>,
+
+    #[account(
+        seeds = [PLAYER_STATS_SEED, player.key().as_ref()],
+        bump = player_stats.bump,
+    )]
+    pub player_stats: Option<Account<'info, PlayerStats>>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, GameConfig>,
+}
+
+#[derive(Accounts)]
+pub struct GetStats<'info> {
+    #[account(
+        seeds = [PLAYER_STATS_SEED, player_stats.player.as_ref()],
+        bump = player_stats.bump,
+    )]
+    pub player_stats: Account<'info, PlayerStats>,
+}
+
+#[queue_computation_accounts("play_chest_game", player)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, num_chests: u8, bet_amount: u64)]
+pub struct PlayChestGame<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = GameAccount::SPACE,
+        seeds = [GAME_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub game_account: Box<Account<'info, GameAccount>>,
+
+    /// CHECK: the player's `PlayerAccess` PDA, read only if it exists
+    #[account(
+        seeds = [PLAYER_ACCESS_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub player_access: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+    )]
+    pub treasury: Box<Account<'info, Treasury>>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, GameConfig>>,
+
+    #[account(
+        mut,
+        seeds = [HOUSE_STATS_SEED],
+        bump = house_stats.bump,
+    )]
+    pub house_stats: Box<Account<'info, HouseStats>>,
+
+    #[account(
+        seeds = [FEE_SPLIT_SEED],
+        bump = fee_split.bump,
+    )]
+    pub fee_split: Box<Account<'info, FeeSplit>>,
+
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = PendingClaim::SPACE,
+        seeds = [PENDING_CLAIM_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub pending_claim: Box<Account<'info, PendingClaim>>,
+
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = PlayerStats::SPACE,
+        seeds = [PLAYER_STATS_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub player_stats: Box<Account<'info, PlayerStats>>,
+
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = FairnessProof::SPACE,
+        seeds = [FAIRNESS_PROOF_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub fairness_proof: Box<Account<'info, FairnessProof>>,
+
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = TipVault::SPACE,
+        seeds = [TIP_VAULT_SEED],
+        bump,
+    )]
+    pub tip_vault: Box<Account<'info, TipVault>>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, used to find the ed25519 promo signature check
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    /// CHECK: promo redemption PDA, created in the handler when a promo code is used
+    #[account(mut)]
+    pub promo_redemption: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = player,
+        seeds = [b"ArciumSignerAccount"],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLAY_CHEST_GAME))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::slot_hashes::ID)]
+    /// CHECK: slot_hashes sysvar, read when `GameConfig::mix_slot_hash` is set
+    pub slot_hashes: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("play_hidden_chest_game", player)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct PlayHiddenChestGame<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = GameAccount::SPACE,
+        seeds = [GAME_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub game_account: Box<Account<'info, GameAccount>>,
+
+    /// CHECK: the player's `PlayerAccess` PDA, read only if it exists
+    #[account(
+        seeds = [PLAYER_ACCESS_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub player_access: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+    )]
+    pub treasury: Box<Account<'info, Treasury>>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, GameConfig>>,
+
+    #[account(
+        mut,
+        seeds = [HOUSE_STATS_SEED],
+        bump = house_stats.bump,
+    )]
+    pub house_stats: Box<Account<'info, HouseStats>>,
+
+    #[account(
+        seeds = [FEE_SPLIT_SEED],
+        bump = fee_split.bump,
+    )]
+    pub fee_split: Box<Account<'info, FeeSplit>>,
+
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = PendingClaim::SPACE,
+        seeds = [PENDING_CLAIM_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub pending_claim: Box<Account<'info, PendingClaim>>,
+
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = PlayerStats::SPACE,
+        seeds = [PLAYER_STATS_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub player_stats: Box<Account<'info, PlayerStats>>,
+
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = FairnessProof::SPACE,
+        seeds = [FAIRNESS_PROOF_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub fairness_proof: Box<Account<'info, FairnessProof>>,
+
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = TipVault::SPACE,
+        seeds = [TIP_VAULT_SEED],
+        bump,
+    )]
+    pub tip_vault: Box<Account<'info, TipVault>>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = player,
+        seeds = [b"ArciumSignerAccount"],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLAY_HIDDEN_CHEST_GAME))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("play_odds_game", player)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct PlayOddsGame<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = GameAccount::SPACE,
+        seeds = [GAME_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub game_account: Box<Account<'info, GameAccount>>,
+
+    /// CHECK: the player's `PlayerAccess` PDA, read only if it exists
+    #[account(
+        seeds = [PLAYER_ACCESS_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub player_access: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+    )]
+    pub treasury: Box<Account<'info, Treasury>>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, GameConfig>>,
+
+    #[account(
+        mut,
+        seeds = [HOUSE_STATS_SEED],
+        bump = house_stats.bump,
+    )]
+    pub house_stats: Box<Account<'info, HouseStats>>,
+
+    #[account(
+        seeds = [FEE_SPLIT_SEED],
+        bump = fee_split.bump,
+    )]
+    pub fee_split: Box<Account<'info, FeeSplit>>,
+
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = PendingClaim::SPACE,
+        seeds = [PENDING_CLAIM_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub pending_claim: Box<Account<'info, PendingClaim>>,
+
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = PlayerStats::SPACE,
+        seeds = [PLAYER_STATS_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub player_stats: Box<Account<'info, PlayerStats>>,
+
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = FairnessProof::SPACE,
+        seeds = [FAIRNESS_PROOF_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub fairness_proof: Box<Account<'info, FairnessProof>>,
+
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = TipVault::SPACE,
+        seeds = [TIP_VAULT_SEED],
+        bump,
+    )]
+    pub tip_vault: Box<Account<'info, TipVault>>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = player,
+        seeds = [b"ArciumSignerAccount"],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLAY_ODDS_GAME))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+    
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[queue_computation_accounts("play_chest_game", player)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct PlayNextRound<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GAME_SEED, player.key().as_ref()],
+        bump = game_account.bump,
+        constraint = game_account.player == player.key() @ ErrorCode::NotGamePlayer,
+    )]
+    pub game_account: Box<Account<'info, GameAccount>>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+    )]
+    pub treasury: Box<Account<'info, Treasury>>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, GameConfig>>,
+
+    #[account(
+        mut,
+        seeds = [HOUSE_STATS_SEED],
+        bump = house_stats.bump,
+    )]
+    pub house_stats: Box<Account<'info, HouseStats>>,
+
+    #[account(
+        seeds = [FEE_SPLIT_SEED],
+        bump = fee_split.bump,
+    )]
+    pub fee_split: Box<Account<'info, FeeSplit>>,
+
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = PendingClaim::SPACE,
+        seeds = [PENDING_CLAIM_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub pending_claim: Box<Account<'info, PendingClaim>>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = player,
+        seeds = [b"ArciumSignerAccount"],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLAY_CHEST_GAME))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::slot_hashes::ID)]
+    /// CHECK: slot_hashes sysvar, read when `GameConfig::mix_slot_hash` is set
+    pub slot_hashes: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("play_chest_game")]
+#[derive(Accounts)]
+pub struct PlayChestGameCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLAY_CHEST_GAME))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    /// CHECK: computation_account, checked by arcium program via constraints in the callback context.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    // Custom accounts passed via CallbackAccount
+    #[account(mut)]
+    pub game_account: Box<Account<'info, GameAccount>>,
+
+    #[account(mut)]
+    pub treasury: Box<Account<'info, Treasury>>,
+
+    /// CHECK: player account for receiving winnings
+    #[account(mut)]
+    pub player: AccountInfo<'info>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, GameConfig>>,
+
+    #[account(
+        mut,
+        seeds = [HOUSE_STATS_SEED],
+        bump = house_stats.bump,
+    )]
+    pub house_stats: Box<Account<'info, HouseStats>>,
+
+    #[account(
+        seeds = [FEE_SPLIT_SEED],
+        bump = fee_split.bump,
+    )]
+    pub fee_split: Box<Account<'info, FeeSplit>>,
+
+    #[account(
+        mut,
+        seeds = [PENDING_CLAIM_SEED, game_account.player.as_ref()],
+        bump = pending_claim.bump,
+    )]
+    pub pending_claim: Box<Account<'info, PendingClaim>>,
+
+    #[account(
+        mut,
+        seeds = [RESULT_LOG_SEED],
+        bump = result_log.load()?.bump,
+    )]
+    pub result_log: AccountLoader<'info, ResultLog>,
+
+    #[account(
+        mut,
+        seeds = [PLAYER_STATS_SEED, game_account.player.as_ref()],
+        bump = player_stats.bump,
+    )]
+    pub player_stats: Box<Account<'info, PlayerStats>>,
+    #[account(
+        mut,
+        seeds = [FAIRNESS_PROOF_SEED, game_account.player.as_ref()],
+        bump = fairness_proof.bump,
+    )]
+    pub fairness_proof: Box<Account<'info, FairnessProof>>,
+}
+
+#[callback_accounts("play_hidden_chest_game")]
+#[derive(Accounts)]
+pub struct PlayHiddenChestGameCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLAY_HIDDEN_CHEST_GAME))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    /// CHECK: computation_account, checked by arcium program via constraints in the callback context.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    // Custom accounts passed via CallbackAccount
+    #[account(mut)]
+    pub game_account: Box<Account<'info, GameAccount>>,
+
+    #[account(mut)]
+    pub treasury: Box<Account<'info, Treasury>>,
+
+    /// CHECK: player account for receiving winnings
+    #[account(mut)]
+    pub player: AccountInfo<'info>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, GameConfig>>,
+
+    #[account(
+        mut,
+        seeds = [HOUSE_STATS_SEED],
+        bump = house_stats.bump,
+    )]
+    pub house_stats: Box<Account<'info, HouseStats>>,
+
+    #[account(
+        seeds = [FEE_SPLIT_SEED],
+        bump = fee_split.bump,
+    )]
+    pub fee_split: Box<Account<'info, FeeSplit>>,
+
+    #[account(
+        mut,
+        seeds = [PENDING_CLAIM_SEED, game_account.player.as_ref()],
+        bump = pending_claim.bump,
+    )]
+    pub pending_claim: Box<Account<'info, PendingClaim>>,
+
+    #[account(
+        mut,
+        seeds = [RESULT_LOG_SEED],
+        bump = result_log.load()?.bump,
+    )]
+    pub result_log: AccountLoader<'info, ResultLog>,
+
+    #[account(
+        mut,
+        seeds = [PLAYER_STATS_SEED, game_account.player.as_ref()],
+        bump = player_stats.bump,
+    )]
+    pub player_stats: Box<Account<'info, PlayerStats>>,
+    #[account(
+        mut,
+        seeds = [FAIRNESS_PROOF_SEED, game_account.player.as_ref()],
+        bump = fairness_proof.bump,
+    )]
+    pub fairness_proof: Box<Account<'info, FairnessProof>>,
+}
+
+#[callback_accounts("play_odds_game")]
+#[derive(Accounts)]
+pub struct PlayOddsGameCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLAY_ODDS_GAME))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    
+    /// CHECK: computation_account, checked by arcium program via constraints in the callback context.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    // Custom accounts passed via CallbackAccount
+    #[account(mut)]
+    pub game_account: Box<Account<'info, GameAccount>>,
+
+    #[account(mut)]
+    pub treasury: Box<Account<'info, Treasury>>,
+
+    /// CHECK: player account for receiving winnings
+    #[account(mut)]
+    pub player: AccountInfo<'info>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, GameConfig>>,
+
+    #[account(
+        mut,
+        seeds = [HOUSE_STATS_SEED],
+        bump = house_stats.bump,
+    )]
+    pub house_stats: Box<Account<'info, HouseStats>>,
+
+    #[account(
+        seeds = [FEE_SPLIT_SEED],
+        bump = fee_split.bump,
+    )]
+    pub fee_split: Box<Account<'info, FeeSplit>>,
+
+    #[account(
+        mut,
+        seeds = [PENDING_CLAIM_SEED, game_account.player.as_ref()],
+        bump = pending_claim.bump,
+    )]
+    pub pending_claim: Box<Account<'info, PendingClaim>>,
+
+    #[account(
+        mut,
+        seeds = [RESULT_LOG_SEED],
+        bump = result_log.load()?.bump,
+    )]
+    pub result_log: AccountLoader<'info, ResultLog>,
+
+    #[account(
+        mut,
+        seeds = [PLAYER_STATS_SEED, game_account.player.as_ref()],
+        bump = player_stats.bump,
+    )]
+    pub player_stats: Box<Account<'info, PlayerStats>>,
+    #[account(
+        mut,
+        seeds = [FAIRNESS_PROOF_SEED, game_account.player.as_ref()],
+        bump = fairness_proof.bump,
+    )]
+    pub fairness_proof: Box<Account<'info, FairnessProof>>,
+}
+
+#[queue_computation_accounts("play_pot_game", player_0)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct PlayPotGame<'info> {
+    #[account(mut)]
+    pub player_0: Signer<'info>,
+    #[account(mut)]
+    pub player_1: Signer<'info>,
+    #[account(mut)]
+    pub player_2: Option<Signer<'info>>,
+
+    #[account(
+        init,
+        payer = player_0,
+        space = PotGame::SPACE,
+        seeds = [POT_GAME_SEED, computation_offset.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub pot_game: Box<Account<'info, PotGame>>,
+
+    /// CHECK: player 0's `PlayerAccess` PDA, read only if it exists
+    #[account(
+        seeds = [PLAYER_ACCESS_SEED, player_0.key().as_ref()],
+        bump,
+    )]
+    pub player_access_0: UncheckedAccount<'info>,
+    /// CHECK: player 1's `PlayerAccess` PDA, read only if it exists
+    #[account(
+        seeds = [PLAYER_ACCESS_SEED, player_1.key().as_ref()],
+        bump,
+    )]
+    pub player_access_1: UncheckedAccount<'info>,
+    /// CHECK: player 2's `PlayerAccess` PDA, checked against `player_2` in the handler
+    pub player_access_2: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, GameConfig>>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = player_0,
+        seeds = [b"ArciumSignerAccount"],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLAY_POT_GAME))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("play_pot_game")]
+#[derive(Accounts)]
+pub struct PlayPotGameCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLAY_POT_GAME))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+
+    /// CHECK: computation_account, checked by arcium program via constraints in the callback context.
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    // Custom accounts passed via CallbackAccount
+    #[account(
+        mut,
+        seeds = [POT_GAME_SEED, pot_game.computation_offset.to_le_bytes().as_ref()],
+        bump = pot_game.bump,
+    )]
+    pub pot_game: Box<Account<'info, PotGame>>,
+
+    /// CHECK: compared against `pot_game.players` in the handler
+    #[account(mut)]
+    pub player_0: AccountInfo<'info>,
+    /// CHECK: compared against `pot_game.players` in the handler
+    #[account(mut)]
+    pub player_1: AccountInfo<'info>,
+    /// CHECK: compared against `pot_game.players` in the handler; player 0 again in two-player games
+    #[account(mut)]
+    pub player_2: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelPotGame<'info> {
+    #[account(
+        mut,
+        seeds = [POT_GAME_SEED, pot_game.computation_offset.to_le_bytes().as_ref()],
+        bump = pot_game.bump,
+    )]
+    pub pot_game: Account<'info, PotGame>,
+
+    /// CHECK: compared against `pot_game.players` in the handler
+    #[account(mut)]
+    pub player_0: AccountInfo<'info>,
+    /// CHECK: compared against `pot_game.players` in the handler
+    #[account(mut)]
+    pub player_1: AccountInfo<'info>,
+    /// CHECK: compared against `pot_game.players` in the handler; player 0 again in two-player games
+    #[account(mut)]
+    pub player_2: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelGame<'info> {
+    /// CHECK: player receiving refund
+    #[account(mut)]
+    pub player: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [GAME_SEED, player.key().as_ref()],
+        bump = game_account.bump,
+        constraint = game_account.player == player.key() @ ErrorCode::NotGamePlayer,
+    )]
+    pub game_account: Account<'info, GameAccount>,
+
+    #[account(
+        mut,
+        seeds = [HOUSE_STATS_SEED],
+        bump = house_stats.bump,
+    )]
+    pub house_stats: Account<'info, HouseStats>,
+
+    // Optional so games from before player stats existed can still be cancelled
+    #[account(
+        mut,
+        seeds = [PLAYER_STATS_SEED, player.key().as_ref()],
+        bump = player_stats.bump,
+    )]
+    pub player_stats: Option<Account<'info, PlayerStats>>,
+
+    // Optional proof that the computation is no longer queued, allowing
+    // cancellation before GAME_HARD_TIMEOUT_SECS
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Option<Box<Account<'info, MXEAccount>>>,
+    /// CHECK: compared against the game's derived computation PDA in the handler
+    pub computation_account: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct TopUpBet<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GAME_SEED, player.key().as_ref()],
+        bump = game_account.bump,
+        constraint = game_account.player == player.key() @ ErrorCode::NotGamePlayer,
+    )]
+    pub game_account: Box<Account<'info, GameAccount>>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+    )]
+    pub treasury: Box<Account<'info, Treasury>>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, GameConfig>>,
+
+    #[account(
+        mut,
+        seeds = [PLAYER_STATS_SEED, player.key().as_ref()],
+        bump = player_stats.bump,
+    )]
+    pub player_stats: Box<Account<'info, PlayerStats>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CashOut<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GAME_SEED, player.key().as_ref()],
+        bump = game_account.bump,
+        constraint = game_account.player == player.key() @ ErrorCode::NotGamePlayer,
+    )]
+    pub game_account: Box<Account<'info, GameAccount>>,
+
+    #[account(
+        mut,
+        seeds = [PLAYER_STATS_SEED, player.key().as_ref()],
+        bump = player_stats.bump,
+    )]
+    pub player_stats: Box<Account<'info, PlayerStats>>,
+
+    #[account(
+        mut,
+        seeds = [PENDING_CLAIM_SEED, player.key().as_ref()],
+        bump = pending_claim.bump,
+    )]
+    pub pending_claim: Box<Account<'info, PendingClaim>>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, GameConfig>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimWinnings<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PENDING_CLAIM_SEED, player.key().as_ref()],
+        bump = pending_claim.bump,
+        has_one = player @ ErrorCode::NotGamePlayer,
+    )]
+    pub pending_claim: Box<Account<'info, PendingClaim>>,
+
+    /// Pays out winnings the treasury owed the player at settlement
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+}
+
+#[derive(Accounts)]
+#[instruction(player: Pubkey)]
+pub struct ClaimWinningsFor<'info> {
+    /// Anyone may claim an abandoned escrow, for the keeper fee
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    /// CHECK: the escrow's owner, receives the payout
+    #[account(mut, address = player @ ErrorCode::NotGamePlayer)]
+    pub player_wallet: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [PENDING_CLAIM_SEED, player.as_ref()],
+        bump = pending_claim.bump,
+    )]
+    pub pending_claim: Box<Account<'info, PendingClaim>>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, GameConfig>,
+}
+
+#[derive(Accounts)]
+pub struct CreateSession<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    #[account(
+        init,
+        payer = player,
+        space = SessionKey::SPACE,
+        seeds = [SESSION_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub session: Account<'info, SessionKey>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSession<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    #[account(
+        mut,
+        close = player,
+        seeds = [SESSION_SEED, player.key().as_ref()],
+        bump = session.bump,
+        has_one = player @ ErrorCode::NotGamePlayer,
+    )]
+    pub session: Account<'info, SessionKey>,
+}
+
+#[queue_computation_accounts("play_chest_game", session_signer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct PlayChestGameSession<'info> {
+    /// The ephemeral session key; pays transaction and account costs
+    #[account(mut)]
+    pub session_signer: Signer<'info>,
+
+    /// CHECK: the player the session bets for, tied to the session by its seeds
+    pub player: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [SESSION_SEED, player.key().as_ref()],
+        bump = session.bump,
+        constraint = session.session_key == session_signer.key() @ ErrorCode::InvalidSessionKey,
+    )]
+    pub session: Box<Account<'info, SessionKey>>,
+
+    #[account(
+        init_if_needed,
+        payer = session_signer,
+        space = GameAccount::SPACE,
+        seeds = [GAME_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub game_account: Box<Account<'info, GameAccount>>,
+
+    /// CHECK: the player's `PlayerAccess` PDA, read only if it exists
+    #[account(
+        seeds = [PLAYER_ACCESS_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub player_access: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+    )]
+    pub treasury: Box<Account<'info, Treasury>>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, GameConfig>>,
+
+    #[account(
+        mut,
+        seeds = [HOUSE_STATS_SEED],
+        bump = house_stats.bump,
+    )]
+    pub house_stats: Box<Account<'info, HouseStats>>,
+
+    #[account(
+        seeds = [FEE_SPLIT_SEED],
+        bump = fee_split.bump,
+    )]
+    pub fee_split: Box<Account<'info, FeeSplit>>,
+
+    #[account(
+        init_if_needed,
+        payer = session_signer,
+        space = PendingClaim::SPACE,
+        seeds = [PENDING_CLAIM_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub pending_claim: Box<Account<'info, PendingClaim>>,
+
+    #[account(
+        init_if_needed,
+        payer = session_signer,
+        space = PlayerStats::SPACE,
+        seeds = [PLAYER_STATS_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub player_stats: Box<Account<'info, PlayerStats>>,
+
+    #[account(
+        init_if_needed,
+        payer = session_signer,
+        space = FairnessProof::SPACE,
+        seeds = [FAIRNESS_PROOF_SEED, player.key().as_ref()],
+        bump,
+    )]
+    pub fairness_proof: Box<Account<'info, FairnessProof>>,
+
+    #[account(
+        init_if_needed,
+        payer = session_signer,
+        space = TipVault::SPACE,
+        seeds = [TIP_VAULT_SEED],
+        bump,
+    )]
+    pub tip_vault: Box<Account<'info, TipVault>>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = session_signer,
+        seeds = [b"ArciumSignerAccount"],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, ArciumSignerAccount>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+
+    #[account(mut, address = derive_mempool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_execpool_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_comp_pda!(computation_offset, mxe_account, ErrorCode::ClusterNotSet))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLAY_CHEST_GAME))]
+    pub comp_def_account: Box<Account<'info, ComputationDefinitionAccount>>,
+
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Box<Account<'info, FeePool>>,
+
+    #[account(mut, address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Box<Account<'info, ClockAccount>>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::slot_hashes::ID)]
+    /// CHECK: slot_hashes sysvar, read when `GameConfig::mix_slot_hash` is set
+    pub slot_hashes: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateGameAccount<'info> {
+    /// The game's player or the treasury authority, checked in the handler
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    /// CHECK: may be too short to deserialize; owner, discriminator and PDA are checked in the handler
+    #[account(mut)]
+    pub game_account: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct IsCancellable<'info> {
+    /// CHECK: only used to derive the game PDA
+    pub player: AccountInfo<'info>,
+
+    #[account(
+        seeds = [GAME_SEED, player.key().as_ref()],
+        bump = game_account.bump,
+    )]
+    pub game_account: Account<'info, GameAccount>,
+
+    // Optional proof that the computation is no longer queued, as for `cancel_game`
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Option<Box<Account<'info, MXEAccount>>>,
+    /// CHECK: compared against the game's derived computation PDA in the handler
+    pub computation_account: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelExpiredGame<'info> {
+    /// Anyone may crank an expired auto-cancel game
+    pub keeper: Signer<'info>,
+
+    /// CHECK: player receiving refund
+    #[account(mut)]
+    pub player: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [GAME_SEED, player.key().as_ref()],
+        bump = game_account.bump,
+        constraint = game_account.player == player.key() @ ErrorCode::NotGamePlayer,
+    )]
+    pub game_account: Account<'info, GameAccount>,
+
+    #[account(
+        mut,
+        seeds = [HOUSE_STATS_SEED],
+        bump = house_stats.bump,
+    )]
+    pub house_stats: Account<'info, HouseStats>,
+
+    #[account(
+        mut,
+        seeds = [PLAYER_STATS_SEED, player.key().as_ref()],
+        bump = player_stats.bump,
+    )]
+    pub player_stats: Option<Account<'info, PlayerStats>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelExpiredGamesBatch<'info> {
+    /// Anyone may crank expired auto-cancel games
+    pub keeper: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [HOUSE_STATS_SEED],
+        bump = house_stats.bump,
+    )]
+    pub house_stats: Account<'info, HouseStats>,
+}
+
+#[derive(Accounts)]
+pub struct FlagDispute<'info> {
+    pub player: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GAME_SEED, player.key().as_ref()],
+        bump = game_account.bump,
+        constraint = game_account.player == player.key() @ ErrorCode::NotGamePlayer,
+    )]
+    pub game_account: Account<'info, GameAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub config: Account<'info, GameConfig>,
+
+    /// CHECK: player receiving a refund
+    #[account(mut)]
+    pub player: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [GAME_SEED, player.key().as_ref()],
+        bump = game_account.bump,
+        constraint = game_account.player == player.key() @ ErrorCode::NotGamePlayer,
+    )]
+    pub game_account: Account<'info, GameAccount>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        mut,
+        seeds = [HOUSE_STATS_SEED],
+        bump = house_stats.bump,
+    )]
+    pub house_stats: Account<'info, HouseStats>,
+
+    // Optional so games from before player stats existed can still be resolved
+    #[account(
+        mut,
+        seeds = [PLAYER_STATS_SEED, player.key().as_ref()],
+        bump = player_stats.bump,
+    )]
+    pub player_stats: Option<Account<'info, PlayerStats>>,
+
+    // Proof that the game's computation is no longer queued
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: compared against the game's derived computation PDA in the handler
+    pub computation_account: UncheckedAccount<'info>,
+}
+
+#[init_computation_definition_accounts("play_chest_game", payer)]
+#[derive(Accounts)]
+pub struct InitPlayChestGameCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table, checked by arcium program.
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program is the Address Lookup Table program.
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("play_hidden_chest_game", payer)]
+#[derive(Accounts)]
+pub struct InitPlayHiddenChestGameCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table, checked by arcium program.
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program is the Address Lookup Table program.
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("play_odds_game", payer)]
+#[derive(Accounts)]
+pub struct InitPlayOddsGameCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table, checked by arcium program.
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program is the Address Lookup Table program.
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("play_pot_game", payer)]
+#[derive(Accounts)]
+pub struct InitPlayPotGameCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_lut_pda!(mxe_account.lut_offset_slot))]
+    /// CHECK: address_lookup_table, checked by arcium program.
+    pub address_lookup_table: UncheckedAccount<'info>,
+    #[account(address = LUT_PROGRAM_ID)]
+    /// CHECK: lut_program is the Address Lookup Table program.
+    pub lut_program: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+// ============= Helpers =============
+
+/// Move lamports between two accounts owned by this program (or being credited),
+/// using checked math so an underflow/overflow surfaces as a program error
+/// instead of a runtime panic.
+pub fn transfer_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    if from.key() == to.key() {
+        return Ok(());
+    }
+    let from_balance = from
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::InsufficientFunds)?;
+    let to_balance = to
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    **from.try_borrow_mut_lamports()? = from_balance;
+    **to.try_borrow_mut_lamports()? = to_balance;
+    Ok(())
+}
+
+/// Guard against settlement moving an account below its rent-exempt minimum,
+/// which would get it garbage collected along with the game state.
+pub fn require_rent_exempt(account: &AccountInfo) -> Result<()> {
+    let rent_floor = Rent::get()?.minimum_balance(account.data_len());
+    require!(account.lamports() >= rent_floor, ErrorCode::WouldBreakRentExemption);
+    Ok(())
+}
+
+/// Read a player's `PlayerAccess` PDA, or `None` if it was never created
+pub fn load_player_access(info: &AccountInfo) -> Result<Option<PlayerAccess>> {
+    if info.owner != &crate::ID || info.data_is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(PlayerAccess::try_deserialize(&mut &info.try_borrow_data()?[..])?))
+}
+
+/// Rejects a new game from a blocked wallet, or in `allowlist_only` mode
+/// from one that isn't allowlisted
+pub fn validate_player_access(access: Option<&PlayerAccess>, allowlist_only: bool) -> Result<()> {
+    require!(!access.is_some_and(|a| a.blocked), ErrorCode::PlayerBlocked);
+    require!(
+        !allowlist_only || access.is_some_and(|a| a.allowed),
+        ErrorCode::PlayerNotAllowlisted
+    );
+    Ok(())
+}
+
+/// Index into the per-chest-count win caps: the chest count, or 0 for odds games
+pub fn win_cap_slot(num_chests: u8, win_probability_bps: u16) -> usize {
+    if win_probability_bps > 0 {
+        0
+    } else {
+        (num_chests as usize).min(WIN_CAP_SLOTS - 1)
+    }
+}
+
+/// Decode a game account of any older (shorter) layout, zero-filling the
+/// fields it predates and backfilling `settle_by` from `created_at`
+pub fn migrated_game_account(data: &[u8]) -> Result<GameAccount> {
+    require!(data.starts_with(GameAccount::DISCRIMINATOR), ErrorCode::InvalidGameAccount);
+    let mut padded = data.to_vec();
+    if padded.len() < GameAccount::SPACE {
+        padded.resize(GameAccount::SPACE, 0);
+    }
+    let mut game = GameAccount::try_deserialize(&mut &padded[..])?;
+    if game.settle_by == 0 {
+        game.settle_by = game.created_at.saturating_add(GAME_TIMEOUT_SECS);
+    }
+    Ok(game)
+}
+
+/// Refund a pending game's whole bet and mark it refunded, for results that
+/// can't be settled fairly. Timeouts and disputes use `Cancelled` instead.
+pub fn refund_pending_game(
+    game_account: &mut Account<GameAccount>,
+    player: &AccountInfo,
+    house_stats: &mut Account<HouseStats>,
+    player_stats: &mut Account<PlayerStats>,
+) -> Result<()> {
+    let bet_amount = game_account.bet_amount;
+    transfer_lamports(&game_account.to_account_info(), player, bet_amount)?;
+    require_rent_exempt(&game_account.to_account_info())?;
+
+    game_account.status = GameStatus::Refunded as u8;
+    house_stats.record_cancelled()?;
+    player_stats.record_payout(bet_amount)?;
+    player_stats.release_exposure(game_account);
+
+    emit!(GameRefundedEvent {
+        player: game_account.player,
+        bet_amount,
+        computation_offset: game_account.computation_offset,
+        created_at: game_account.created_at,
+    });
+    Ok(())
+}
+
+/// Fair odds of a best-of-`rounds` series where each round is won with
+/// probability winners/num_chests, as (all outcomes, winning outcomes). The
+/// fair multiplier is their ratio; a single round reduces to (num_chests, winners).
+pub fn series_odds(num_chests: u8, winners: u8, rounds: u8) -> (u64, u64) {
+    let chests = num_chests as u64;
+    let winners = winners as u64;
+    let rounds = rounds as u64;
+    let needed = rounds / 2 + 1;
+    let outcomes = chests.pow(rounds as u32);
+    let mut winning_outcomes = 0;
+    let mut choose = 1; // C(rounds, k), built up incrementally
+    for k in 0..=rounds {
+        if k >= needed {
+            winning_outcomes +=
+                choose * winners.pow(k as u32) * (chests - winners).pow((rounds - k) as u32);
+        }
+        choose = choose * (rounds - k) / (k + 1);
+    }
+    (outcomes, winning_outcomes)
+}
+
+/// Whether a computation account is still waiting on the cluster. A closed
+/// (empty or reassigned) account has necessarily left the queue.
+pub fn computation_is_queued(computation_account: &AccountInfo) -> Result<bool> {
+    if computation_account.owner != &ARCIUM_PROG_ID || computation_account.data_is_empty() {
+        return Ok(false);
+    }
+    let data = computation_account.try_borrow_data()?;
+    let computation = ComputationAccount::try_deserialize(&mut &data[..])
+        .map_err(|_| ErrorCode::InvalidComputationAccount)?;
+    Ok(matches!(computation.status, ComputationStatus::Queued))
+}
+
+/// Whether a callback is a redelivery for a game this same computation
+/// (`queued_computation`, derived from the game's `computation_offset`)
+/// already completed. Such a callback is acknowledged without settling, so a
+/// double delivery can't pay out twice. Games cancelled, refunded or resolved
+/// by a dispute were not settled by it, and still fail the pending check.
+pub fn is_redelivered_callback(
+    game: &GameAccount,
+    computation_account: &Pubkey,
+    queued_computation: &Pubkey,
+) -> bool {
+    game.status == GameStatus::Completed as u8 && game.settled_by_callback && computation_account == queued_computation
+}
+
+/// `play_chest_game` circuit output with named fields, see `decode_result`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChestGameResult {
+    pub outcome: u8, // RoundOutcome
+    pub winning_chest: u8,
+    pub sampled: bool,  // false if the RNG fell back; such games are refunded
+    pub num_chests: u8, // Echoed from the input
+    pub raw_draw: u8,   // RNG draw before the player entropy shift, 0 unless revealed
+    pub draw_revealed: bool, // Whether `reveal_raw_draw` asked the circuit for `raw_draw`
+}
+
+impl ChestGameResult {
+    pub fn player_won(&self) -> bool {
+        self.outcome == RoundOutcome::Win as u8
+    }
+}
+
+/// Names the fields of the generated `PlayChestGameOutput`. The circuit
+/// returns (u8, u8, bool, u8, u8, bool), which arrives as
+/// `PlayChestGameOutput { field_0: PlayChestGameOutputStruct0 { field_0..field_5 } }`.
+/// Off-chain consumers decoding callback data can use this as well.
+pub fn decode_result(output: &PlayChestGameOutput) -> ChestGameResult {
+    let PlayChestGameOutputStruct0 { field_0, field_1, field_2, field_3, field_4, field_5 } = output.field_0;
+    ChestGameResult {
+        outcome: field_0,
+        winning_chest: field_1,
+        sampled: field_2,
+        num_chests: field_3,
+        raw_draw: field_4,
+        draw_revealed: field_5,
+    }
+}
+
+/// Bet of `bps` of the lamports a player holds above `rent_floor`, clamped to
+/// MIN_BET_LAMPORTS and `max_bet` (0 = uncapped). A clamped bet that would dip
+/// into the rent floor fails rather than leave the wallet below rent exemption.
+pub fn bet_from_balance_bps(lamports: u64, rent_floor: u64, bps: u64, max_bet: u64) -> Result<u64> {
+    require!((1..=10_000).contains(&bps), ErrorCode::InvalidBetBps);
+    let spendable = lamports.saturating_sub(rent_floor);
+    let mut bet = ((spendable as u128 * bps as u128) / 10_000) as u64;
+    bet = bet.max(MIN_BET_LAMPORTS);
+    if max_bet > 0 {
+        bet = bet.min(max_bet);
+    }
+    require!(bet <= spendable, ErrorCode::InsufficientFunds);
+    Ok(bet)
+}
+
+/// Fails with a readable error, instead of a system transfer failing halfway
+/// through the play, if `player` can't pay `spend` and stay rent exempt. Rent
+/// for the accounts the play creates was already taken when they were
+/// initialized, so `spend` only counts what the handler still transfers.
+pub fn validate_player_balance(player: &AccountInfo, spend: u64) -> Result<()> {
+    let rent_floor = Rent::get()?.minimum_balance(player.data_len());
+    require!(covers_spend(player.lamports(), rent_floor, spend), ErrorCode::InsufficientPlayerBalance);
+    Ok(())
+}
+
+/// Whether `lamports` covers `spend` without dipping below `rent_floor`
+pub fn covers_spend(lamports: u64, rent_floor: u64, spend: u64) -> bool {
+    lamports.saturating_sub(rent_floor) >= spend
+}
+
+/// A computation definition whose circuit hasn't finished uploading can't
+/// run; fail before the bet moves instead of deep inside `queue_computation`
+pub fn validate_comp_def_ready(comp_def: &ComputationDefinitionAccount) -> Result<()> {
+    let ready = match &comp_def.circuit_source {
+        CircuitSource::OnChain(source) => source.is_completed,
+        CircuitSource::OffChain(_) | CircuitSource::Local(_) => true,
+    };
+    require!(ready, ErrorCode::CompDefNotInitialized);
+    Ok(())
+}
+
+/// Rejects clients built against other economics than this PROGRAM_VERSION
+pub fn validate_program_version(expected: u16) -> Result<()> {
+    require!(expected == PROGRAM_VERSION, ErrorCode::VersionMismatch);
+    Ok(())
+}
+
+/// Chest count within MIN_CHESTS..=MAX_CHESTS, with a distinct error per bound
+pub fn validate_chest_count(num_chests: u8) -> Result<()> {
+    require!(num_chests >= MIN_CHESTS, ErrorCode::ChestCountTooLow);
+    require!(num_chests <= MAX_CHESTS, ErrorCode::ChestCountTooHigh);
+    Ok(())
+}
+
+pub fn validate_win_mode(win_mode: u8, num_chests: u8) -> Result<()> {
+    if win_mode == WinMode::NearMiss as u8 {
+        require!(num_chests >= NEAR_MISS_MIN_CHESTS, ErrorCode::InvalidWinMode);
+    } else {
+        require!(win_mode == WinMode::Exact as u8, ErrorCode::InvalidWinMode);
+    }
+    Ok(())
+}
+
+// Curve25519 points of order 1, 2, 4 or 8 as u-coordinates, including the
+// non-canonical encodings of 0 and 1 (p and p + 1)
+const LOW_ORDER_X25519_POINTS: [[u8; 32]; 7] = [
+    [0; 32],
+    [
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    ],
+    [
+        0xe0, 0xeb, 0x7a, 0x7c, 0x3b, 0x41, 0xb8, 0xae, 0x16, 0x56, 0xe3, 0xfa, 0xf1, 0x9f, 0xc4, 0x6a,
+        0xda, 0x09, 0x8d, 0xeb, 0x9c, 0x32, 0xb1, 0xfd, 0x86, 0x62, 0x05, 0x16, 0x5f, 0x49, 0xb8, 0x00,
+    ],
+    [
+        0x5f, 0x9c, 0x95, 0xbc, 0xa3, 0x50, 0x8c, 0x24, 0xb1, 0xd0, 0xb1, 0x55, 0x9c, 0x83, 0xef, 0x5b,
+        0x04, 0x44, 0x5c, 0xc4, 0x58, 0x1c, 0x8e, 0x86, 0xd8, 0x22, 0x4e, 0xdd, 0xd0, 0x9f, 0x11, 0x57,
+    ],
+    [
+        0xec, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f,
+    ],
+    [
+        0xed, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f,
+    ],
+    [
+        0xee, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f,
+    ],
+];
+
+/// Rejects encryption keys no real x25519 keypair produces: the low-order
+/// points, which would make the shared secret predictable and the MPC
+/// computation fail after the bet is locked. X25519 ignores the top bit of
+/// the last byte, so it is masked off before comparing.
+pub fn validate_x25519_pubkey(pub_key: &[u8; 32]) -> Result<()> {
+    let mut u = *pub_key;
+    u[31] &= 0x7f;
+    require!(!LOW_ORDER_X25519_POINTS.contains(&u), ErrorCode::PubkeyMalformed);
+    Ok(())
+}
+
+/// Odds games must pay at most `MAX_CHESTS`x and can't be a sure win
+pub fn validate_win_probability(win_probability_bps: u16) -> Result<()> {
+    require!(
+        (MIN_WIN_PROBABILITY_BPS..=MAX_WIN_PROBABILITY_BPS).contains(&win_probability_bps),
+        ErrorCode::InvalidWinProbability
+    );
+    Ok(())
+}
+
+/// Chests per round that count as a win under `win_mode`
+pub fn winning_chests(win_mode: u8) -> u8 {
+    if win_mode == WinMode::NearMiss as u8 { 3 } else { 1 }
+}
+
+/// Whether `choice` wins against a non-push `winning_chest`, mirroring the
+/// circuit
+pub fn chest_wins(choice: u8, winning_chest: u8, num_chests: u8, win_mode: u8) -> bool {
+    if win_mode != WinMode::NearMiss as u8 {
+        return choice == winning_chest;
+    }
+    let distance = choice.abs_diff(winning_chest);
+    distance <= 1 || distance == num_chests - 1
+}
+
+/// A pot game needs 2 or 3 distinct players and one encrypted entry each
+pub fn validate_pot_players(players: &[Pubkey], entries: usize) -> Result<()> {
+    require!(
+        (MIN_POT_PLAYERS..=MAX_POT_PLAYERS).contains(&players.len()) && entries == players.len(),
+        ErrorCode::InvalidPotPlayers
+    );
+    for (i, player) in players.iter().enumerate() {
+        require!(!players[..i].contains(player), ErrorCode::InvalidPotPlayers);
+    }
+    Ok(())
+}
+
+/// What each pot player is paid: the winners split every bet, the first
+/// winner taking any remainder, and with no winner every bet is refunded
+pub fn pot_payouts(bet_amount: u64, winners: &[bool]) -> Result<Vec<u64>> {
+    let winner_count = winners.iter().filter(|won| **won).count() as u64;
+    if winner_count == 0 {
+        return Ok(vec![bet_amount; winners.len()]);
+    }
+    let pot = bet_amount.checked_mul(winners.len() as u64).ok_or(ErrorCode::Overflow)?;
+    let share = pot / winner_count;
+    let mut remainder = pot % winner_count;
+    Ok(winners
+        .iter()
+        .map(|won| {
+            if !won {
+                return 0;
+            }
+            let payout = share + remainder;
+            remainder = 0;
+            payout
+        })
+        .collect())
+}
+
+/// The player's seed reduced to the u64 the `play_chest_game` circuit adds to
+/// its draw. Hashing first means every byte of the seed counts.
+pub fn player_entropy(player_seed: &[u8; 32]) -> u64 {
+    let digest = hash(player_seed).to_bytes();
+    u64::from_le_bytes(digest[..8].try_into().unwrap())
+}
+
+/// Newest entry of the SlotHashes sysvar as (slot, hash). The sysvar is too
+/// big to deserialize on-chain, so this reads it in place: a u64 entry count,
+/// then (slot, hash) pairs, newest first.
+pub fn latest_slot_hash(slot_hashes: &AccountInfo) -> Result<(u64, [u8; 32])> {
+    let data = slot_hashes.try_borrow_data()?;
+    require!(data.len() >= 8 + 8 + 32, ErrorCode::SlotHashUnavailable);
+    let count = u64::from_le_bytes(data[..8].try_into().unwrap());
+    require!(count > 0, ErrorCode::SlotHashUnavailable);
+    let slot = u64::from_le_bytes(data[8..16].try_into().unwrap());
+    Ok((slot, data[16..48].try_into().unwrap()))
+}
+
+/// A slot hash reduced to the u64 the `play_chest_game` circuit adds to its
+/// draw. The hash is already uniform, so its first 8 bytes will do; all
+/// zeros gives 0.
+pub fn slot_entropy(slot_hash: &[u8; 32]) -> u64 {
+    u64::from_le_bytes(slot_hash[..8].try_into().unwrap())
+}
+
+/// Mirror of the circuit's mixing step: the MPC draw shifted by the player's
+/// entropy, mod the draw range
+pub fn mix_player_entropy(draw: u8, entropy: u64, range: u8) -> u8 {
+    let offset = (entropy % range as u64) as u8;
+    (draw + offset) % range
+}
+
+/// The circuit echoes the `num_chests` it ran with; settling against a
+/// different stored count would pay the wrong odds
+pub fn require_echoed_chest_count(echoed: u8, stored: u8) -> Result<()> {
+    require!(echoed == stored, ErrorCode::ParameterMismatch);
+    Ok(())
+}
+
+/// Single source of the account order `play_chest_game_callback` expects;
+/// every instruction that queues the circuit builds its list here
+pub struct CallbackAccounts;
+
+impl CallbackAccounts {
+    /// Named accounts of `PlayChestGameCallback`, ahead of the fee split recipients
+    pub const PLAY_CHEST_GAME_FIXED: usize = 10;
+
+    /// Fixed callback accounts in `PlayChestGameCallback` order, then one
+    /// writable entry per fee split recipient and per loss route treasury
+    #[allow(clippy::too_many_arguments)]
+    pub fn for_play_chest_game(
+        game_account: Pubkey,
+        treasury: Pubkey,
+        player: Pubkey,
+        config: Pubkey,
+        house_stats: Pubkey,
+        fee_split: &Account<FeeSplit>,
+        pending_claim: Pubkey,
+        result_log: Pubkey,
+        player_stats: Pubkey,
+        fairness_proof: Pubkey,
+        loss_routes: &[Pubkey],
+        rewards: &[CallbackAccount],
+    ) -> Result<Vec<CallbackAccount>> {
+        let account = |pubkey: Pubkey, is_writable: bool| CallbackAccount { pubkey, is_writable };
+        let mut accounts = vec![
+            account(game_account, true),
+            account(treasury, true),
+            account(player, true),
+            account(config, false),
+            account(house_stats, true),
+            account(fee_split.key(), false),
+            account(pending_claim, true),
+            account(result_log, true),
+            account(player_stats, true),
+            account(fairness_proof, true),
+        ];
+        accounts.extend(fee_split.shares.iter().map(|share| account(share.recipient, true)));
+        accounts.extend(loss_routes.iter().map(|pool| account(*pool, true)));
+        accounts.extend(rewards.iter().cloned());
+        Self::require_play_chest_game_count(&accounts, fee_split.shares.len() + loss_routes.len() + rewards.len())?;
+        Ok(accounts)
+    }
+
+    /// `PlayPotGameCallback` order: the pot, then MAX_POT_PLAYERS wallets,
+    /// repeating player 0 in unused slots
+    pub fn for_play_pot_game(pot_game: Pubkey, players: &[Pubkey]) -> Vec<CallbackAccount> {
+        let mut accounts = vec![CallbackAccount { pubkey: pot_game, is_writable: true }];
+        accounts.extend((0..MAX_POT_PLAYERS).map(|i| CallbackAccount {
+            pubkey: *players.get(i).unwrap_or(&players[0]),
+            is_writable: true,
+        }));
+        accounts
+    }
+
+    /// A list that drifted from the callback context would only fail once
+    /// the MPC callback lands, after the bet is already escrowed; catch it
+    /// before the computation is queued instead
+    pub fn require_play_chest_game_count(accounts: &[CallbackAccount], extra: usize) -> Result<()> {
+        require!(
+            accounts.len() == Self::PLAY_CHEST_GAME_FIXED + extra,
+            ErrorCode::CallbackAccountsMismatch
+        );
+        Ok(())
+    }
+
+    /// The callback moves lamports between these three, so one account
+    /// passed in two slots would corrupt the accounting
+    pub fn require_distinct(game_account: &Pubkey, treasury: &Pubkey, player: &Pubkey) -> Result<()> {
+        require_keys_neq!(*game_account, *treasury, ErrorCode::CallbackAccountsMismatch);
+        require_keys_neq!(*game_account, *player, ErrorCode::CallbackAccountsMismatch);
+        require_keys_neq!(*treasury, *player, ErrorCode::CallbackAccountsMismatch);
+        Ok(())
+    }
+
+    /// Winnings and refunds go to the callback's `player`, which is only
+    /// checked as writable, so it must be the wallet that placed the bet
+    pub fn require_game_player(player: &Pubkey, game_account: &GameAccount) -> Result<()> {
+        require_keys_eq!(*player, game_account.player, ErrorCode::WrongPlayerAccount);
+        Ok(())
+    }
+}
+
+/// A game a play path is about to open, as `GameAccount::start` stores it
+#[derive(Default)]
+pub struct NewGame {
+    pub player: Pubkey,
+    pub bump: u8, // Game account PDA bump
+    pub computation_offset: u64,
+    pub bet_amount: u64,
+    pub num_chests: u8, // 0 for hidden and odds games
+    pub auto_cancel: bool,
+    pub promo_bonus_bps: u16,
+    pub rounds_total: u8,
+    pub idempotency_key: [u8; 16],
+    pub insured: bool,
+    pub metadata: [u8; 32],
+    pub win_mode: u8,
+    pub hidden_chests: bool,
+    pub win_probability_bps: u16,
+    pub player_seed: [u8; 32],
+    pub auto_compound: bool,
+    pub entropy_slot: u64,
+    pub slot_hash: [u8; 32],
+}
+
+/// Where a new game's bet, fees and tip come from
+pub enum Payer<'a, 'info> {
+    /// The player's wallet, through the system program
+    Wallet {
+        player: &'a AccountInfo<'info>,
+        system_program: &'a AccountInfo<'info>,
+    },
+    /// A program-owned escrow, such as a session, which must stay rent exempt
+    Escrow(&'a AccountInfo<'info>),
+}
+
+impl<'info> Payer<'_, 'info> {
+    pub fn account(&self) -> &AccountInfo<'info> {
+        match self {
+            Payer::Wallet { player, .. } => player,
+            Payer::Escrow(escrow) => escrow,
+        }
+    }
+
+    pub fn pay(&self, to: &AccountInfo<'info>, amount: u64) -> Result<()> {
+        match self {
+            Payer::Wallet { player, system_program } => system_program::transfer(
+                CpiContext::new(
+                    (*system_program).clone(),
+                    system_program::Transfer {
+                        from: (*player).clone(),
+                        to: to.clone(),
+                    },
+                ),
+                amount,
+            ),
+            Payer::Escrow(escrow) => {
+                transfer_lamports(escrow, to, amount)?;
+                require_rent_exempt(escrow)
+            }
+        }
+    }
+}
+
+/// Accounts `start_game` opens a game with, shared by every play path
+pub struct StartAccounts<'a, 'info> {
+    pub payer: Payer<'a, 'info>,
+    pub game_account: &'a mut Account<'info, GameAccount>,
+    pub treasury: &'a mut Account<'info, Treasury>,
+    pub config: &'a GameConfig,
+    pub house_stats: &'a mut Account<'info, HouseStats>,
+    pub player_stats: &'a mut Account<'info, PlayerStats>,
+    pub tip_vault: &'a Account<'info, TipVault>,
+}
+
+/// Checks every play path makes before opening a game: the house key may be
+/// kept from betting house funds, the player must pass the access lists, and
+/// the game account must be free. True, and the caller should do nothing,
+/// when `idempotency_key` retries the play that started the current game.
+pub fn check_new_game(
+    config: &GameConfig,
+    treasury: &Treasury,
+    player: &Pubkey,
+    player_access: &AccountInfo,
+    game: &GameAccount,
+    idempotency_key: &[u8; 16],
+) -> Result<bool> {
+    require!(
+        !(config.block_authority_play && *player == treasury.authority),
+        ErrorCode::AuthorityCannotPlay
+    );
+    validate_player_access(load_player_access(player_access)?.as_ref(), config.allowlist_only)?;
+    if game.is_retried_play(idempotency_key) {
+        return Ok(true);
+    }
+    require!(game.accepts_new_game(), ErrorCode::GameAlreadyActive);
+    Ok(false)
+}
+
+/// Open `new_game`: check the treasury can cover `worst_case` and the payer
+/// the bet, `fees` and `tip`, then hold the bet in the game account, send the
+/// fees to the treasury and the tip to its vault, and reserve the worst case
+/// against the player's exposure cap
+pub fn start_game(accounts: &mut StartAccounts, new_game: &NewGame, fees: u64, tip: u64, worst_case: u64) -> Result<()> {
+    let config = accounts.config;
+    let clock = Clock::get()?;
+    let available = treasury_liquid_lamports(accounts.treasury)?;
+    config.validate_treasury_ready(accounts.treasury.created_at, available, clock.unix_timestamp)?;
+    accounts.treasury.record_play(clock.slot, config.max_games_per_slot)?;
+    require!(available >= worst_case, ErrorCode::InsufficientTreasury);
+
+    let bet_amount = new_game.bet_amount;
+    let spend = bet_amount
+        .checked_add(fees)
+        .and_then(|spend| spend.checked_add(tip))
+        .ok_or(ErrorCode::Overflow)?;
+    validate_player_balance(accounts.payer.account(), spend)?;
+
+    // Flat entry fee (less any loyalty rebate) and any insurance premium go
+    // straight to the treasury and are not refunded; tips go to their own
+    // vault, apart from the treasury's bankroll
+    accounts.payer.pay(&accounts.game_account.to_account_info(), bet_amount)?;
+    if fees > 0 {
+        accounts.payer.pay(&accounts.treasury.to_account_info(), fees)?;
+    }
+    if tip > 0 {
+        accounts.payer.pay(&accounts.tip_vault.to_account_info(), tip)?;
+    }
+
+    accounts.player_stats.record_game_start(bet_amount)?;
+    accounts.house_stats.record_pending_start(config.max_pending_games)?;
+    accounts
+        .game_account
+        .start(new_game, clock.unix_timestamp, config.settle_by(clock.unix_timestamp));
+    accounts
+        .player_stats
+        .reserve_exposure(accounts.game_account, worst_case, config.max_player_exposure)
+}
+
+/// Accounts `settle_round` pays out of, shared by every settlement path
+pub struct SettleAccounts<'a, 'info, 'r> {
+    pub game_account: &'a mut Account<'info, GameAccount>,
+    pub treasury: &'a mut Account<'info, Treasury>,
+    pub player: &'a AccountInfo<'info>,
+    pub config: &'a GameConfig,
+    pub house_stats: &'a mut Account<'info, HouseStats>,
+    pub fee_split: &'a FeeSplit,
+    pub pending_claim: &'a mut Account<'info, PendingClaim>,
+    pub result_log: &'a AccountLoader<'info, ResultLog>,
+    pub player_stats: &'a mut Account<'info, PlayerStats>,
+    pub fairness_proof: &'a mut Account<'info, FairnessProof>,
+    pub remaining_accounts: &'a [AccountInfo<'r>], // Fee split recipients, loss routes and reward accounts
+}
+
+/// Record an auto-compounded win and keep it in the game as the bet of a new
+/// round. Only the player can encrypt the next choice, and the callback can't
+/// CPI back into Arcium to queue it, so the round waits for `play_next_round`.
+/// Until then the player can take the stake back with `cash_out`.
+fn compound_win(accounts: &mut SettleAccounts, payout: u64, winning_chest: u8) -> Result<()> {
+    let game = &accounts.game_account;
+    let bet_amount = game.bet_amount;
+    let player_key = game.player;
+    let num_chests = game.num_chests;
+    let metadata = game.metadata;
+    let player_seed = game.player_seed;
+    let (entropy_slot, slot_hash) = (game.entropy_slot, game.slot_hash);
+    let winnings = payout.checked_sub(bet_amount).ok_or(ErrorCode::Overflow)?;
+
+    transfer_lamports(
+        &accounts.treasury.to_account_info(),
+        &accounts.game_account.to_account_info(),
+        winnings,
+    )?;
+    accounts.house_stats.record_game(bet_amount, payout)?;
+    accounts.result_log.load_mut()?.push(ResultEntry {
+        player: player_key,
+        bet_amount,
+        player_won: true as u8,
+        winning_chest,
+        _padding: [0; 6],
+    });
+
+    // The payout counts as won and then wagered again on a new game
+    accounts.player_stats.record_payout(payout)?;
+    accounts.player_stats.record_game_start(payout)?;
+    emit!(GameResultEvent {
+        player: player_key,
+        player_won: true,
+        pushed: false,
+        winning_chest,
+        num_chests,
+        bet_amount,
+        metadata,
+        payout,
+        player_net_pnl: accounts.player_stats.net_pnl(),
+        player_seed,
+        entropy_slot,
+        slot_hash,
+    });
+    msg!("{}", result_line(&player_key, true, winning_chest, bet_amount, payout));
+
+    // The next round is drawn by MPC whichever source drew this one
+    let game = &mut accounts.game_account;
+    game.bet_amount = payout;
+    game.status = GameStatus::SeriesInProgress as u8;
+    game.rounds_played = 0;
+    game.rounds_won = 0;
+    game.promo_bonus_bps = 0;
+    game.insured = false;
+
+    emit!(WinningsCompoundedEvent {
+        player: player_key,
+        payout,
+        next_bet: payout,
+    });
+
+    msg!("Player WON! Chest {} was correct. {} lamports compounded into the next round", winning_chest, payout);
+    Ok(())
+}
+
+/// Settle one drawn round of a pending game: replay or refund a push, record
+/// a series round, or pay out the win or loss once the game is decided
+pub fn settle_round(accounts: &mut SettleAccounts, outcome: u8, winning_chest: u8) -> Result<()> {
+    let game = &accounts.game_account;
+    // Odds games roll over ODDS_DRAW_RANGE rather than drawing a chest
+    let draw_range = if game.win_probability_bps > 0 { 0 } else { accounts.config.chest_draw_range(game.num_chests) };
+    accounts.fairness_proof.record(game, draw_range, winning_chest);
+
+    settle_round_outcome(accounts, outcome, winning_chest)?;
+    accounts.game_account.settled_by_callback = accounts.game_account.status == GameStatus::Completed as u8;
+
+    // Every round leaves `Pending`; a refunded one was already counted by `record_cancelled`
+    if accounts.game_account.status != GameStatus::Refunded as u8 {
+        accounts.house_stats.record_pending_end();
+    }
+
+    // A replayed push or an undecided series keeps the game's reservation
+    let status = accounts.game_account.status;
+    if status != GameStatus::Pending as u8 && status != GameStatus::SeriesInProgress as u8 {
+        accounts.player_stats.release_exposure(accounts.game_account);
+    }
+
+    // Safe mode refunds a win instead of settling it, so it earns no reward
+    if outcome == RoundOutcome::Win as u8 && status != GameStatus::Refunded as u8 {
+        mint_win_reward(accounts)?;
+    }
+    Ok(())
+}
+
+/// Mint `reward_amount` of the loyalty token to the winner's associated
+/// token account, signed by the reward authority PDA. A reward never blocks
+/// a settlement: with its accounts missing, or a token account the player
+/// hasn't created, the round just goes unrewarded.
+fn mint_win_reward(accounts: &SettleAccounts) -> Result<()> {
+    let config = accounts.config;
+    if config.reward_mint == Pubkey::default() || config.reward_amount == 0 {
+        return Ok(());
+    }
+    let player = accounts.game_account.player;
+    let (authority_key, bump) = reward_authority_pda();
+    let destination_key = get_associated_token_address(&player, &config.reward_mint);
+    let find = |key: &Pubkey| accounts.remaining_accounts.iter().find(|a| a.key == key);
+    let (Some(authority), Some(mint), Some(destination), Some(token_program)) = (
+        find(&authority_key),
+        find(&config.reward_mint),
+        find(&destination_key),
+        find(&token::ID),
+    ) else {
+        msg!("Reward accounts missing, round not rewarded");
+        return Ok(());
+    };
+
+    let ready = mint.owner == &token::ID
+        && destination.owner == &token::ID
+        && match (
+            SplMint::unpack(&mint.try_borrow_data()?),
+            SplTokenAccount::unpack(&destination.try_borrow_data()?),
+        ) {
+            (Ok(mint_state), Ok(account_state)) => {
+                reward_recipient_ready(&mint_state, &account_state, mint.key, &authority_key)
+            }
+            _ => false,
+        };
+    if !ready {
+        msg!("Reward token account not ready, round not rewarded");
+        return Ok(());
+    }
+
+    token::mint_to(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            MintTo {
+                mint: mint.clone(),
+                to: destination.clone(),
+                authority: authority.clone(),
+            },
+            &[&[REWARD_AUTHORITY_SEED, &[bump]]],
+        ),
+        config.reward_amount,
+    )?;
+    emit!(RewardMintedEvent {
+        player,
+        mint: config.reward_mint,
+        amount: config.reward_amount,
+    });
+    Ok(())
+}
+
+/// Whether `mint_to` can succeed: the program holds the mint authority and
+/// the destination is a live account of that mint
+pub fn reward_recipient_ready(
+    mint: &SplMint,
+    destination: &SplTokenAccount,
+    mint_key: &Pubkey,
+    authority: &Pubkey,
+) -> bool {
+    mint.is_initialized
+        && mint.mint_authority == COption::Some(*authority)
+        && destination.mint == *mint_key
+        && destination.state == AccountState::Initialized
+}
+
+fn settle_round_outcome(accounts: &mut SettleAccounts, outcome: u8, winning_chest: u8) -> Result<()> {
+    let player_won = outcome == RoundOutcome::Win as u8;
+    let game = &mut accounts.game_account;
+    let bet_amount = game.bet_amount;
+    let num_chests = game.num_chests;
+    let player_key = game.player;
+    let promo_bonus_bps = game.promo_bonus_bps;
+    let rounds_total = game.rounds_total;
+    let insured = game.insured;
+    let metadata = game.metadata;
+    let win_mode = game.win_mode;
+    let win_probability_bps = game.win_probability_bps;
+    let player_seed = game.player_seed;
+    let (entropy_slot, slot_hash) = (game.entropy_slot, game.slot_hash);
+
+    // A push is replayed within a series, and refunds a single game with no house edge
+    if outcome == RoundOutcome::Push as u8 {
+        if rounds_total > 1 {
+            game.status = GameStatus::SeriesInProgress as u8;
+
+            emit!(RoundResultEvent {
+                player: player_key,
+                round: game.rounds_played + 1,
+                player_won: false,
+                pushed: true,
+                winning_chest,
+                rounds_won: game.rounds_won,
+                rounds_played: game.rounds_played,
+                rounds_total,
+            });
+
+            msg!("Round {} of {} pushed, replaying it", game.rounds_played + 1, rounds_total);
+            return Ok(());
+        }
+
+        settle_refund(accounts, winning_chest)?;
+        msg!("Push on chest {}. Bet refunded.", winning_chest);
+        return Ok(());
+    }
+
+    // Record the round; a series only settles once a majority is decided
+    game.rounds_played = game.rounds_played.checked_add(1).ok_or(ErrorCode::Overflow)?;
+    if player_won {
+        game.rounds_won = game.rounds_won.checked_add(1).ok_or(ErrorCode::Overflow)?;
+    }
+    let rounds_needed = rounds_total / 2 + 1;
+    let rounds_lost = game.rounds_played - game.rounds_won;
+    if game.rounds_won < rounds_needed && rounds_lost < rounds_needed {
+        game.status = GameStatus::SeriesInProgress as u8;
+
+        emit!(RoundResultEvent {
+            player: player_key,
+            round: game.rounds_played,
+            player_won,
+            pushed: false,
+            winning_chest,
+            rounds_won: game.rounds_won,
+            rounds_played: game.rounds_played,
+            rounds_total,
+        });
+
+        msg!(
+            "Round {} of {} settled: won={}, series score {}-{}",
+            game.rounds_played,
+            rounds_total,
+            player_won,
+            game.rounds_won,
+            rounds_lost
+        );
+        return Ok(());
+    }
+    let player_won = game.rounds_won >= rounds_needed;
+
+    // Safe mode pays no winnings: a won game gets its bet back, a lost one settles
+    if player_won && accounts.config.payouts_frozen {
+        refund_pending_game(accounts.game_account, accounts.player, accounts.house_stats, accounts.player_stats)?;
+        msg!("Payouts frozen, {} lamports refunded", bet_amount);
+        return Ok(());
+    }
+
+    let max_loss = accounts.config.max_loss_per_window;
+    if accounts.config.uses_loss_window() {
+        accounts
+            .treasury
+            .roll_loss_window(Clock::get()?.unix_timestamp, accounts.config.window_secs);
+    }
+    let cap_slot = win_cap_slot(num_chests, win_probability_bps);
+    let chest_cap = accounts.config.win_cap_by_chests[cap_slot];
+
+    if player_won {
+        // Player won! Calculate payout: bet * multiplier, clamped to the config cap
+        // Multiplier is the inverse win probability (or the series odds for best-of-N)
+        let payout = if win_probability_bps > 0 {
+            accounts.config.odds_payout_for(bet_amount, win_probability_bps)?
+        } else {
+            accounts
+                .config
+                .payout_for(bet_amount, num_chests, win_mode, rounds_total, promo_bonus_bps)?
+        };
+
+        // Circuit breaker: a win that would push the window's loss past the cap,
+        // or its chest count's winnings past their cap, is refunded as a push
+        let winnings = payout.checked_sub(bet_amount).ok_or(ErrorCode::Overflow)?;
+        if !accounts.treasury.window_allows(winnings, max_loss)
+            || !accounts.treasury.chest_window_allows(cap_slot, winnings, chest_cap)
+        {
+            emit!(CircuitBreakerTrippedEvent {
+                player: player_key,
+                bet_amount,
+                blocked_payout: payout,
+                window_start: accounts.treasury.window_start,
+                window_net_loss: accounts.treasury.window_net_loss,
+            });
+            settle_refund(accounts, winning_chest)?;
+
+            msg!("Circuit breaker tripped, {} lamports refunded instead of {}", bet_amount, payout);
+            return Ok(());
+        }
+        accounts.treasury.record_window_result(winnings, 0);
+        accounts.treasury.record_chest_win(cap_slot, winnings);
+
+        // Large wins are escrowed in the pending claim instead of paid out instantly.
+        // Each game passed the solvency check when it was queued, but several
+        // wins settling together can still drain the treasury: a win it can no
+        // longer cover is escrowed with its winnings owed, rather than failing
+        let shortfall = winnings > treasury_available_lamports(&accounts.treasury.to_account_info())?;
+        let lock_slots = accounts.config.winnings_lock_slots;
+        let delayed = shortfall || accounts.config.escrows_payout(payout);
+
+        // An auto-compounding win stays in the game as the next bet, unless it
+        // would have been escrowed or locked, or the caps or the player's
+        // exposure limit say to pay it out instead
+        let available = treasury_liquid_lamports(accounts.treasury)?;
+        if !delayed {
+            if let Some((next_bet, next_exposure)) = accounts.config.compounded_bet(accounts.game_account, payout, available)? {
+                let cap = accounts.config.max_player_exposure;
+                if accounts.player_stats.reserve_exposure(accounts.game_account, next_exposure, cap).is_ok() {
+                    return compound_win(accounts, next_bet, winning_chest);
+                }
+            }
+        }
+        let payee = if delayed {
+            accounts.pending_claim.to_account_info()
+        } else {
+            accounts.player.clone()
+        };
+
+        // First return the original bet from game account
+        transfer_lamports(
+            &accounts.game_account.to_account_info(),
+            &payee,
+            bet_amount,
+        )?;
+        require_rent_exempt(&accounts.game_account.to_account_info())?;
+
+        // Then pay winnings from treasury (payout - bet = net winnings)
+        // Effective multiplier (payout / bet) with 4 decimals, for support and disputes
+        let multiplier_bps = (payout as u128 * 10_000 / bet_amount as u128) as u64;
+        msg!(
+            "multiplier={}.{:04}, gross={}, net={}",
+            multiplier_bps / 10_000,
+            multiplier_bps % 10_000,
+            payout,
+            winnings
+        );
+
+        if winnings > 0 && !shortfall {
+            transfer_lamports(
+                &accounts.treasury.to_account_info(),
+                &payee,
+                winnings,
+            )?;
+        }
+
+        if delayed {
+            let clock = Clock::get()?;
+            let claimable_at = clock
+                .unix_timestamp
+                .checked_add(accounts.config.claim_delay_secs)
+                .ok_or(ErrorCode::Overflow)?;
+            // Slot-locked wins can't be spent in the block that settled them
+            let claimable_slot = clock.slot.checked_add(lock_slots).ok_or(ErrorCode::Overflow)?;
+            let (funded, owed) = if shortfall { (bet_amount, winnings) } else { (payout, 0) };
+            accounts.pending_claim.escrow(funded, owed, claimable_at, claimable_slot)?;
+
+            emit!(WinningsEscrowedEvent {
+                player: player_key,
+                amount: payout,
+                claimable_at,
+                owed,
+                claimable_slot,
+            });
+        }
+
+        // Update game status
+        accounts.game_account.status = GameStatus::Completed as u8;
+        accounts.house_stats.record_game(bet_amount, payout)?;
+        
+        accounts.result_log.load_mut()?.push(ResultEntry {
+            player: player_key,
+            bet_amount,
+            player_won: true as u8,
+            winning_chest,
+            _padding: [0; 6],
+        });
+
+        accounts.player_stats.record_payout(payout)?;
+        emit!(GameResultEvent {
+            player: player_key,
+            player_won: true,
+            pushed: false,
+            winning_chest,
+            num_chests,
+            bet_amount,
+            metadata,
+            payout,
+            player_net_pnl: accounts.player_stats.net_pnl(),
+            player_seed,
+            entropy_slot,
+            slot_hash,
+        });
+        msg!("{}", result_line(&player_key, true, winning_chest, bet_amount, payout));
+
+        msg!("Player WON! Chest {} was correct. Paid out {} lamports", winning_chest, payout);
+    } else {
+        // Player lost - bet goes to treasury, which refunds the insured share
+        let refund = if insured { accounts.config.insured_refund(bet_amount) } else { 0 };
+        let refund_bps = (refund as u128 * 10_000 / bet_amount as u128) as u64;
+        msg!("multiplier=0.{:04}, gross={}, net=0", refund_bps, refund);
+        transfer_lamports(
+            &accounts.game_account.to_account_info(),
+            &accounts.treasury.to_account_info(),
+            bet_amount,
+        )?;
+        require_rent_exempt(&accounts.game_account.to_account_info())?;
+
+        // Route each beneficiary's share of what the player lost after the
+        // insured refund, so the shares and refund never pay out more than the
+        // bet; a recipient missing from the callback accounts (split changed
+        // mid-game) leaves it in the treasury
+        let lost = bet_amount.saturating_sub(refund);
+        let mut routed = 0u64;
+        for share in accounts.fee_split.shares.iter() {
+            let amount = share.amount_of(lost);
+            if amount == 0 {
+                continue;
+            }
+            if let Some(recipient) = accounts.remaining_accounts.iter().find(|a| a.key() == share.recipient) {
+                transfer_lamports(&accounts.treasury.to_account_info(), recipient, amount)?;
+                routed += amount;
+            }
+        }
+        // The house's kept share is spread over the treasuries by weight. The
+        // cold treasury must already exist, or its share stays hot like a
+        // missing fee recipient's
+        let kept = lost.saturating_sub(routed);
+        let cold_share = accounts.config.cold_loss_share(kept);
+        let cold_key = cold_treasury_pda().0;
+        if cold_share > 0 {
+            if let Some(cold) = accounts
+                .remaining_accounts
+                .iter()
+                .find(|a| a.key() == cold_key && a.owner == &crate::ID)
+            {
+                transfer_lamports(&accounts.treasury.to_account_info(), cold, cold_share)?;
+            }
+        }
+        if refund > 0 {
+            transfer_lamports(
+                &accounts.treasury.to_account_info(),
+                accounts.player,
+                refund,
+            )?;
+        }
+        accounts.treasury.record_window_result(0, kept);
+
+        // Update game status
+        accounts.game_account.status = GameStatus::Completed as u8;
+        accounts.house_stats.record_game(bet_amount, refund)?;
+
+        accounts.result_log.load_mut()?.push(ResultEntry {
+            player: player_key,
+            bet_amount,
+            player_won: false as u8,
+            winning_chest,
+            _padding: [0; 6],
+        });
+
+        accounts.player_stats.record_payout(refund)?;
+        emit!(GameResultEvent {
+            player: player_key,
+            player_won: false,
+            pushed: false,
+            winning_chest,
+            num_chests,
+            bet_amount,
+            metadata,
+            payout: refund,
+            player_net_pnl: accounts.player_stats.net_pnl(),
+            player_seed,
+            entropy_slot,
+            slot_hash,
+        });
+        msg!("{}", result_line(&player_key, false, winning_chest, bet_amount, refund));
+
+        msg!(
+            "Player lost. Winning chest was {}. Bet kept by treasury, {} lamports insured refund.",
+            winning_chest,
+            refund
+        );
+    }
+
+    Ok(())
+}
+
+/// Settle a decided game as a push: the bet goes back to the player with no
+/// house edge, and it's recorded and logged like any other result
+fn settle_refund(accounts: &mut SettleAccounts, winning_chest: u8) -> Result<()> {
+    let game = &accounts.game_account;
+    let bet_amount = game.bet_amount;
+    let player_key = game.player;
+    let num_chests = game.num_chests;
+    let metadata = game.metadata;
+    let player_seed = game.player_seed;
+    let (entropy_slot, slot_hash) = (game.entropy_slot, game.slot_hash);
+
+    msg!("multiplier=1.0000, gross={}, net=0", bet_amount);
+    transfer_lamports(
+        &accounts.game_account.to_account_info(),
+        accounts.player,
+        bet_amount,
+    )?;
+    require_rent_exempt(&accounts.game_account.to_account_info())?;
+
+    accounts.game_account.status = GameStatus::Completed as u8;
+    accounts.house_stats.record_game(bet_amount, bet_amount)?;
+
+    accounts.result_log.load_mut()?.push(ResultEntry {
+        player: player_key,
+        bet_amount,
+        player_won: false as u8,
+        winning_chest,
+        _padding: [0; 6],
+    });
+
+    accounts.player_stats.record_payout(bet_amount)?;
+    emit!(GameResultEvent {
+        player: player_key,
+        player_won: false,
+        pushed: true,
+        winning_chest,
+        num_chests,
+        bet_amount,
+        metadata,
+        payout: bet_amount,
+        player_net_pnl: accounts.player_stats.net_pnl(),
+        player_seed,
+        entropy_slot,
+        slot_hash,
+    });
+    msg!("{}", result_line(&player_key, false, winning_chest, bet_amount, bet_amount));
+    Ok(())
+}
+
+/// Machine-readable summary of a settled game for log scrapers, kept stable
+/// independently of the events:
+/// `RESULT|<player>|<won: 0 or 1>|<winning_chest>|<bet_amount>|<payout>`,
+/// with the player in base58 and amounts in lamports. Pushes and refunds
+/// log as a loss with the refunded amount as the payout. Only append fields.
+pub fn result_line(player: &Pubkey, player_won: bool, winning_chest: u8, bet_amount: u64, payout: u64) -> String {
+    format!("RESULT|{}|{}|{}|{}|{}", player, player_won as u8, winning_chest, bet_amount, payout)
+}
+
+/// Treasury lamports new games can be covered from: what can be paid out,
+/// less the `reserve` the authority holds back
+pub fn treasury_liquid_lamports(treasury: &Account<Treasury>) -> Result<u64> {
+    Ok(treasury_available_lamports(&treasury.to_account_info())?.saturating_sub(treasury.reserve))
+}
+
+/// Treasury lamports above its rent-exempt minimum, i.e. what can be paid out
+pub fn treasury_available_lamports(treasury: &AccountInfo) -> Result<u64> {
+    let rent_floor = Rent::get()?.minimum_balance(treasury.data_len());
+    Ok(treasury.lamports().saturating_sub(rent_floor))
+}
+
+/// Check that the instruction right before this one is an ed25519 program
+/// verification of `message` signed by `signer` with `signature`.
+pub fn verify_ed25519_ix(
+    instructions_sysvar: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+    signature: &[u8; 64],
+) -> Result<()> {
+    let current = load_current_index_checked(instructions_sysvar)?;
+    require!(current > 0, ErrorCode::InvalidPromoSignature);
+    let ix = load_instruction_at_checked(current as usize - 1, instructions_sysvar)?;
+    require_keys_eq!(ix.program_id, ed25519_program::ID, ErrorCode::InvalidPromoSignature);
+
+    // Layout: [num_signatures, padding, Ed25519SignatureOffsets (7 x u16), ...data]
+    let data = &ix.data;
+    require!(data.len() >= 16 && data[0] == 1, ErrorCode::InvalidPromoSignature);
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let slice = |offset: u16, len: usize| {
+        data.get(offset as usize..offset as usize + len)
+            .ok_or(ErrorCode::InvalidPromoSignature)
+    };
+
+    // All offsets must point into the ed25519 instruction itself
+    require!(
+        read_u16(4) == u16::MAX && read_u16(8) == u16::MAX && read_u16(14) == u16::MAX,
+        ErrorCode::InvalidPromoSignature
+    );
+    require!(slice(read_u16(2), 64)? == signature, ErrorCode::InvalidPromoSignature);
+    require!(slice(read_u16(6), 32)? == signer.as_ref(), ErrorCode::InvalidPromoSignature);
+    require!(
+        slice(read_u16(10), read_u16(12) as usize)? == message,
+        ErrorCode::InvalidPromoSignature
+    );
+    Ok(())
+}
+
+/// Create the redemption PDA for a promo code, failing if it already exists
+fn redeem_promo<'info>(
+    redemption: &UncheckedAccount<'info>,
+    player: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    payload: &PromoPayload,
+) -> Result<()> {
+    let (expected, bump) = promo_pda(&payload.code_id);
+    require_keys_eq!(redemption.key(), expected, ErrorCode::InvalidPromo);
+    require!(redemption.data_is_empty(), ErrorCode::PromoAlreadyRedeemed);
+
+    system_program::create_account(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            system_program::CreateAccount {
+                from: player.to_account_info(),
+                to: redemption.to_account_info(),
+            },
+            &[&[PROMO_SEED, &payload.code_id, &[bump]]],
+        ),
+        Rent::get()?.minimum_balance(PromoRedemption::SPACE),
+        PromoRedemption::SPACE as u64,
+        &ID,
+    )?;
+
+    let record = PromoRedemption {
+        code_id: payload.code_id,
+        player: player.key(),
+        redeemed_at: Clock::get()?.unix_timestamp,
+        bump,
+    };
+    record.try_serialize(&mut &mut redemption.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+// ============= Events =============
+
+/// Emitted when a game is queued. `pub_key` and `nonce` are the player's
+/// encryption context so a client can recover its state from logs alone.
+#[event]
+pub struct GameStartedEvent {
+    pub player: Pubkey,
+    pub computation_offset: u64,
+    pub num_chests: u8,
+    pub bet_amount: u64,
+    pub pub_key: [u8; 32],
+    pub nonce: u128,
+    pub metadata: [u8; 32],
+    pub tip: u64, // Lamports tipped to the tip vault on top of the bet
+    pub win_probability_bps: u16, // Chance a round wins; 0 for hidden games until settlement
+}
+
+#[event]
+pub struct GameResultEvent {
+    pub player: Pubkey,
+    pub player_won: bool,
+    pub pushed: bool, // Bet refunded; `payout` equals `bet_amount`
+    pub winning_chest: u8,
+    pub num_chests: u8,
+    pub bet_amount: u64,
+    pub payout: u64,
+    pub metadata: [u8; 32],
+    pub player_net_pnl: i64, // The player's lifetime PlayerStats::net_pnl after this game
+    pub player_seed: [u8; 32], // Seed whose `player_entropy` was mixed into the draw
+    pub entropy_slot: u64,     // Slot whose hash was mixed into the draw, 0 for none
+    pub slot_hash: [u8; 32],
+}
+
+/// Emitted after each non-final round of a best-of-N series
+#[event]
+pub struct RoundResultEvent {
+    pub player: Pubkey,
+    pub round: u8,
+    pub player_won: bool,
+    pub pushed: bool, // Round doesn't count and will be replayed
+    pub winning_chest: u8,
+    pub rounds_won: u8,
+    pub rounds_played: u8,
+    pub rounds_total: u8,
+}
+
+/// A win was refunded as a push because paying it would exceed the window's loss cap
+#[event]
+pub struct CircuitBreakerTrippedEvent {
+    pub player: Pubkey,
+    pub bet_amount: u64,
+    pub blocked_payout: u64,
+    pub window_start: i64,
+    pub window_net_loss: u64,
+}
+
+#[event]
+pub struct BetToppedUpEvent {
+    pub player: Pubkey,
+    pub additional: u64,
+    pub bet_amount: u64, // New total bet
+}
+
+#[event]
+pub struct TreasuryFundedEvent {
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub new_balance: u64, // Treasury lamports after the deposit, rent included
+}
+
+#[event]
+pub struct EmergencyDrainScheduledEvent {
+    pub authority: Pubkey,
+    pub destination: Pubkey,
+    pub executable_at: i64,
+}
+
+#[event]
+pub struct EmergencyDrainExecutedEvent {
+    pub authority: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64, // Hot plus cold lamports moved, rent left behind
+}
+
+#[event]
+pub struct TipsWithdrawnEvent {
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+}
+
+#[event]
+pub struct PayoutsFrozenEvent {
+    pub authority: Pubkey,
+    pub frozen: bool,
+}
+
+#[event]
+pub struct TreasuryReserveSetEvent {
+    pub authority: Pubkey,
+    pub reserve: u64,
+    pub liquid: u64, // Treasury lamports left to cover new games
+}
+
+#[event]
+pub struct TreasuryRebalancedEvent {
+    pub swept: u64,
+    pub hot_balance: u64,
+    pub cold_balance: u64,
+}
+
+#[event]
+pub struct StakedEvent {
+    pub staker: Pubkey,
+    pub amount: u64,
+    pub total_staked: u64,
+}
+
+#[event]
+pub struct UnstakedEvent {
+    pub staker: Pubkey,
+    pub amount: u64,
+    pub profit: u64,
+    pub total_staked: u64,
+}
+
+#[event]
+pub struct ProfitsDistributedEvent {
+    pub amount: u64,
+    pub total_staked: u64,
+}
+
+#[event]
+pub struct RewardMintedEvent {
+    pub player: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct WinningsCompoundedEvent {
+    pub player: Pubkey,
+    pub payout: u64,
+    pub next_bet: u64, // Held in the game account until `play_next_round` or `cash_out`
+}
+
+#[event]
+pub struct GameCashedOutEvent {
+    pub player: Pubkey,
+    pub amount: u64, // Stake returned from the game account
+}
+
+#[event]
+pub struct WinningsEscrowedEvent {
+    pub player: Pubkey,
+    pub amount: u64,
+    pub claimable_at: i64,
+    pub owed: u64, // Part of `amount` the treasury couldn't cover yet
+    pub claimable_slot: u64,
+}
+
+#[event]
+pub struct WinningsClaimedEvent {
+    pub player: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct PotGameStartedEvent {
+    pub pot: Pubkey,
+    pub players: [Pubkey; MAX_POT_PLAYERS], // Unused slots are Pubkey::default()
+    pub num_chests: u8,
+    pub bet_amount: u64, // Staked by each player
+    pub computation_offset: u64,
+}
+
+#[event]
+pub struct PotGameResultEvent {
+    pub pot: Pubkey,
+    pub winning_chest: u8,
+    pub sampled: bool, // False if the draw fell back; every bet is refunded
+    pub winners: [bool; MAX_POT_PLAYERS],
+    pub payouts: [u64; MAX_POT_PLAYERS], // Lamports paid to each player, bet included
+}
+
+#[event]
+pub struct PlayerAccessChangedEvent {
+    pub player: Pubkey,
+    pub blocked: bool,
+    pub allowed: bool,
+}
+
+#[event]
+pub struct WinningsClaimedForEvent {
+    pub player: Pubkey,
+    pub keeper: Pubkey,
+    pub amount: u64, // Paid to the player, after the keeper fee
+    pub keeper_fee: u64,
+}
+
+#[event]
+pub struct GameCancelledEvent {
+    pub player: Pubkey,
+    pub bet_amount: u64,
+    pub computation_offset: u64,
+    pub created_at: i64,
+}
+
+#[event]
+pub struct GameRefundedEvent {
+    pub player: Pubkey,
+    pub bet_amount: u64,
+    pub computation_offset: u64,
+    pub created_at: i64,
+}
+
+#[event]
+pub struct DisputeRaisedEvent {
+    pub player: Pubkey,
+    pub reason_code: u8,
+    pub computation_offset: u64,
+    pub created_at: i64,
+}
+
+#[event]
+pub struct DisputeResolvedEvent {
+    pub player: Pubkey,
+    pub refunded: bool, // false = force-settled as a loss
+    pub bet_amount: u64,
+    pub computation_offset: u64,
+}
+
+#[event]
+pub struct RawDrawRevealedEvent {
+    pub player: Pubkey,
+    pub raw_draw: u8,       // RNG draw in [0, draw_range) before the shift
+    pub entropy_offset: u8, // player_entropy plus slot_entropy, mod draw_range
+    pub draw_range: u8,
+    pub winning_chest: u8,  // (raw_draw + entropy_offset) mod draw_range
+}
+
+#[event]
+pub struct OddsGameRolledEvent {
+    pub player: Pubkey,
+    pub roll: u16, // In [0, 10000); see the play_odds_game circuit for which side wins
+    pub win_probability_bps: u16,
+}
+
+// ============= Errors =============
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("The computation was aborted")]
+    AbortedComputation,
+    #[msg("Cluster not set")]
+    ClusterNotSet,
+    #[msg("Too few chests - minimum 2")]
+    ChestCountTooLow,
+    #[msg("Bet amount too small - minimum 0.01 SOL")]
+    BetTooSmall,
     #[msg("Player already has an active game")]
     GameAlreadyActive,
     #[msg("Game is not in pending status")]
@@ -500,4 +7113,1878 @@ pub enum ErrorCode {
     Overflow,
     #[msg("Not the game player")]
     NotGamePlayer,
+    #[msg("Insufficient lamports for transfer")]
+    InsufficientFunds,
+    #[msg("Game did not opt into auto-cancel")]
+    AutoCancelNotEnabled,
+    #[msg("Signer is not the authority")]
+    Unauthorized,
+    #[msg("Treasury cannot cover the worst-case payout")]
+    InsufficientTreasury,
+    #[msg("Promo codes are not enabled")]
+    PromosDisabled,
+    #[msg("Promo redemption account does not match the promo code")]
+    InvalidPromo,
+    #[msg("Promo code signature is invalid")]
+    InvalidPromoSignature,
+    #[msg("Promo code has already been redeemed")]
+    PromoAlreadyRedeemed,
+    #[msg("Settlement would drop the account below rent exemption")]
+    WouldBreakRentExemption,
+    #[msg("Series length must be odd")]
+    RoundCountEven,
+    #[msg("No best-of-N series is awaiting its next round")]
+    SeriesNotInProgress,
+    #[msg("Computation account does not belong to this game")]
+    InvalidComputationAccount,
+    #[msg("Computation is still queued; wait for the hard timeout to cancel")]
+    ComputationStillQueued,
+    #[msg("Bet amount exceeds max_bet")]
+    BetTooLarge,
+    #[msg("Insurance is not offered")]
+    InsuranceDisabled,
+    #[msg("Basis point settings must not exceed 10000")]
+    InvalidConfig,
+    #[msg("Fee split needs at most 4 recipients with bps summing to <= 10000")]
+    InvalidFeeSplit,
+    #[msg("Too many chests - maximum 5")]
+    ChestCountTooHigh,
+    #[msg("Too many rounds - maximum best of 9")]
+    RoundCountTooHigh,
+    #[msg("Encryption public key is a low-order x25519 point")]
+    PubkeyMalformed,
+    #[msg("Promo payload and signature must be supplied together")]
+    PromoIncomplete,
+    #[msg("Promo code needs its redemption account")]
+    PromoRedemptionMissing,
+    #[msg("Session has expired")]
+    SessionExpired,
+    #[msg("Bet would exceed the session spend cap")]
+    SessionCapExceeded,
+    #[msg("Signer is not this session's key")]
+    InvalidSessionKey,
+    #[msg("No escrowed winnings to claim")]
+    NothingToClaim,
+    #[msg("Claim delay has not passed yet")]
+    ClaimNotReady,
+    #[msg("Circuit ran with different parameters than the stored game")]
+    ParameterMismatch,
+    #[msg("Remaining accounts must be one (game, player) pair per listed player")]
+    BatchAccountsMismatch,
+    #[msg("The treasury authority may not play")]
+    AuthorityCannotPlay,
+    #[msg("Treasury could not cover the worst-case payout under this config")]
+    ConfigWouldBeInsolvent,
+    #[msg("Win mode must be exact, or near-miss with at least 4 chests")]
+    InvalidWinMode,
+    #[msg("Treasury is already initialized")]
+    TreasuryAlreadyInitialized,
+    #[msg("Game config is already initialized; use update_config to change it")]
+    ConfigAlreadyInitialized,
+    #[msg("Account is not a game account of this program")]
+    InvalidGameAccount,
+    #[msg("Win probability must be between 2000 and 9999 bps")]
+    InvalidWinProbability,
+    #[msg("Hot treasury is below hot_floor; refill it before rebalancing")]
+    TreasuryBelowFloor,
+    #[msg("Treasury is too new or underfunded to accept games yet")]
+    TreasuryNotReady,
+    #[msg("Game already has a dispute raised")]
+    GameAlreadyDisputed,
+    #[msg("Game has no dispute to resolve")]
+    GameNotDisputed,
+    #[msg("Stake amount must be positive and no more than the current stake")]
+    InvalidStakeAmount,
+    #[msg("No stake to distribute profits to")]
+    NoStakers,
+    #[msg("Game's RNG draw range exceeds the configured max_rng_range")]
+    RngRangeTooLarge,
+    #[msg("Too many games started this slot, try again")]
+    RateLimited,
+    #[msg("Fairness proof does not verify")]
+    FairnessProofMismatch,
+    #[msg("Funding amount is below the minimum deposit")]
+    FundingTooSmall,
+    #[msg("Keeper claims are disabled")]
+    KeeperClaimsDisabled,
+    #[msg("Callback account list does not match the callback's expected accounts")]
+    CallbackAccountsMismatch,
+    #[msg("Player is blocked from starting games")]
+    PlayerBlocked,
+    #[msg("Only allowlisted players may start games")]
+    PlayerNotAllowlisted,
+    #[msg("House edge must be between MIN_HOUSE_EDGE_BPS and MAX_HOUSE_EDGE_BPS")]
+    HouseEdgeOutOfBounds,
+    #[msg("Pot games need 2 or 3 distinct players with one entry each")]
+    InvalidPotPlayers,
+    #[msg("Game would take the player's outstanding exposure past max_player_exposure")]
+    PlayerExposureExceeded,
+    #[msg("Percentage bets take 1 to 10000 bps of the balance")]
+    InvalidBetBps,
+    #[msg("Emergency drain is still timelocked")]
+    DrainTimelocked,
+    #[msg("Client was built for another PROGRAM_VERSION; upgrade it")]
+    VersionMismatch,
+    #[msg("Player balance can't cover the bet, fees and tip and stay rent exempt")]
+    InsufficientPlayerBalance,
+    #[msg("Computation definition is not ready; finish its init_*_comp_def first")]
+    CompDefNotInitialized,
+    #[msg("Loss routing weights must sum to 10000, or all be 0")]
+    InvalidLossRouting,
+    #[msg("Config was updated too recently; wait out min_config_update_interval")]
+    ConfigUpdateTooSoon,
+    #[msg("SlotHashes sysvar has no entry to mix into the draw")]
+    SlotHashUnavailable,
+    #[msg("Too many games are pending; try again once some settle")]
+    TooManyPendingGames,
+    #[msg("Callback player is not the game's player")]
+    WrongPlayerAccount,
+    #[msg("Promo bonus exceeds MAX_PROMO_BONUS_BPS")]
+    PromoBonusTooHigh,
+    #[msg("Bet can't be topped up once its round is queued")]
+    TopUpAfterQueue,
+    #[msg("Promo code was issued to a different player")]
+    PromoWrongPlayer,
+    #[msg("Promo code has expired")]
+    PromoExpired,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A config with every limit and feature off, for tests to override
+    fn test_config() -> GameConfig {
+        GameConfig {
+            authority: Pubkey::default(),
+            max_payout: 0,
+            promo_pubkey: Pubkey::default(),
+            round_in_favor_of_player: false,
+            entry_fee: 0,
+            push_enabled: false,
+            insurance_bps: 0,
+            insurance_premium_bps: 0,
+            instant_payout_ceiling: 0,
+            claim_delay_secs: 0,
+            block_authority_play: false,
+            max_bet: 0,
+            max_loss_per_window: 0,
+            window_secs: 0,
+            tier_thresholds: [0; LOYALTY_TIERS],
+            tier_rebate_bps: [0; LOYALTY_TIERS],
+            game_timeout_secs: 0,
+            hot_ceiling: 0,
+            hot_floor: 0,
+            min_treasury_age_secs: 0,
+            min_treasury_balance: 0,
+            max_rng_range: 0,
+            max_games_per_slot: 0,
+            min_funding: 0,
+            keeper_claim_grace_secs: 0,
+            keeper_fee_bps: 0,
+            allowlist_only: false,
+            win_cap_by_chests: [0; WIN_CAP_SLOTS],
+            winnings_lock_slots: 0,
+            max_player_exposure: 0,
+            payouts_frozen: false,
+            first_game_bonus_bps: 0,
+            reveal_raw_draw: false,
+            credit_wins_to_claim: false,
+            loss_route_bps: [0; TREASURY_POOLS],
+            min_config_update_interval: 0,
+            last_config_update: 0,
+            mix_slot_hash: false,
+            max_pending_games: 0,
+            reward_mint: Pubkey::default(),
+            reward_amount: 0,
+            house_edge_bps: 0,
+            max_rake_per_game: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn transfer_lamports_underflow_returns_error() {
+        let owner = ID;
+        let (from_key, to_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut from_lamports, mut to_lamports) = (100u64, 0u64);
+        let (mut from_data, mut to_data) = (vec![], vec![]);
+        let from = AccountInfo::new(&from_key, false, true, &mut from_lamports, &mut from_data, &owner, false, 0);
+        let to = AccountInfo::new(&to_key, false, true, &mut to_lamports, &mut to_data, &owner, false, 0);
+
+        let err = transfer_lamports(&from, &to, 101).unwrap_err();
+        assert_eq!(err, ErrorCode::InsufficientFunds.into());
+        assert_eq!(from.lamports(), 100);
+        assert_eq!(to.lamports(), 0);
+
+        transfer_lamports(&from, &to, 100).unwrap();
+        assert_eq!(from.lamports(), 0);
+        assert_eq!(to.lamports(), 100);
+    }
+
+    #[test]
+    fn computation_is_queued_reads_status() {
+        use arcium_client::idl::arcium::types::ExecutionFee;
+
+        let computation = |status| ComputationAccount {
+            payer: Pubkey::default(),
+            mxe_program_id: crate::ID,
+            computation_definition_offset: 0,
+            execution_fee: ExecutionFee {
+                base_fee: 0,
+                priority_fee: 0,
+                output_delivery_fee: 0,
+            },
+            slot: 0,
+            slot_counter: 0,
+            status,
+            arguments: ArgumentList {
+                args: vec![],
+                byte_arrays: vec![],
+                plaintext_numbers: vec![],
+                values_128_bit: vec![],
+                accounts: vec![],
+            },
+            custom_callback_instructions: vec![],
+            callback_transactions_required: 0,
+            callback_transactions_submitted_bm: 0,
+            bump: 0,
+        };
+        let key = Pubkey::new_unique();
+
+        for (status, queued) in [(ComputationStatus::Queued, true), (ComputationStatus::Finalized, false)] {
+            let mut data = vec![];
+            computation(status).try_serialize(&mut data).unwrap();
+            let mut lamports = 1;
+            let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &ARCIUM_PROG_ID, false, 0);
+            assert_eq!(computation_is_queued(&info).unwrap(), queued);
+        }
+
+        // A closed account has left the queue
+        let mut lamports = 0;
+        let mut data = vec![];
+        let system = Pubkey::default();
+        let closed = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &system, false, 0);
+        assert!(!computation_is_queued(&closed).unwrap());
+    }
+
+    #[test]
+    fn payout_is_clamped_to_max_payout() {
+        let config = GameConfig { max_payout: 300, ..test_config() };
+        assert_eq!(config.payout_for(50, 5, 0, 1, 0).unwrap(), 250);
+        assert_eq!(config.payout_for(100, 5, 0, 1, 0).unwrap(), 300);
+        // Never pay back less than the stake
+        assert_eq!(config.payout_for(400, 2, 0, 1, 0).unwrap(), 400);
+
+        let uncapped = GameConfig { max_payout: 0, ..config };
+        assert_eq!(uncapped.payout_for(100, 5, 0, 1, 0).unwrap(), 500);
+        // Promo bonus applies on top of the gross payout
+        assert_eq!(uncapped.payout_for(100, 5, 0, 1, 1_000).unwrap(), 550);
+        // Best of 3 coin flips is a 50% shot, so it pays 2x
+        assert_eq!(uncapped.payout_for(100, 2, 0, 3, 0).unwrap(), 200);
+        // Near-miss on 5 chests wins 3 in 5, so it pays 5/3x
+        assert_eq!(uncapped.payout_for(300, 5, 1, 1, 0).unwrap(), 500);
+    }
+
+    #[test]
+    fn payout_rounding_direction_is_configurable() {
+        let house = test_config();
+        let player = GameConfig { round_in_favor_of_player: true, ..house };
+        // Best of 3 with 3 chests pays 27/7x: 100 * 27 / 7 = 385.71
+        assert_eq!(house.payout_for(100, 3, 0, 3, 0).unwrap(), 385);
+        assert_eq!(player.payout_for(100, 3, 0, 3, 0).unwrap(), 386);
+        // 3.3% promo bonus on a 3x win: 101 * 3 * 1.033 = 312.999
+        assert_eq!(house.payout_for(101, 3, 0, 1, 330).unwrap(), 312);
+        assert_eq!(player.payout_for(101, 3, 0, 1, 330).unwrap(), 313);
+        // Exact results are not bumped
+        assert_eq!(player.payout_for(100, 5, 0, 1, 0).unwrap(), 500);
+        // The exposure check always assumes the player-favorable rounding
+        assert_eq!(house.worst_case_payout_for(100, 3, 0, 3, 0).unwrap(), 386);
+    }
+
+    #[test]
+    fn odds_payouts_are_the_inverse_probability() {
+        let house = test_config();
+        let player = GameConfig { round_in_favor_of_player: true, ..house };
+        // 20% pays 5x, the same as one chest in five
+        assert_eq!(house.odds_payout_for(100, 2_000).unwrap(), 500);
+        assert_eq!(house.payout_for(100, 5, 0, 1, 0).unwrap(), 500);
+        // 50% pays 2x
+        assert_eq!(house.odds_payout_for(100, 5_000).unwrap(), 200);
+        // 33.33% pays 3.0003x: 100 * 10000 / 3333 = 300.03
+        assert_eq!(house.odds_payout_for(100, 3_333).unwrap(), 300);
+        assert_eq!(player.odds_payout_for(100, 3_333).unwrap(), 301);
+        assert_eq!(house.worst_case_odds_payout_for(100, 3_333).unwrap(), 301);
+        // 99% pays 1.0101x: 10_000 * 10000 / 9900 = 10_101.01
+        assert_eq!(house.odds_payout_for(10_000, 9_900).unwrap(), 10_101);
+        // max_payout clamps odds games too
+        let capped = GameConfig { max_payout: 150, ..house };
+        assert_eq!(capped.odds_payout_for(100, 5_000).unwrap(), 150);
+
+        assert!(validate_win_probability(MIN_WIN_PROBABILITY_BPS).is_ok());
+        assert!(validate_win_probability(MAX_WIN_PROBABILITY_BPS).is_ok());
+        assert!(validate_win_probability(MIN_WIN_PROBABILITY_BPS - 1).is_err());
+        assert!(validate_win_probability(10_000).is_err());
+    }
+
+    #[test]
+    fn max_exposure_covers_the_longest_series_with_every_bonus() {
+        let config = test_config();
+        assert_eq!(config.max_exposure().unwrap(), None);
+        assert!(config.validate_bet_cap(u64::MAX).is_ok());
+        assert!(config.validate_solvency(0).is_ok());
+
+        // A best-of-9 on 5 chests pays far more than a single 5-chest round
+        let bet = 1_000_000;
+        let limited = GameConfig { max_bet: bet, ..config };
+        let series = limited.worst_case_payout_for(bet, MAX_CHESTS, WinMode::Exact as u8, MAX_SERIES_ROUNDS, 0).unwrap();
+        assert_eq!(limited.max_exposure().unwrap(), Some(series - bet));
+        assert!(series - bet > bet * (MAX_CHESTS as u64 - 1));
+        assert!(limited.validate_bet_cap(bet + 1).is_err());
+        assert!(limited.validate_solvency(series - bet).is_ok());
+        assert_eq!(
+            limited.validate_solvency(series - bet - 1).unwrap_err(),
+            error!(ErrorCode::ConfigWouldBeInsolvent)
+        );
+
+        // Promos, once enabled, and the first-game bonus both raise it
+        let bonus = |bonus_bps| {
+            limited.worst_case_payout_for(bet, MAX_CHESTS, WinMode::Exact as u8, MAX_SERIES_ROUNDS, bonus_bps).unwrap() - bet
+        };
+        let promos = GameConfig { promo_pubkey: Pubkey::new_unique(), first_game_bonus_bps: 500, ..limited };
+        assert_eq!(promos.max_exposure().unwrap(), Some(bonus(MAX_PROMO_BONUS_BPS + 500)));
+
+        // The payout cap bounds the winnings, even with bets unlimited
+        let capped = GameConfig { max_payout: 3 * bet, ..limited };
+        assert_eq!(capped.max_exposure().unwrap(), Some(2 * bet));
+        let uncapped_bets = GameConfig { max_payout: 3 * bet, ..config };
+        assert_eq!(uncapped_bets.max_exposure().unwrap(), Some(3 * bet));
+
+        // A payout cap under the stake leaves only the insured refund at risk
+        let tight = GameConfig { max_payout: bet / 2, ..limited };
+        assert_eq!(tight.max_exposure().unwrap(), Some(0));
+        let insured = GameConfig { insurance_bps: 5_000, ..tight };
+        assert_eq!(insured.max_exposure().unwrap(), Some(bet / 2));
+    }
+
+    #[test]
+    fn insured_loss_splits_only_what_the_player_lost() {
+        let config = GameConfig { insurance_bps: 5_000, ..test_config() };
+        let bet = 100_000_000;
+        let refund = config.insured_refund(bet);
+        let shares = [
+            FeeShare { recipient: Pubkey::new_unique(), bps: 6_000 },
+            FeeShare { recipient: Pubkey::new_unique(), bps: 4_000 },
+        ];
+        assert!(FeeSplit::validate(&shares).is_ok());
+
+        let lost = bet - refund;
+        let routed: u64 = shares.iter().map(|share| share.amount_of(lost)).sum();
+        assert_eq!(routed, 50_000_000);
+        // A full split of an insured loss pays out exactly the bet, never more
+        assert_eq!(refund + routed, bet);
+    }
+
+    #[test]
+    fn insurance_amounts_are_shares_of_the_bet() {
+        let config = GameConfig { insurance_bps: 5_000, insurance_premium_bps: 300, ..test_config() };
+        assert_eq!(config.insured_refund(100_000_000), 50_000_000);
+        assert_eq!(config.insurance_premium(100_000_000), 3_000_000);
+    }
+
+    #[test]
+    fn pending_game_ceiling_rejects_once_saturated() {
+        let mut stats = HouseStats::try_from_slice(&[0u8; HouseStats::SPACE - 8]).unwrap();
+        for _ in 0..3 {
+            stats.record_pending_start(3).unwrap();
+        }
+        assert_eq!(stats.active_pending_games, 3);
+        assert_eq!(stats.record_pending_start(3).unwrap_err(), ErrorCode::TooManyPendingGames.into());
+        assert_eq!(stats.active_pending_games, 3);
+
+        // Settling or cancelling one frees a slot
+        stats.record_pending_end();
+        stats.record_pending_start(3).unwrap();
+        stats.record_cancelled().unwrap();
+        stats.record_pending_start(3).unwrap();
+        assert_eq!(stats.record_pending_start(3).unwrap_err(), ErrorCode::TooManyPendingGames.into());
+
+        // 0 is uncapped, and games from before the counter don't underflow it
+        stats.record_pending_start(0).unwrap();
+        assert_eq!(stats.active_pending_games, 4);
+        for _ in 0..6 {
+            stats.record_pending_end();
+        }
+        assert_eq!(stats.active_pending_games, 0);
+    }
+
+    #[test]
+    fn win_rewards_need_the_mint_authority_and_a_live_account() {
+        let mut config = test_config();
+        assert!(config.reward_accounts(&Pubkey::new_unique()).is_empty());
+
+        let (player, reward_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        config.reward_mint = reward_mint;
+        let authority = reward_authority_pda().0;
+        let accounts: Vec<(Pubkey, bool)> =
+            config.reward_accounts(&player).iter().map(|a| (a.pubkey, a.is_writable)).collect();
+        assert_eq!(
+            accounts,
+            vec![
+                (authority, false),
+                (reward_mint, true),
+                (get_associated_token_address(&player, &reward_mint), true),
+                (token::ID, false),
+            ]
+        );
+
+        let mint = SplMint {
+            mint_authority: COption::Some(authority),
+            supply: 0,
+            decimals: 0,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        let destination = SplTokenAccount {
+            mint: reward_mint,
+            owner: player,
+            amount: 0,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        };
+        assert!(reward_recipient_ready(&mint, &destination, &reward_mint, &authority));
+
+        // Any of these would fail `mint_to` and with it the whole settlement
+        let foreign = SplMint { mint_authority: COption::Some(Pubkey::new_unique()), ..mint };
+        assert!(!reward_recipient_ready(&foreign, &destination, &reward_mint, &authority));
+        let frozen = SplTokenAccount { state: AccountState::Frozen, ..destination };
+        assert!(!reward_recipient_ready(&mint, &frozen, &reward_mint, &authority));
+        let other_mint = SplTokenAccount { mint: Pubkey::new_unique(), ..destination };
+        assert!(!reward_recipient_ready(&mint, &other_mint, &reward_mint, &authority));
+    }
+
+    #[test]
+    fn house_stats_track_profit_both_ways() {
+        let mut stats = HouseStats {
+            total_games: 0,
+            total_wagered: 0,
+            total_paid_out: 0,
+            house_profit: 0,
+            total_cancelled: 0,
+            rng_failures: 0,
+            active_pending_games: 0,
+            bump: 0,
+        };
+        stats.record_game(100, 300).unwrap();
+        stats.record_game(100, 0).unwrap();
+        assert_eq!(stats.total_games, 2);
+        assert_eq!(stats.total_wagered, 200);
+        assert_eq!(stats.total_paid_out, 300);
+        assert_eq!(stats.house_profit, -100);
+
+        stats.record_cancelled().unwrap();
+        assert_eq!(stats.total_cancelled, 1);
+        assert_eq!(stats.total_games, 2);
+
+        // Each fallen-back draw counts once, on top of its refund
+        for _ in 0..3 {
+            stats.record_rng_failure().unwrap();
+            stats.record_cancelled().unwrap();
+        }
+        assert_eq!((stats.rng_failures, stats.total_cancelled), (3, 4));
+        assert_eq!(stats.total_games, 2);
+    }
+
+    #[test]
+    fn seeds_module_derives_program_addresses() {
+        use crate::seeds;
+
+        let player = Pubkey::new_unique();
+        let (game, bump) = seeds::game_pda(&player);
+        assert_eq!(
+            Pubkey::create_program_address(&[b"game", player.as_ref(), &[bump]], &crate::ID).unwrap(),
+            game
+        );
+        assert_eq!(
+            seeds::treasury_pda(),
+            Pubkey::find_program_address(&[b"treasury"], &crate::ID)
+        );
+        assert_eq!(
+            seeds::house_stats_pda().0,
+            Pubkey::find_program_address(&[seeds::HOUSE_STATS_SEED], &crate::ID).0
+        );
+        assert_ne!(seeds::config_pda().0, seeds::treasury_pda().0);
+    }
+
+    #[test]
+    fn stakers_share_profits_pro_rata() {
+        let mut pool = StakePool { total_staked: 0, profit_per_share: 0, bump: 0 };
+        let stake = |amount| Stake {
+            staker: Pubkey::new_unique(),
+            amount,
+            profit_debt: 0,
+            unclaimed_profit: 0,
+            bump: 0,
+        };
+        assert_eq!(pool.distribute(1_000).unwrap_err(), error!(ErrorCode::NoStakers));
+
+        let mut alice = stake(3_000);
+        let mut bob = stake(1_000);
+        pool.total_staked = 4_000;
+        pool.distribute(1_000).unwrap();
+
+        // Carol joins after the first distribution, so only shares in the second
+        let mut carol = stake(4_000);
+        carol.reset_profit_debt(&pool).unwrap();
+        pool.total_staked = 8_000;
+        pool.distribute(800).unwrap();
+
+        for s in [&mut alice, &mut bob, &mut carol] {
+            s.accrue(&pool).unwrap();
+        }
+        assert_eq!(alice.unclaimed_profit, 750 + 300);
+        assert_eq!(bob.unclaimed_profit, 250 + 100);
+        assert_eq!(carol.unclaimed_profit, 400);
+
+        // Accruing again without a new distribution adds nothing
+        alice.accrue(&pool).unwrap();
+        assert_eq!(alice.unclaimed_profit, 1_050);
+    }
+
+    #[test]
+    fn low_order_x25519_keys_are_rejected() {
+        let malformed = error!(ErrorCode::PubkeyMalformed);
+        assert_eq!(validate_x25519_pubkey(&[0; 32]).unwrap_err(), malformed);
+        for point in LOW_ORDER_X25519_POINTS {
+            let mut high_bit = point;
+            high_bit[31] |= 0x80;
+            assert_eq!(validate_x25519_pubkey(&point).unwrap_err(), malformed);
+            assert_eq!(validate_x25519_pubkey(&high_bit).unwrap_err(), malformed);
+        }
+
+        // The curve's base point, u = 9, is a valid public key
+        let mut base_point = [0u8; 32];
+        base_point[0] = 9;
+        assert!(validate_x25519_pubkey(&base_point).is_ok());
+    }
+
+    #[test]
+    fn decode_result_names_the_circuit_outputs() {
+        let output = PlayChestGameOutput {
+            field_0: PlayChestGameOutputStruct0 {
+                field_0: 1,
+                field_1: 3,
+                field_2: true,
+                field_3: 4,
+                field_4: 2,
+                field_5: true,
+            },
+        };
+        let result = decode_result(&output);
+        assert_eq!(
+            result,
+            ChestGameResult {
+                outcome: RoundOutcome::Win as u8,
+                winning_chest: 3,
+                sampled: true,
+                num_chests: 4,
+                raw_draw: 2,
+                draw_revealed: true,
+            }
+        );
+        assert!(result.player_won());
+
+        let push = decode_result(&PlayChestGameOutput {
+            field_0: PlayChestGameOutputStruct0 {
+                field_0: 2,
+                field_1: 4,
+                field_2: true,
+                field_3: 4,
+                field_4: 0,
+                field_5: false,
+            },
+        });
+        assert!(!push.player_won());
+        assert_eq!(push.outcome, RoundOutcome::Push as u8);
+    }
+
+    #[test]
+    fn fairness_proofs_recompute_the_stored_outcome() {
+        let mut game = migrated_game_account(GameAccount::DISCRIMINATOR).unwrap();
+        game.player = Pubkey::new_unique();
+        game.num_chests = 3;
+
+        let mut proof = FairnessProof::try_from_slice(&[0u8; FairnessProof::SPACE - 8]).unwrap();
+        proof.record(&game, 4, 2);
+        assert!(proof.verify().is_ok());
+        let stale = FairnessProof { version: FAIRNESS_PROOF_VERSION - 1, ..proof.clone() };
+        assert_eq!(stale.verify().unwrap_err(), error!(ErrorCode::FairnessProofMismatch));
+
+        // Draws can't be recomputed, but still have to land in range
+        proof.record(&game, 4, 3);
+        assert!(proof.verify().is_ok());
+        proof.record(&game, 4, 4);
+        assert!(proof.verify().is_err());
+    }
+
+    #[test]
+    fn fee_split_rejects_oversubscribed_shares() {
+        let share = |bps| FeeShare { recipient: Pubkey::new_unique(), bps };
+        assert!(FeeSplit::validate(&[]).is_ok());
+        assert!(FeeSplit::validate(&[share(6_000), share(4_000)]).is_ok());
+        assert!(FeeSplit::validate(&[share(6_000), share(4_001)]).is_err());
+        assert!(FeeSplit::validate(&[share(1), share(1), share(1), share(1), share(1)]).is_err());
+    }
+
+    #[test]
+    fn exported_comp_def_offsets_match_the_circuit_names() {
+        let offsets = [
+            (COMP_DEF_OFFSET_PLAY_CHEST_GAME, "play_chest_game"),
+            (COMP_DEF_OFFSET_PLAY_HIDDEN_CHEST_GAME, "play_hidden_chest_game"),
+            (COMP_DEF_OFFSET_PLAY_ODDS_GAME, "play_odds_game"),
+            (COMP_DEF_OFFSET_PLAY_POT_GAME, "play_pot_game"),
+        ];
+        for (offset, circuit) in offsets {
+            assert_eq!(offset, comp_def_offset(circuit));
+            // The offset is the first four bytes of sha256(circuit name), little endian
+            let digest = hash(circuit.as_bytes()).to_bytes();
+            assert_eq!(offset, u32::from_le_bytes(digest[..4].try_into().unwrap()));
+        }
+    }
+
+    #[test]
+    fn pot_winners_split_every_bet() {
+        // Two players, one winner: the winner takes both bets
+        assert_eq!(pot_payouts(100, &[true, false]).unwrap(), vec![200, 0]);
+        // Three players, two winners: the odd lamport goes to the first winner
+        assert_eq!(pot_payouts(101, &[false, true, true]).unwrap(), vec![0, 152, 151]);
+        assert_eq!(pot_payouts(100, &[true, true, true]).unwrap(), vec![100, 100, 100]);
+        // Nobody won: every bet comes back
+        assert_eq!(pot_payouts(100, &[false, false, false]).unwrap(), vec![100, 100, 100]);
+        assert!(pot_payouts(u64::MAX, &[true, false]).is_err());
+
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(validate_pot_players(&[a, b], 2).is_ok());
+        assert!(validate_pot_players(&[a, b, c], 3).is_ok());
+        assert!(validate_pot_players(&[a], 1).is_err());
+        assert!(validate_pot_players(&[a, b], 3).is_err());
+        assert_eq!(validate_pot_players(&[a, b, a], 3).unwrap_err(), error!(ErrorCode::InvalidPotPlayers));
+
+        // Two-player callbacks repeat player 0 in the last slot
+        let pot = Pubkey::new_unique();
+        let keys: Vec<Pubkey> = CallbackAccounts::for_play_pot_game(pot, &[a, b]).iter().map(|acc| acc.pubkey).collect();
+        assert_eq!(keys, vec![pot, a, b, a]);
+    }
+
+    #[test]
+    fn player_entropy_shifts_the_draw_without_bias() {
+        let entropy = player_entropy(&[5u8; 32]);
+        assert_eq!(player_entropy(&[5u8; 32]), entropy);
+        assert_ne!(player_entropy(&[6u8; 32]), entropy);
+
+        // A zero offset leaves the MPC draw as is
+        assert_eq!(mix_player_entropy(2, 0, 3), 2);
+        assert_eq!(mix_player_entropy(2, 4, 3), 0);
+        // For any fixed entropy, each draw maps to a distinct chest, so a
+        // uniform draw stays uniform
+        for range in MIN_CHESTS..=MAX_CHESTS + 1 {
+            let mut chests: Vec<u8> = (0..range).map(|draw| mix_player_entropy(draw, entropy, range)).collect();
+            chests.sort();
+            assert_eq!(chests, (0..range).collect::<Vec<u8>>());
+        }
+    }
+
+    #[test]
+    fn latest_slot_hash_reads_the_newest_entry() {
+        let key = anchor_lang::solana_program::sysvar::slot_hashes::ID;
+        let owner = anchor_lang::solana_program::sysvar::ID;
+        let mut lamports = 0;
+        let mut data = 2u64.to_le_bytes().to_vec();
+        for (slot, byte) in [(100u64, 1u8), (99, 2)] {
+            data.extend_from_slice(&slot.to_le_bytes());
+            data.extend_from_slice(&[byte; 32]);
+        }
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(latest_slot_hash(&info).unwrap(), (100, [1; 32]));
+        assert_eq!(slot_entropy(&[1; 32]), u64::from_le_bytes([1; 8]));
+
+        // An empty sysvar has nothing to mix in
+        let (mut lamports, mut data) = (0, 0u64.to_le_bytes().to_vec());
+        let empty = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(latest_slot_hash(&empty).unwrap_err(), ErrorCode::SlotHashUnavailable.into());
+
+        // All zeros, the unmixed default, leaves the player's shift alone
+        let mut game = migrated_game_account(GameAccount::DISCRIMINATOR).unwrap();
+        game.player_seed = [5; 32];
+        assert_eq!(slot_entropy(&[0; 32]), 0);
+        assert_eq!(game.entropy_offset(4), (player_entropy(&[5; 32]) % 4) as u8);
+    }
+
+    #[test]
+    fn revealed_raw_draws_reduce_to_the_reported_chest() {
+        let num_chests = 4;
+        for entropy in [0, 8, player_entropy(&[5u8; 32])] {
+            let offset = (entropy % num_chests as u64) as u8;
+            for raw_draw in 0..num_chests {
+                // What the circuit reports as the winning chest for this draw
+                let winning_chest = mix_player_entropy(raw_draw, entropy, num_chests);
+                assert_eq!((raw_draw + offset) % num_chests, winning_chest);
+                if offset == 0 {
+                    assert_eq!(raw_draw % num_chests, winning_chest);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn callback_accounts_follow_the_callback_order() {
+        let recipients = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let fee_split = FeeSplit {
+            shares: recipients.iter().map(|&recipient| FeeShare { recipient, bps: 100 }).collect(),
+            bump: 0,
+        };
+        let mut data = Vec::new();
+        fee_split.try_serialize(&mut data).unwrap();
+        let fee_split_key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let info = AccountInfo::new(&fee_split_key, false, false, &mut lamports, &mut data, &crate::ID, false, 0);
+        let fee_split = Account::<FeeSplit>::try_from(&info).unwrap();
+
+        let keys: Vec<Pubkey> = (0..9).map(|_| Pubkey::new_unique()).collect();
+        let accounts = CallbackAccounts::for_play_chest_game(
+            keys[0], keys[1], keys[2], keys[3], keys[4], &fee_split, keys[5], keys[6], keys[7], keys[8], &[], &[],
+        )
+        .unwrap();
+
+        let expected = [
+            (keys[0], true),  // game_account
+            (keys[1], true),  // treasury
+            (keys[2], true),  // player
+            (keys[3], false), // config
+            (keys[4], true),  // house_stats
+            (fee_split_key, false),
+            (keys[5], true),  // pending_claim
+            (keys[6], true),  // result_log
+            (keys[7], true),  // player_stats
+            (keys[8], true),  // fairness_proof
+            (recipients[0], true),
+            (recipients[1], true),
+        ];
+        let actual: Vec<(Pubkey, bool)> = accounts.iter().map(|a| (a.pubkey, a.is_writable)).collect();
+        assert_eq!(actual, expected);
+
+        // Dropping any account, or losing a recipient, fails before queueing
+        assert_eq!(
+            CallbackAccounts::require_play_chest_game_count(&accounts[..accounts.len() - 1], recipients.len())
+                .unwrap_err(),
+            error!(ErrorCode::CallbackAccountsMismatch)
+        );
+        assert_eq!(
+            CallbackAccounts::require_play_chest_game_count(&accounts, recipients.len() - 1).unwrap_err(),
+            error!(ErrorCode::CallbackAccountsMismatch)
+        );
+    }
+
+    #[test]
+    fn compounding_stops_at_the_bet_cap_and_the_treasury() {
+        let mut config = test_config();
+        let mut game = migrated_game_account(GameAccount::DISCRIMINATOR).unwrap();
+        game.bet_amount = 1_000;
+        game.num_chests = 2;
+        game.rounds_total = 1;
+        // A 2-chest win pays 2_000: 1_000 of winnings now, 2_000 worst case next round
+        assert_eq!(config.compounded_bet(&game, 2_000, 3_000).unwrap(), None);
+
+        game.auto_compound = true;
+        assert_eq!(config.compounded_bet(&game, 2_000, 3_000).unwrap(), Some((2_000, 2_000)));
+        assert_eq!(config.compounded_bet(&game, 2_000, 2_999).unwrap(), None);
+
+        config.max_bet = 1_999;
+        assert_eq!(config.compounded_bet(&game, 2_000, u64::MAX).unwrap(), None);
+        config.max_bet = 2_000;
+        assert!(config.compounded_bet(&game, 2_000, u64::MAX).unwrap().is_some());
+    }
+
+    #[test]
+    fn kept_losses_are_routed_across_treasuries_by_weight() {
+        let mut config = test_config();
+        // Unrouted: everything stays in the hot treasury
+        assert_eq!(config.cold_loss_share(1_000), 0);
+        assert!(config.loss_route_accounts().is_empty());
+
+        config.loss_route_bps = [7_000, 3_000];
+        assert_eq!(config.cold_loss_share(1_000), 300);
+        assert_eq!(config.cold_loss_share(999), 299);
+        assert_eq!(config.loss_route_accounts(), vec![cold_treasury_pda().0]);
+
+        config.loss_route_bps = [10_000, 0];
+        assert_eq!(config.cold_loss_share(1_000), 0);
+        assert!(config.loss_route_accounts().is_empty());
+    }
+
+    #[test]
+    fn config_updates_wait_out_the_interval() {
+        let mut config = test_config();
+        config.record_update(1_000).unwrap();
+        // No interval: back-to-back updates are fine
+        config.record_update(1_000).unwrap();
+
+        config.min_config_update_interval = 60;
+        assert_eq!(config.record_update(1_059).unwrap_err(), error!(ErrorCode::ConfigUpdateTooSoon));
+        assert_eq!(config.last_config_update, 1_000);
+        config.record_update(1_060).unwrap();
+        assert_eq!(config.last_config_update, 1_060);
+        assert!(config.record_update(1_061).is_err());
+    }
+
+    #[test]
+    fn player_balance_must_cover_the_spend_above_rent() {
+        let rent_floor = 890_880;
+        assert!(covers_spend(rent_floor + 1_000, rent_floor, 1_000));
+        assert!(!covers_spend(rent_floor + 999, rent_floor, 1_000));
+        // A wallet already under the floor can only spend nothing
+        assert!(!covers_spend(rent_floor - 1, rent_floor, 1));
+        assert!(covers_spend(0, rent_floor, 0));
+    }
+
+    #[test]
+    fn plays_need_a_fully_uploaded_comp_def() {
+        use arcium_client::idl::arcium::types::{ComputationDefinitionMeta, ComputationSignature, OnChainCircuitSource};
+
+        let comp_def = |circuit_source| ComputationDefinitionAccount {
+            finalization_authority: None,
+            cu_amount: 0,
+            definition: ComputationDefinitionMeta {
+                circuit_len: 0,
+                signature: ComputationSignature { parameters: vec![], outputs: vec![] },
+            },
+            circuit_source,
+            bump: 0,
+        };
+        let on_chain = |is_completed| {
+            comp_def(CircuitSource::OnChain(OnChainCircuitSource {
+                is_completed,
+                upload_auth: Pubkey::default(),
+            }))
+        };
+
+        assert_eq!(
+            validate_comp_def_ready(&on_chain(false)).unwrap_err(),
+            error!(ErrorCode::CompDefNotInitialized)
+        );
+        validate_comp_def_ready(&on_chain(true)).unwrap();
+        // Off-chain circuits are fetched by the nodes, so init is all they need
+        validate_comp_def_ready(&comp_def(CircuitSource::OffChain(OffChainCircuitSource {
+            source: String::new(),
+            hash: [0; 32],
+        })))
+        .unwrap();
+    }
+
+    #[test]
+    fn plays_need_the_current_program_version() {
+        validate_program_version(PROGRAM_VERSION).unwrap();
+        for stale in [0, PROGRAM_VERSION - 1, PROGRAM_VERSION + 1] {
+            assert_eq!(validate_program_version(stale).unwrap_err(), error!(ErrorCode::VersionMismatch));
+        }
+    }
+
+    #[test]
+    fn percentage_bets_leave_the_rent_floor_intact() {
+        let rent_floor = 890_880;
+        let sol = 1_000_000_000;
+        // 5% of what sits above the rent floor
+        assert_eq!(bet_from_balance_bps(sol + rent_floor, rent_floor, 500, 0).unwrap(), sol / 20);
+        // Clamped up to the minimum bet, and down to max_bet
+        assert_eq!(bet_from_balance_bps(sol + rent_floor, rent_floor, 1, 0).unwrap(), MIN_BET_LAMPORTS);
+        assert_eq!(bet_from_balance_bps(sol + rent_floor, rent_floor, 10_000, sol / 4).unwrap(), sol / 4);
+        // All of it leaves exactly the rent floor behind
+        assert_eq!(bet_from_balance_bps(sol + rent_floor, rent_floor, 10_000, 0).unwrap(), sol);
+
+        // A wallet whose spendable balance can't cover the minimum bet is refused
+        assert_eq!(
+            bet_from_balance_bps(rent_floor + MIN_BET_LAMPORTS - 1, rent_floor, 10_000, 0).unwrap_err(),
+            error!(ErrorCode::InsufficientFunds)
+        );
+        assert_eq!(
+            bet_from_balance_bps(rent_floor / 2, rent_floor, 10_000, 0).unwrap_err(),
+            error!(ErrorCode::InsufficientFunds)
+        );
+        for bps in [0, 10_001] {
+            assert_eq!(
+                bet_from_balance_bps(sol, rent_floor, bps, 0).unwrap_err(),
+                error!(ErrorCode::InvalidBetBps)
+            );
+        }
+    }
+
+    #[test]
+    fn emergency_drains_wait_out_the_timelock() {
+        let drain = EmergencyDrain {
+            destination: Pubkey::new_unique(),
+            scheduled_at: 1_000,
+            executable_at: 1_000 + EMERGENCY_DRAIN_TIMELOCK_SECS,
+            bump: 0,
+        };
+        assert_eq!(drain.require_executable(1_000).unwrap_err(), error!(ErrorCode::DrainTimelocked));
+        assert_eq!(
+            drain.require_executable(drain.executable_at - 1).unwrap_err(),
+            error!(ErrorCode::DrainTimelocked)
+        );
+        drain.require_executable(drain.executable_at).unwrap();
+        assert_eq!(EMERGENCY_DRAIN_TIMELOCK_SECS, 172_800);
+    }
+
+    #[test]
+    fn callback_rejects_one_account_in_two_slots() {
+        let (game, treasury, player) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        CallbackAccounts::require_distinct(&game, &treasury, &player).unwrap();
+        for (game, treasury, player) in [(game, game, player), (game, treasury, game), (game, player, player)] {
+            assert_eq!(
+                CallbackAccounts::require_distinct(&game, &treasury, &player).unwrap_err(),
+                error!(ErrorCode::CallbackAccountsMismatch)
+            );
+        }
+    }
+
+    #[test]
+    fn callback_rejects_a_substituted_player() {
+        let mut game = migrated_game_account(GameAccount::DISCRIMINATOR).unwrap();
+        game.player = Pubkey::new_unique();
+        CallbackAccounts::require_game_player(&game.player, &game).unwrap();
+        assert_eq!(
+            CallbackAccounts::require_game_player(&Pubkey::new_unique(), &game).unwrap_err(),
+            error!(ErrorCode::WrongPlayerAccount)
+        );
+    }
+
+    #[test]
+    fn refunded_games_free_the_account_like_cancelled_ones() {
+        let mut game = migrated_game_account(GameAccount::DISCRIMINATOR).unwrap();
+        for (status, free) in [
+            (GameStatus::None, true),
+            (GameStatus::Pending, false),
+            (GameStatus::Completed, true),
+            (GameStatus::Cancelled, true),
+            (GameStatus::SeriesInProgress, false),
+            (GameStatus::Refunded, true),
+        ] {
+            game.status = status as u8;
+            assert_eq!(game.accepts_new_game(), free);
+        }
+        assert_ne!(GameStatus::Refunded as u8, GameStatus::Cancelled as u8);
+    }
+
+    #[test]
+    fn idempotency_keys_only_dedupe_games_still_in_play() {
+        let mut game = migrated_game_account(GameAccount::DISCRIMINATOR).unwrap();
+        let key = [7u8; 16];
+        game.last_idempotency_key = key;
+        for (status, retried) in [
+            (GameStatus::None, false),
+            (GameStatus::Pending, true),
+            (GameStatus::Completed, false),
+            (GameStatus::Cancelled, false),
+            (GameStatus::SeriesInProgress, true),
+            (GameStatus::Refunded, false),
+        ] {
+            game.status = status as u8;
+            assert_eq!(game.is_retried_play(&key), retried);
+        }
+
+        // Zero keys opt out, and a different key is a different play
+        game.status = GameStatus::Pending as u8;
+        assert!(!game.is_retried_play(&[8u8; 16]));
+        game.last_idempotency_key = [0u8; 16];
+        assert!(!game.is_retried_play(&[0u8; 16]));
+    }
+
+    #[test]
+    fn redelivered_callbacks_are_acknowledged_once_completed() {
+        let mut game = migrated_game_account(GameAccount::DISCRIMINATOR).unwrap();
+        let queued = Pubkey::new_unique();
+        let stale = Pubkey::new_unique();
+
+        // First delivery: the game is pending, so it settles normally
+        game.status = GameStatus::Pending as u8;
+        assert!(!is_redelivered_callback(&game, &queued, &queued));
+
+        // Second delivery of the computation that completed the game is a no-op
+        game.status = GameStatus::Completed as u8;
+        game.settled_by_callback = true;
+        assert!(is_redelivered_callback(&game, &queued, &queued));
+        // A callback for some other computation still fails the pending check
+        assert!(!is_redelivered_callback(&game, &stale, &queued));
+
+        // Games the callback didn't complete aren't acknowledged: a dispute
+        // resolved as a loss, a cancel, a refund or an undecided series
+        game.settled_by_callback = false;
+        assert!(!is_redelivered_callback(&game, &queued, &queued));
+        for status in [GameStatus::Cancelled, GameStatus::SeriesInProgress, GameStatus::Refunded] {
+            game.status = status as u8;
+            assert!(!is_redelivered_callback(&game, &queued, &queued));
+        }
+    }
+
+    #[test]
+    fn starting_a_game_clears_the_last_one() {
+        let mut game = migrated_game_account(GameAccount::DISCRIMINATOR).unwrap();
+        game.status = GameStatus::Completed as u8;
+        game.rounds_played = 3;
+        game.rounds_won = 2;
+        game.disputed = true;
+        game.dispute_reason = 4;
+        game.settled_by_callback = true;
+        game.hidden_chests = true;
+        game.metadata = [7; 32];
+
+        let player = Pubkey::new_unique();
+        let new_game = NewGame {
+            player,
+            bet_amount: 1_000,
+            num_chests: 3,
+            rounds_total: 1,
+            idempotency_key: [1; 16],
+            ..Default::default()
+        };
+        game.start(&new_game, 100, 400);
+
+        assert_eq!(game.player, player);
+        assert_eq!(game.status, GameStatus::Pending as u8);
+        assert_eq!((game.created_at, game.settle_by), (100, 400));
+        assert_eq!((game.rounds_played, game.rounds_won), (0, 0));
+        assert!(!game.disputed && !game.settled_by_callback && !game.hidden_chests);
+        assert_eq!(game.dispute_reason, 0);
+        assert_eq!(game.metadata, [0; 32]);
+        // The retry key only dedupes while the new game is in play
+        assert!(game.is_retried_play(&[1; 16]));
+    }
+
+    #[test]
+    fn disputes_need_a_game_stuck_past_its_deadline() {
+        let mut game = migrated_game_account(GameAccount::DISCRIMINATOR).unwrap();
+        game.settle_by = 1_000;
+        assert_eq!(game.raise_dispute(1, 2_000).unwrap_err(), error!(ErrorCode::GameNotPending));
+
+        game.status = GameStatus::Pending as u8;
+        assert_eq!(game.raise_dispute(1, 1_000).unwrap_err(), error!(ErrorCode::GameNotTimedOut));
+        game.raise_dispute(7, 1_001).unwrap();
+        assert!(game.disputed);
+        assert_eq!(game.dispute_reason, 7);
+        assert_eq!(game.raise_dispute(2, 1_001).unwrap_err(), error!(ErrorCode::GameAlreadyDisputed));
+        assert_eq!(game.dispute_reason, 7);
+    }
+
+    #[test]
+    fn unproven_games_wait_out_the_hard_timeout_past_settle_by() {
+        let mut game = migrated_game_account(GameAccount::DISCRIMINATOR).unwrap();
+        game.created_at = 1_000;
+        game.settle_by = 1_000 + GAME_TIMEOUT_SECS;
+        assert_eq!(game.check_cancellable(2_000, None, None).unwrap_err(), error!(ErrorCode::GameNotPending));
+
+        game.status = GameStatus::Pending as u8;
+        assert_eq!(game.check_cancellable(game.settle_by, None, None).unwrap_err(), error!(ErrorCode::GameNotTimedOut));
+        // Past the deadline but short of the hard timeout, the computation may still land
+        for now in [game.settle_by + 1, 1_000 + GAME_HARD_TIMEOUT_SECS] {
+            assert_eq!(
+                game.check_cancellable(now, None, None).unwrap_err(),
+                error!(ErrorCode::ComputationStillQueued)
+            );
+        }
+        assert!(game.check_cancellable(1_001 + GAME_HARD_TIMEOUT_SECS, None, None).unwrap());
+    }
+
+    #[test]
+    fn old_game_layouts_migrate_with_zeroed_new_fields() {
+        let game = GameAccount {
+            player: Pubkey::new_unique(),
+            bet_amount: 50_000_000,
+            num_chests: 3,
+            status: GameStatus::Pending as u8,
+            created_at: 1_000,
+            computation_offset: 7,
+            bump: 254,
+            auto_cancel: true,
+            promo_bonus_bps: 0,
+            rounds_total: 1,
+            rounds_played: 0,
+            rounds_won: 0,
+            last_idempotency_key: [0; 16],
+            insured: false,
+            metadata: [9; 32],
+            win_mode: WinMode::NearMiss as u8,
+            hidden_chests: true,
+            settle_by: 5_000,
+            win_probability_bps: 2_500,
+            player_seed: [3; 32],
+            disputed: true,
+            dispute_reason: 4,
+            exposure: 0,
+            auto_compound: true,
+            entropy_slot: 7,
+            slot_hash: [8; 32],
+            settled_by_callback: true,
+        };
+        let mut data = Vec::new();
+        game.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), GameAccount::SPACE);
+
+        // A layout from before near-miss games: everything up to `metadata`
+        let old = &data[..GameAccount::SPACE - 96];
+        let migrated = migrated_game_account(old).unwrap();
+        assert_eq!(migrated.player, game.player);
+        assert_eq!(migrated.metadata, game.metadata);
+        assert!(migrated.auto_cancel);
+        assert_eq!(migrated.win_mode, WinMode::Exact as u8);
+        assert!(!migrated.hidden_chests);
+        assert_eq!(migrated.settle_by, 1_000 + GAME_TIMEOUT_SECS);
+
+        // Current accounts round-trip unchanged
+        assert_eq!(migrated_game_account(&data).unwrap().settle_by, 5_000);
+        assert_eq!(
+            migrated_game_account(&[0u8; 16]).map(|_| ()).unwrap_err(),
+            error!(ErrorCode::InvalidGameAccount)
+        );
+    }
+
+    #[test]
+    fn settle_by_uses_the_configured_timeout() {
+        let mut config = test_config();
+        assert_eq!(config.settle_by(1_000), 1_000 + GAME_TIMEOUT_SECS);
+        config.game_timeout_secs = 300;
+        assert_eq!(config.settle_by(1_000), 1_300);
+    }
+
+    #[test]
+    fn loyalty_rebate_follows_the_highest_tier_reached() {
+        let config = GameConfig {
+            entry_fee: 1_000,
+            tier_thresholds: [100, 500, 0],
+            tier_rebate_bps: [1_000, 2_500, 10_000],
+            ..test_config()
+        };
+        assert_eq!(config.entry_fee_for(99), 1_000);
+        assert_eq!(config.entry_fee_for(100), 900);
+        assert_eq!(config.entry_fee_for(499), 900);
+        assert_eq!(config.entry_fee_for(500), 750);
+        // A zero threshold disables its tier, however large the rebate
+        assert_eq!(config.entry_fee_for(u64::MAX), 750);
+    }
+
+    #[test]
+    fn net_pnl_tracks_wagers_against_payouts() {
+        let mut stats = PlayerStats {
+            player: Pubkey::default(),
+            total_wagered: 0,
+            bump: 0,
+            total_won: 0,
+            outstanding_exposure: 0,
+            games_played: 0,
+            stats_version: PLAYER_STATS_VERSION,
+        };
+        // A 3x win, a loss, then a push
+        let games = [(100, 300), (200, 0), (50, 50)];
+        let mut expected = 0i64;
+        for (bet, payout) in games {
+            stats.record_wager(bet).unwrap();
+            stats.record_payout(payout).unwrap();
+            expected += payout as i64 - bet as i64;
+            assert_eq!(stats.net_pnl(), expected);
+        }
+        assert_eq!(stats.net_pnl(), 0);
+
+        stats.total_wagered = u64::MAX;
+        assert_eq!(stats.net_pnl(), i64::MIN);
+    }
+
+    #[test]
+    fn stats_view_matches_the_stored_fields() {
+        let mut stats = PlayerStats {
+            player: Pubkey::new_unique(),
+            total_wagered: 0,
+            bump: 255,
+            total_won: 0,
+            outstanding_exposure: 700,
+            games_played: 0,
+            stats_version: PLAYER_STATS_VERSION,
+        };
+        stats.record_game_start(1_000).unwrap();
+        stats.record_game_start(500).unwrap();
+        stats.record_payout(2_000).unwrap();
+
+        assert_eq!(
+            stats.view(),
+            PlayerStatsView {
+                stats_version: PLAYER_STATS_VERSION,
+                player: stats.player,
+                games_played: 2,
+                total_wagered: 1_500,
+                total_won: 2_000,
+                net_pnl: 500,
+                outstanding_exposure: 700,
+            }
+        );
+        // The view is what clients decode, so its layout is pinned to the version
+        assert_eq!(stats.view().try_to_vec().unwrap().len(), 1 + 32 + 8 * 5);
+    }
+
+    #[test]
+    fn full_state_fits_in_return_data() {
+        let config = test_config();
+        let stats = PlayerStats {
+            player: Pubkey::new_unique(),
+            total_wagered: 0,
+            bump: 0,
+            total_won: 0,
+            outstanding_exposure: 0,
+            games_played: 0,
+            stats_version: PLAYER_STATS_VERSION,
+        };
+        let view = FullStateView {
+            game: Some(migrated_game_account(GameAccount::DISCRIMINATOR).unwrap()),
+            stats: Some(stats.view()),
+            config,
+        };
+        // Every field is fixed size, so this is the largest the view gets
+        let len = view.try_to_vec().unwrap().len();
+        assert_eq!(len, 1 + (GameAccount::SPACE - 8) + 1 + (1 + 32 + 8 * 5) + (GameConfig::SPACE - 8));
+        assert!(len <= anchor_lang::solana_program::program::MAX_RETURN_DATA);
+    }
+
+    #[test]
+    fn first_game_bonus_applies_once_per_player() {
+        let config = GameConfig { first_game_bonus_bps: 500, ..test_config() };
+        let mut stats = PlayerStats {
+            player: Pubkey::default(),
+            total_wagered: 0,
+            bump: 0,
+            total_won: 0,
+            outstanding_exposure: 0,
+            games_played: 0,
+            stats_version: PLAYER_STATS_VERSION,
+        };
+        assert_eq!(config.first_game_bonus_for(&stats), 500);
+        // The bonus is paid on the payout and covered by the solvency check
+        assert_eq!(config.payout_for(1_000, 2, WinMode::Exact as u8, 1, 500).unwrap(), 2_100);
+        assert_eq!(config.worst_case_payout_for(1_000, 2, WinMode::Exact as u8, 1, 500).unwrap(), 2_100);
+
+        stats.record_game_start(1_000).unwrap();
+        assert_eq!((stats.games_played, stats.total_wagered), (1, 1_000));
+        assert_eq!(config.first_game_bonus_for(&stats), 0);
+        // Topping up the bet doesn't count as another game
+        stats.record_wager(500).unwrap();
+        assert_eq!(stats.games_played, 1);
+    }
+
+    #[test]
+    fn exposure_reservations_stop_at_the_player_cap() {
+        let mut stats = PlayerStats {
+            player: Pubkey::default(),
+            total_wagered: 0,
+            bump: 0,
+            total_won: 0,
+            outstanding_exposure: 0,
+            games_played: 0,
+            stats_version: PLAYER_STATS_VERSION,
+        };
+        let mut game = migrated_game_account(GameAccount::DISCRIMINATOR).unwrap();
+        let mut other = migrated_game_account(GameAccount::DISCRIMINATOR).unwrap();
+
+        stats.reserve_exposure(&mut game, 600, 1_000).unwrap();
+        assert_eq!(
+            stats.reserve_exposure(&mut other, 401, 1_000).unwrap_err(),
+            error!(ErrorCode::PlayerExposureExceeded)
+        );
+        stats.reserve_exposure(&mut other, 400, 1_000).unwrap();
+        assert_eq!(stats.outstanding_exposure, 1_000);
+
+        // A top-up replaces the game's reservation rather than adding to it
+        stats.reserve_exposure(&mut game, 500, 1_000).unwrap();
+        assert_eq!(stats.outstanding_exposure, 900);
+
+        stats.release_exposure(&mut game);
+        stats.release_exposure(&mut game);
+        assert_eq!((stats.outstanding_exposure, game.exposure), (400, 0));
+        // 0 leaves the exposure uncapped
+        stats.reserve_exposure(&mut game, u64::MAX - 400, 0).unwrap();
+    }
+
+    #[test]
+    fn loss_window_caps_net_winnings_until_it_rolls() {
+        let mut treasury = Treasury {
+            authority: Pubkey::default(),
+            bump: 0,
+            window_start: 1_000,
+            window_net_loss: 0,
+            created_at: 0,
+            last_slot: 0,
+            games_this_slot: 0,
+            window_wins_by_chests: [0; WIN_CAP_SLOTS],
+            reserve: 0,
+        };
+        treasury.roll_loss_window(1_010, 60);
+        assert_eq!(treasury.window_start, 1_000);
+
+        assert!(treasury.window_allows(100, 150));
+        treasury.record_window_result(100, 0);
+        assert!(!treasury.window_allows(100, 150));
+        // Kept bets offset winnings, but never below zero
+        treasury.record_window_result(0, 40);
+        assert_eq!(treasury.window_net_loss, 60);
+        treasury.record_window_result(0, 500);
+        assert_eq!(treasury.window_net_loss, 0);
+
+        treasury.record_window_result(150, 0);
+        treasury.roll_loss_window(1_060, 60);
+        assert_eq!((treasury.window_start, treasury.window_net_loss), (1_060, 0));
+        assert!(treasury.window_allows(u64::MAX, 0));
+    }
+
+    #[test]
+    fn rebalance_sweeps_above_the_ceiling_and_blocks_below_the_floor() {
+        let off = test_config();
+        assert_eq!(off.hot_excess(u64::MAX).unwrap(), 0);
+
+        let config = GameConfig { hot_ceiling: 1_000, hot_floor: 200, ..off };
+        assert_eq!(config.hot_excess(1_500).unwrap(), 500);
+        assert_eq!(config.hot_excess(1_000).unwrap(), 0);
+        assert_eq!(config.hot_excess(200).unwrap(), 0);
+        assert_eq!(config.hot_excess(199).unwrap_err(), error!(ErrorCode::TreasuryBelowFloor));
+    }
+
+    #[test]
+    fn treasury_guard_blocks_young_or_underfunded_treasuries() {
+        let off = test_config();
+        assert!(off.validate_treasury_ready(1_000, 0, 1_000).is_ok());
+
+        let guarded = GameConfig { min_treasury_age_secs: 3_600, min_treasury_balance: 500, ..off };
+        assert_eq!(
+            guarded.validate_treasury_ready(1_000, 500, 4_599).unwrap_err(),
+            error!(ErrorCode::TreasuryNotReady)
+        );
+        assert_eq!(
+            guarded.validate_treasury_ready(1_000, 499, 4_600).unwrap_err(),
+            error!(ErrorCode::TreasuryNotReady)
+        );
+        assert!(guarded.validate_treasury_ready(1_000, 500, 4_600).is_ok());
+        // Treasuries from before `created_at` was tracked count as old
+        assert!(guarded.validate_treasury_ready(0, 500, 4_600).is_ok());
+    }
+
+    #[test]
+    fn rng_range_cap_bounds_the_draw() {
+        let mut config = test_config();
+        assert!(config.validate_rng_range(ODDS_DRAW_RANGE).is_ok());
+
+        config.max_rng_range = 5;
+        assert_eq!(config.chest_draw_range(MAX_CHESTS), 5);
+        assert!(config.validate_rng_range(config.chest_draw_range(MAX_CHESTS) as u16).is_ok());
+        // The push chest widens the draw by one
+        config.push_enabled = true;
+        assert_eq!(
+            config.validate_rng_range(config.chest_draw_range(MAX_CHESTS) as u16).unwrap_err(),
+            error!(ErrorCode::RngRangeTooLarge)
+        );
+        assert!(config.validate_rng_range(config.chest_draw_range(4) as u16).is_ok());
+        assert!(config.validate_rng_range(HIDDEN_DRAW_RANGE).is_err());
+    }
+
+    #[test]
+    fn result_line_is_pipe_delimited() {
+        let player = Pubkey::new_unique();
+        let line = result_line(&player, true, 2, 100_000_000, 300_000_000);
+        assert_eq!(line, format!("RESULT|{}|1|2|100000000|300000000", player));
+
+        let refund = result_line(&player, false, 0, 5, 5);
+        let fields: Vec<&str> = refund.split('|').collect();
+        assert_eq!(fields, ["RESULT", &player.to_string(), "0", "0", "5", "5"]);
+    }
+
+    #[test]
+    fn rtp_is_fair_minus_the_house_edge() {
+        for num_chests in MIN_CHESTS..=MAX_CHESTS {
+            for house_edge_bps in [0, 100, 500] {
+                let config = GameConfig { house_edge_bps, ..test_config() };
+                assert_eq!(config.rtp_bps(num_chests, 0), 10_000 - house_edge_bps);
+            }
+        }
+
+        // The push chest refunds without an edge, diluting it
+        let config = GameConfig { push_enabled: true, ..test_config() };
+        assert_eq!(config.rtp_bps(3, 0), 10_000);
+        assert_eq!(GameConfig { house_edge_bps: 400, ..config }.rtp_bps(3, 0), 9_700);
+    }
+
+    #[test]
+    fn rake_cap_bounds_the_edge_on_a_large_win() {
+        let config = GameConfig { house_edge_bps: 500, max_rake_per_game: 1_000, ..test_config() };
+        let exact = WinMode::Exact as u8;
+        // A small win stays under the cap and pays the full edge
+        assert_eq!(config.payout_for(1_000, 2, exact, 1, 0).unwrap(), 1_900);
+        // A large one loses exactly the cap: 2x 100_000 is 200_000, less 1_000
+        assert_eq!(config.payout_for(100_000, 2, exact, 1, 0).unwrap(), 199_000);
+        assert_eq!(config.worst_case_payout_for(100_000, 2, exact, 1, 0).unwrap(), 199_000);
+        assert_eq!(config.odds_payout_for(100_000, 5_000).unwrap(), 199_000);
+        // The cap applies to a bonus-boosted win too
+        assert_eq!(config.payout_for(100_000, 2, exact, 1, 1_000).unwrap(), 219_000);
+
+        // RTP climbs back towards fair once the bet reaches the cap
+        assert_eq!(config.rtp_bps(2, 1_000), 9_500);
+        assert_eq!(config.rtp_bps(2, 100_000), 9_950);
+        assert_eq!(config.rtp_bps(2, 0), 9_500);
+    }
+
+    #[test]
+    fn house_edge_comes_off_every_win_multiplier() {
+        let config = GameConfig { house_edge_bps: 100, ..test_config() };
+        assert_eq!(config.payout_for(100, 5, WinMode::Exact as u8, 1, 0).unwrap(), 495);
+        assert_eq!(config.odds_payout_for(1_000, 2_000).unwrap(), 4_950);
+        // The edge applies before a bonus
+        assert_eq!(config.payout_for(1_000, 2, WinMode::Exact as u8, 1, 1_000).unwrap(), 2_178);
+
+        // A win on long odds still returns at least the stake
+        let steep = GameConfig { house_edge_bps: MAX_HOUSE_EDGE_BPS, ..test_config() };
+        assert_eq!(steep.odds_payout_for(10_000, 9_900).unwrap(), 10_000);
+    }
+
+    #[test]
+    fn chest_win_probability_follows_chest_count_mode_and_push() {
+        let mut config = test_config();
+        let exact = WinMode::Exact as u8;
+        for (num_chests, bps) in [(2, 5_000), (3, 3_333), (4, 2_500), (5, 2_000)] {
+            assert_eq!(config.chest_win_probability_bps(num_chests, exact), bps);
+        }
+        assert_eq!(config.chest_win_probability_bps(5, WinMode::NearMiss as u8), 6_000);
+
+        // The push chest is one more outcome that doesn't win
+        config.push_enabled = true;
+        assert_eq!(config.chest_win_probability_bps(2, exact), 3_333);
+        assert_eq!(config.chest_win_probability_bps(4, exact), 2_000);
+    }
+
+    #[test]
+    fn funding_floor_rejects_zero_and_dust() {
+        let mut config = test_config();
+        assert_eq!(config.validate_funding(0).unwrap_err(), error!(ErrorCode::FundingTooSmall));
+        assert_eq!(config.validate_funding(MIN_FUNDING - 1).unwrap_err(), error!(ErrorCode::FundingTooSmall));
+        assert!(config.validate_funding(MIN_FUNDING).is_ok());
+
+        // A configured floor replaces the default, but zero never passes
+        config.min_funding = 1;
+        assert!(config.validate_funding(1).is_ok());
+        assert_eq!(config.validate_funding(0).unwrap_err(), error!(ErrorCode::FundingTooSmall));
+    }
+
+    #[test]
+    fn keeper_claims_wait_out_the_grace_period() {
+        let mut config = test_config();
+        assert_eq!(
+            config.validate_keeper_claim(0, i64::MAX).unwrap_err(),
+            error!(ErrorCode::KeeperClaimsDisabled)
+        );
+
+        config.keeper_claim_grace_secs = 86_400;
+        config.keeper_fee_bps = 100;
+        assert_eq!(
+            config.validate_keeper_claim(1_000, 1_000 + 86_399).unwrap_err(),
+            error!(ErrorCode::ClaimNotReady)
+        );
+        assert!(config.validate_keeper_claim(1_000, 1_000 + 86_400).is_ok());
+        assert_eq!(config.keeper_fee(2_000_000_000), 20_000_000);
+        assert_eq!(config.keeper_fee(99), 0);
+    }
+
+    #[test]
+    fn wins_the_treasury_cannot_cover_are_owed_until_it_is_refilled() {
+        let mut claim = PendingClaim {
+            player: Pubkey::new_unique(),
+            amount: 0,
+            claimable_at: 0,
+            bump: 0,
+            owed: 0,
+            claimable_slot: 0,
+        };
+        // Two concurrent 1 SOL wins on 0.1 SOL bets against a treasury holding 1.5 SOL:
+        // the first is paid, the second only escrows its bet and owes the rest
+        let (bet, winnings) = (100_000_000, 900_000_000);
+        let mut available: u64 = 1_500_000_000;
+        for _ in 0..2 {
+            if winnings > available {
+                claim.escrow(bet, winnings, 10, 0).unwrap();
+            } else {
+                available -= winnings;
+            }
+        }
+        assert_eq!((claim.amount, claim.owed, claim.claimable_at), (bet, winnings, 10));
+
+        // A partial refill pays what it can, the rest stays owed
+        assert_eq!(claim.take_owed(available), 600_000_000);
+        assert_eq!(claim.owed, 300_000_000);
+        assert_eq!(claim.take_owed(u64::MAX), 300_000_000);
+        assert_eq!(claim.take_owed(u64::MAX), 0);
+    }
+
+    #[test]
+    fn credited_wins_always_go_to_the_pending_claim() {
+        let mut config = test_config();
+        assert!(!config.escrows_payout(u64::MAX));
+
+        config.instant_payout_ceiling = 1_000;
+        assert!(!config.escrows_payout(1_000));
+        assert!(config.escrows_payout(1_001));
+
+        // Crediting escrows wins of any size, even under the ceiling
+        config.credit_wins_to_claim = true;
+        assert!(config.escrows_payout(1));
+        config.instant_payout_ceiling = 0;
+        assert!(config.escrows_payout(1));
+
+        config.credit_wins_to_claim = false;
+        config.winnings_lock_slots = 4;
+        assert!(config.escrows_payout(1));
+    }
+
+    #[test]
+    fn slot_locked_winnings_open_at_the_threshold_slot() {
+        let mut claim = PendingClaim {
+            player: Pubkey::new_unique(),
+            amount: 0,
+            claimable_at: 0,
+            bump: 0,
+            owed: 0,
+            claimable_slot: 0,
+        };
+        // Settled at slot 1_000 with `winnings_lock_slots = 32` and no time delay
+        claim.escrow(500, 0, 100, 1_032).unwrap();
+        assert_eq!(claim.require_claimable(100, 1_031).unwrap_err(), error!(ErrorCode::ClaimNotReady));
+        assert!(claim.require_claimable(100, 1_032).is_ok());
+        // Both locks have to pass
+        assert!(claim.require_claimable(99, 2_000).is_err());
+
+        // A later win pushes the slot back again
+        claim.escrow(500, 0, 100, 1_100).unwrap();
+        assert!(claim.require_claimable(100, 1_032).is_err());
+        assert_eq!(claim.amount, 1_000);
+    }
+
+    #[test]
+    fn player_access_blocks_and_allowlists() {
+        let access = |blocked, allowed| PlayerAccess { player: Pubkey::new_unique(), blocked, allowed, bump: 0 };
+
+        // No PDA is an ordinary player, turned away only in allowlist-only mode
+        assert!(validate_player_access(None, false).is_ok());
+        assert_eq!(validate_player_access(None, true).unwrap_err(), error!(ErrorCode::PlayerNotAllowlisted));
+
+        assert_eq!(
+            validate_player_access(Some(&access(true, false)), false).unwrap_err(),
+            error!(ErrorCode::PlayerBlocked)
+        );
+        assert!(validate_player_access(Some(&access(false, false)), false).is_ok());
+        assert!(validate_player_access(Some(&access(false, true)), true).is_ok());
+        // Blocking wins over the allowlist
+        assert_eq!(
+            validate_player_access(Some(&access(true, true)), true).unwrap_err(),
+            error!(ErrorCode::PlayerBlocked)
+        );
+    }
+
+    #[test]
+    fn win_caps_track_each_chest_count_separately() {
+        let mut treasury = Treasury {
+            authority: Pubkey::default(),
+            bump: 0,
+            window_start: 1_000,
+            window_net_loss: 0,
+            created_at: 0,
+            last_slot: 0,
+            games_this_slot: 0,
+            window_wins_by_chests: [0; WIN_CAP_SLOTS],
+            reserve: 0,
+        };
+        let five = win_cap_slot(5, 0);
+        let two = win_cap_slot(2, 0);
+        assert_eq!((five, two, win_cap_slot(3, 5_000)), (5, 2, 0));
+
+        assert!(treasury.chest_window_allows(five, 400, 500));
+        treasury.record_chest_win(five, 400);
+        // The cap on 5 chests trips while 2-chest games keep paying
+        assert!(!treasury.chest_window_allows(five, 400, 500));
+        assert!(treasury.chest_window_allows(two, 400, 500));
+        assert!(treasury.chest_window_allows(five, u64::MAX, 0));
+
+        treasury.roll_loss_window(1_060, 60);
+        assert_eq!(treasury.window_wins_by_chests, [0; WIN_CAP_SLOTS]);
+        assert!(treasury.chest_window_allows(five, 400, 500));
+    }
+
+    #[test]
+    fn treasury_rate_limits_games_per_slot() {
+        let mut treasury = Treasury {
+            authority: Pubkey::default(),
+            bump: 0,
+            window_start: 0,
+            window_net_loss: 0,
+            created_at: 0,
+            last_slot: 0,
+            games_this_slot: 0,
+            window_wins_by_chests: [0; WIN_CAP_SLOTS],
+            reserve: 0,
+        };
+        treasury.record_play(10, 2).unwrap();
+        treasury.record_play(10, 2).unwrap();
+        assert_eq!(treasury.record_play(10, 2).unwrap_err(), error!(ErrorCode::RateLimited));
+        assert_eq!(treasury.games_this_slot, 2);
+
+        // A new slot starts a fresh count, and 0 lifts the limit
+        treasury.record_play(11, 2).unwrap();
+        assert_eq!((treasury.last_slot, treasury.games_this_slot), (11, 1));
+        for _ in 0..5 {
+            treasury.record_play(11, 0).unwrap();
+        }
+    }
+
+    #[test]
+    fn result_log_wraps_past_capacity() {
+        let mut log = ResultLog {
+            head: 0,
+            len: 0,
+            bump: 0,
+            _padding: [0; 7],
+            entries: [ResultEntry::default(); RESULT_LOG_CAPACITY],
+        };
+        let entry = |bet_amount: u64| ResultEntry { bet_amount, ..ResultEntry::default() };
+        for bet in 0..RESULT_LOG_CAPACITY as u64 + 3 {
+            log.push(entry(bet));
+        }
+        assert_eq!(log.len as usize, RESULT_LOG_CAPACITY);
+        assert_eq!(log.head, 3);
+        // The three newest entries overwrote the three oldest
+        assert_eq!(log.entries[0].bet_amount, RESULT_LOG_CAPACITY as u64);
+        assert_eq!(log.entries[2].bet_amount, RESULT_LOG_CAPACITY as u64 + 2);
+        assert_eq!(log.entries[3].bet_amount, 3);
+
+        log.clear();
+        assert_eq!((log.head, log.len), (0, 0));
+        assert_eq!(log.entries[0].bet_amount, 0);
+    }
+
+    #[test]
+    fn echoed_chest_count_must_match_the_game() {
+        assert!(require_echoed_chest_count(3, 3).is_ok());
+        assert_eq!(
+            require_echoed_chest_count(2, 3).unwrap_err(),
+            error!(ErrorCode::ParameterMismatch)
+        );
+    }
+
+    #[test]
+    fn chest_count_errors_name_the_violated_bound() {
+        assert!(validate_chest_count(MIN_CHESTS).is_ok());
+        assert!(validate_chest_count(MAX_CHESTS).is_ok());
+        assert_eq!(
+            validate_chest_count(1).unwrap_err(),
+            error!(ErrorCode::ChestCountTooLow)
+        );
+        assert_eq!(
+            validate_chest_count(6).unwrap_err(),
+            error!(ErrorCode::ChestCountTooHigh)
+        );
+    }
+
+    #[test]
+    fn series_odds_match_binomial_majority() {
+        assert_eq!(series_odds(3, 1, 1), (3, 1));
+        assert_eq!(series_odds(2, 1, 3), (8, 4));
+        // Best of 3 with 3 chests: 3 * 2 (two wins) + 1 (three wins) of 27
+        assert_eq!(series_odds(3, 1, 3), (27, 7));
+        // Best of 9 with 5 chests
+        assert_eq!(series_odds(5, 1, 9), (1_953_125, 38_245));
+        // Near-miss on 5 chests: 3 of 5 per round, best of 3 is 3 * 9 * 2 + 27 of 125
+        assert_eq!(series_odds(5, 3, 1), (5, 3));
+        assert_eq!(series_odds(5, 3, 3), (125, 81));
+    }
+
+    #[test]
+    fn near_miss_wins_on_neighbouring_chests() {
+        let exact = WinMode::Exact as u8;
+        let near = WinMode::NearMiss as u8;
+        assert!(chest_wins(2, 2, 5, exact));
+        assert!(!chest_wins(2, 3, 5, exact));
+
+        assert!(chest_wins(2, 1, 5, near));
+        assert!(chest_wins(2, 3, 5, near));
+        assert!(!chest_wins(2, 4, 5, near));
+        // Neighbours wrap around the ends, so every pick has three winning chests
+        assert!(chest_wins(0, 4, 5, near));
+        assert!(chest_wins(3, 0, 4, near));
+        assert!(!chest_wins(0, 2, 4, near));
+
+        assert!(validate_win_mode(near, 4).is_ok());
+        assert_eq!(validate_win_mode(near, 3).unwrap_err(), error!(ErrorCode::InvalidWinMode));
+        assert_eq!(validate_win_mode(2, 5).unwrap_err(), error!(ErrorCode::InvalidWinMode));
+    }
+
+    #[test]
+    fn transfer_lamports_overflow_returns_error() {
+        let owner = ID;
+        let (from_key, to_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut from_lamports, mut to_lamports) = (10u64, u64::MAX);
+        let (mut from_data, mut to_data) = (vec![], vec![]);
+        let from = AccountInfo::new(&from_key, false, true, &mut from_lamports, &mut from_data, &owner, false, 0);
+        let to = AccountInfo::new(&to_key, false, true, &mut to_lamports, &mut to_data, &owner, false, 0);
+
+        let err = transfer_lamports(&from, &to, 1).unwrap_err();
+        assert_eq!(err, ErrorCode::Overflow.into());
+        assert_eq!(from.lamports(), 10);
+    }
+}
+
+/// Reference replay of a settled round from its `FairnessProof`, written the
+/// way the circuit computes it. External auditors can follow it step by step;
+/// the fixtures below pin the fairness contract against regressions.
+#[cfg(test)]
+mod replay {
+    use super::*;
+
+    /// Recompute the winning chest and outcome of a proof. Replays need the
+    /// cluster's raw `draw`, which only the attested output carries, and
+    /// shift it by the player's entropy and the slot hash's as the circuit does.
+    pub fn replay(proof: &FairnessProof, draw: u8, choice: u8, win_mode: u8) -> (u8, RoundOutcome) {
+        let range = proof.draw_range;
+        let shifted = mix_player_entropy(draw, player_entropy(&proof.player_seed), range);
+        let winning_chest = mix_player_entropy(shifted, slot_entropy(&proof.slot_hash), range);
+        // With pushes enabled the extra chest at index num_chests is the push chest
+        let outcome = if winning_chest == proof.num_chests {
+            RoundOutcome::Push
+        } else if chest_wins(choice, winning_chest, proof.num_chests, win_mode) {
+            RoundOutcome::Win
+        } else {
+            RoundOutcome::Lose
+        };
+        (winning_chest, outcome)
+    }
+
+    struct Fixture {
+        num_chests: u8,
+        draw_range: u8,
+        player_seed: [u8; 32],
+        draw: u8,
+        choice: u8,
+        win_mode: WinMode,
+    }
+
+    fn proof_for(fixture: &Fixture, winning_chest: u8) -> FairnessProof {
+        let mut game = migrated_game_account(GameAccount::DISCRIMINATOR).unwrap();
+        game.player = Pubkey::new_unique();
+        game.num_chests = fixture.num_chests;
+        game.player_seed = fixture.player_seed;
+        let mut proof = FairnessProof::try_from_slice(&[0u8; FairnessProof::SPACE - 8]).unwrap();
+        proof.record(&game, fixture.draw_range, winning_chest);
+        proof
+    }
+
+    #[test]
+    fn replayed_fixtures_match_their_proofs() {
+        let fixtures = [
+            // No entropy: the draw is the winning chest
+            (
+                Fixture {
+                    num_chests: 3,
+                    draw_range: 3,
+                    player_seed: [0; 32],
+                    draw: 1,
+                    choice: 1,
+                    win_mode: WinMode::Exact,
+                },
+                RoundOutcome::Win,
+            ),
+            // A player seed shifting the draw
+            (
+                Fixture {
+                    num_chests: 5,
+                    draw_range: 5,
+                    player_seed: [5; 32],
+                    draw: 0,
+                    choice: 4,
+                    win_mode: WinMode::Exact,
+                },
+                RoundOutcome::Lose,
+            ),
+            // Near-miss, landing on the push chest
+            (
+                Fixture {
+                    num_chests: 4,
+                    draw_range: 5,
+                    player_seed: [0; 32],
+                    draw: 4,
+                    choice: 3,
+                    win_mode: WinMode::NearMiss,
+                },
+                RoundOutcome::Push,
+            ),
+        ];
+
+        for (fixture, expected) in &fixtures {
+            // The chest settlement stored, derived independently of `replay`
+            let stored = (fixture.draw + (player_entropy(&fixture.player_seed) % fixture.draw_range as u64) as u8)
+                % fixture.draw_range;
+            let proof = proof_for(fixture, stored);
+            assert!(proof.verify().is_ok());
+
+            let (winning_chest, outcome) = replay(&proof, fixture.draw, fixture.choice, fixture.win_mode as u8);
+            assert_eq!(winning_chest, proof.winning_chest);
+            assert_eq!(outcome as u8, *expected as u8);
+        }
+    }
+
+    #[test]
+    fn replay_catches_a_tampered_chest() {
+        let fixture = Fixture {
+            num_chests: 3,
+            draw_range: 4,
+            player_seed: [9; 32],
+            draw: 2,
+            choice: 0,
+            win_mode: WinMode::Exact,
+        };
+        let (honest, _) = replay(&proof_for(&fixture, 0), fixture.draw, fixture.choice, 0);
+        let tampered = proof_for(&fixture, (honest + 1) % fixture.draw_range);
+        // Still in range, so `verify` passes, but the replay disagrees
+        assert!(tampered.verify().is_ok());
+        assert_ne!(replay(&tampered, fixture.draw, fixture.choice, 0).0, tampered.winning_chest);
+    }
+
+    #[test]
+    fn slot_hash_mix_replays_from_the_proof() {
+        let (range, draw) = (5, 3);
+        let mut game = migrated_game_account(GameAccount::DISCRIMINATOR).unwrap();
+        game.player = Pubkey::new_unique();
+        game.num_chests = range;
+        game.player_seed = [7; 32];
+        game.entropy_slot = 1_234;
+        game.slot_hash = [11; 32];
+        // What the callback accepts as the chest for the revealed draw
+        let winning_chest = mix_player_entropy(draw, game.entropy_offset(range) as u64, range);
+
+        let mut proof = FairnessProof::try_from_slice(&[0u8; FairnessProof::SPACE - 8]).unwrap();
+        proof.record(&game, range, winning_chest);
+        assert_eq!(proof.entropy_slot, 1_234);
+        assert_eq!(proof.slot_hash, [11; 32]);
+        assert!(proof.verify().is_ok());
+        // The revealed slot hash and seed alone reproduce the chest, every time
+        for _ in 0..2 {
+            assert_eq!(replay(&proof, draw, 0, 0).0, winning_chest);
+        }
+
+        // The slot hash really moves the draw: some other hash lands elsewhere
+        let moved = (0..=255u8).any(|byte| {
+            let mut other = proof.clone();
+            other.slot_hash = [byte; 32];
+            replay(&other, draw, 0, 0).0 != winning_chest
+        });
+        assert!(moved);
+    }
 }
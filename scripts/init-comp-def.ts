@@ -40,45 +40,53 @@ async function main() {
 
   const program = new Program(idl, provider) as Program<VeiledChests>;
 
-  // Derive Arcium accounts
-  const mxeAccount = getMXEAccAddress(programId);
-  const compDefOffset = Buffer.from(getCompDefAccOffset("play_chest_game")).readUInt32LE();
-  const compDefAccount = getCompDefAccAddress(programId, compDefOffset);
-
   // Derive the Address Lookup Table address from the MXE account
+  const mxeAccount = getMXEAccAddress(programId);
   const arciumProgram = getArciumProgram(provider);
   const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
   const lutAddress = getLookupTableAddress(programId, mxeAcc.lutOffsetSlot);
 
-  console.log("Initializing computation definition for play_chest_game...");
-  console.log("Using payer:", payer.publicKey.toBase58());
-  console.log("MXE Account:", mxeAccount.toBase58());
-  console.log("Comp Def Account:", compDefAccount.toBase58());
-  console.log("LUT Address:", lutAddress.toBase58());
+  for (const circuit of ["play_chest_game", "play_hidden_chest_game", "play_odds_game", "play_pot_game"] as const) {
+    const compDefOffset = Buffer.from(getCompDefAccOffset(circuit)).readUInt32LE();
+    const compDefAccount = getCompDefAccAddress(programId, compDefOffset);
+
+    console.log(`Initializing computation definition for ${circuit}...`);
+    console.log("Using payer:", payer.publicKey.toBase58());
+    console.log("MXE Account:", mxeAccount.toBase58());
+    console.log("Comp Def Account:", compDefAccount.toBase58());
+    console.log("LUT Address:", lutAddress.toBase58());
 
-  try {
-    const tx = await program.methods
-      .initPlayChestGameCompDef()
-      .accounts({
-        payer: payer.publicKey,
-        mxeAccount: mxeAccount,
-        compDefAccount: compDefAccount,
-        addressLookupTable: lutAddress,
-      })
-      .rpc({ commitment: "confirmed", preflightCommitment: "confirmed" });
+    try {
+      const init =
+        circuit === "play_chest_game"
+          ? program.methods.initPlayChestGameCompDef()
+          : circuit === "play_hidden_chest_game"
+            ? program.methods.initPlayHiddenChestGameCompDef()
+            : circuit === "play_odds_game"
+              ? program.methods.initPlayOddsGameCompDef()
+              : program.methods.initPlayPotGameCompDef();
+      const tx = await init
+        .accounts({
+          payer: payer.publicKey,
+          mxeAccount: mxeAccount,
+          compDefAccount: compDefAccount,
+          addressLookupTable: lutAddress,
+        })
+        .rpc({ commitment: "confirmed", preflightCommitment: "confirmed" });
 
-    console.log("✅ Comp def initialized!");
-    console.log("Transaction signature:", tx);
-    console.log(
-      "View on Solana Explorer:",
-      `https://explorer.solana.com/tx/${tx}?cluster=devnet`
-    );
-  } catch (error: any) {
-    if (error.message?.includes("already in use")) {
-      console.log("⚠️  Comp def already initialized (this is fine)");
-    } else {
-      console.error("❌ Error initializing comp def:", error);
-      throw error;
+      console.log("✅ Comp def initialized!");
+      console.log("Transaction signature:", tx);
+      console.log(
+        "View on Solana Explorer:",
+        `https://explorer.solana.com/tx/${tx}?cluster=devnet`
+      );
+    } catch (error: any) {
+      if (error.message?.includes("already in use")) {
+        console.log("⚠️  Comp def already initialized (this is fine)");
+      } else {
+        console.error("❌ Error initializing comp def:", error);
+        throw error;
+      }
     }
   }
 }
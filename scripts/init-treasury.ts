@@ -36,25 +36,150 @@ async function main() {
   console.log("Treasury PDA:", treasuryPda.toBase58());
   console.log("Authority:", wallet.publicKey.toBase58());
 
+  // Derive config PDA
+  const [configPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    PROGRAM_ID
+  );
+
   // Check if treasury already exists
   const treasuryInfo = await connection.getAccountInfo(treasuryPda);
   if (treasuryInfo) {
     console.log("Treasury already initialized!");
     console.log("Balance:", treasuryInfo.lamports / LAMPORTS_PER_SOL, "SOL");
-    return;
+  } else {
+    // Initialize treasury
+    console.log("\nInitializing treasury...");
+    const tx = await program.methods
+      .initTreasury()
+      .accountsPartial({
+        authority: wallet.publicKey,
+      })
+      .rpc();
+
+    console.log("✅ Treasury initialized!");
+    console.log("Transaction:", tx);
+  }
+
+  // Initialize game config (max_payout of 0 = uncapped)
+  const configInfo = await connection.getAccountInfo(configPda);
+  if (configInfo) {
+    console.log("Config already initialized at:", configPda.toBase58());
+  } else {
+    console.log("\nInitializing game config...");
+    const tx = await program.methods
+      .initConfig({
+        maxPayout: new anchor.BN(0),
+        promoPubkey: PublicKey.default,
+        roundInFavorOfPlayer: false,
+        entryFee: new anchor.BN(0),
+        pushEnabled: false,
+        insuranceBps: 0,
+        insurancePremiumBps: 0,
+        instantPayoutCeiling: new anchor.BN(0),
+        claimDelaySecs: new anchor.BN(0),
+        blockAuthorityPlay: false,
+        maxBet: new anchor.BN(0),
+        maxLossPerWindow: new anchor.BN(0),
+        windowSecs: new anchor.BN(0),
+        tierThresholds: [new anchor.BN(0), new anchor.BN(0), new anchor.BN(0)],
+        tierRebateBps: [0, 0, 0],
+        gameTimeoutSecs: new anchor.BN(0),
+        hotCeiling: new anchor.BN(0),
+        hotFloor: new anchor.BN(0),
+        minTreasuryAgeSecs: new anchor.BN(0),
+        minTreasuryBalance: new anchor.BN(0),
+        maxRngRange: 0,
+        maxGamesPerSlot: 0,
+        minFunding: new anchor.BN(0),
+        keeperClaimGraceSecs: new anchor.BN(0),
+        keeperFeeBps: 0,
+        allowlistOnly: false,
+        winCapByChests: new Array(9).fill(new anchor.BN(0)),
+        winningsLockSlots: new anchor.BN(0),
+        maxPlayerExposure: new anchor.BN(0),
+        payoutsFrozen: false,
+        firstGameBonusBps: 0,
+        revealRawDraw: false,
+        creditWinsToClaim: false,
+        lossRouteBps: [0, 0],
+        minConfigUpdateInterval: new anchor.BN(0),
+        mixSlotHash: false,
+        maxPendingGames: new anchor.BN(0),
+        rewardMint: PublicKey.default,
+        rewardAmount: new anchor.BN(0),
+      })
+      .accountsPartial({
+        authority: wallet.publicKey,
+      })
+      .rpc();
+
+    console.log("✅ Config initialized!");
+    console.log("Transaction:", tx);
+  }
+
+  // Initialize house stats
+  const [houseStatsPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("house_stats")],
+    PROGRAM_ID
+  );
+  const houseStatsInfo = await connection.getAccountInfo(houseStatsPda);
+  if (houseStatsInfo) {
+    console.log("House stats already initialized at:", houseStatsPda.toBase58());
+  } else {
+    console.log("\nInitializing house stats...");
+    const tx = await program.methods
+      .initHouseStats()
+      .accountsPartial({
+        authority: wallet.publicKey,
+      })
+      .rpc();
+
+    console.log("✅ House stats initialized!");
+    console.log("Transaction:", tx);
+  }
+
+  // Initialize the (empty) fee split
+  const [feeSplitPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("fee_split")],
+    PROGRAM_ID
+  );
+  const feeSplitInfo = await connection.getAccountInfo(feeSplitPda);
+  if (feeSplitInfo) {
+    console.log("Fee split already initialized at:", feeSplitPda.toBase58());
+  } else {
+    console.log("\nInitializing fee split...");
+    const tx = await program.methods
+      .initFeeSplit()
+      .accountsPartial({
+        authority: wallet.publicKey,
+      })
+      .rpc();
+
+    console.log("✅ Fee split initialized!");
+    console.log("Transaction:", tx);
+  }
+
+  const [resultLogPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("result_log")],
+    PROGRAM_ID
+  );
+  const resultLogInfo = await connection.getAccountInfo(resultLogPda);
+  if (resultLogInfo) {
+    console.log("Result log already initialized at:", resultLogPda.toBase58());
+  } else {
+    console.log("\nInitializing result log...");
+    const tx = await program.methods
+      .initResultLog()
+      .accountsPartial({
+        authority: wallet.publicKey,
+      })
+      .rpc();
+
+    console.log("✅ Result log initialized!");
+    console.log("Transaction:", tx);
   }
 
-  // Initialize treasury
-  console.log("\nInitializing treasury...");
-  const tx = await program.methods
-    .initTreasury()
-    .accountsPartial({
-      authority: wallet.publicKey,
-    })
-    .rpc();
-
-  console.log("✅ Treasury initialized!");
-  console.log("Transaction:", tx);
   console.log("\nTo fund the treasury, send SOL to:");
   console.log(treasuryPda.toBase58());
 }
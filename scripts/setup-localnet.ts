@@ -62,77 +62,206 @@ async function main() {
     process.exit(1);
   }
 
-  // Step 2: Init comp def
-  console.log("\n--- Step 2: Initializing computation definition ---");
+  // Step 2: Init comp defs
+  for (const circuit of ["play_chest_game", "play_hidden_chest_game", "play_odds_game", "play_pot_game"] as const) {
+    console.log(`\n--- Step 2: Initializing ${circuit} computation definition ---`);
+    try {
+      const baseSeedCompDefAcc = getArciumAccountBaseSeed("ComputationDefinitionAccount");
+      const offset = getCompDefAccOffset(circuit);
+      const compDefPDA = PublicKey.findProgramAddressSync(
+        [baseSeedCompDefAcc, programId.toBuffer(), offset],
+        getArciumProgramId()
+      )[0];
+
+      // Get LUT address (v0.7.0+)
+      const arciumProgram = getArciumProgram(provider);
+      const mxeAccount = getMXEAccAddress(programId);
+      const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
+      const lutAddress = getLookupTableAddress(programId, mxeAcc.lutOffsetSlot);
+
+      const init =
+        circuit === "play_chest_game"
+          ? program.methods.initPlayChestGameCompDef()
+          : circuit === "play_hidden_chest_game"
+            ? program.methods.initPlayHiddenChestGameCompDef()
+            : circuit === "play_odds_game"
+              ? program.methods.initPlayOddsGameCompDef()
+              : program.methods.initPlayPotGameCompDef();
+      const sig = await init
+        .accounts({
+          compDefAccount: compDefPDA,
+          payer: payer.publicKey,
+          mxeAccount: mxeAccount,
+          addressLookupTable: lutAddress,
+        } as any)
+        .signers([payer])
+        .rpc({ commitment: "confirmed", preflightCommitment: "confirmed" });
+      console.log("  Init comp def tx:", sig);
+
+      // Finalize comp def
+      const finalizeTx = await buildFinalizeCompDefTx(
+        provider,
+        Buffer.from(offset).readUInt32LE(),
+        programId
+      );
+      const latestBlockhash = await connection.getLatestBlockhash();
+      finalizeTx.recentBlockhash = latestBlockhash.blockhash;
+      finalizeTx.lastValidBlockHeight = latestBlockhash.lastValidBlockHeight;
+      finalizeTx.sign(payer);
+      await provider.sendAndConfirm(finalizeTx);
+      console.log("✅ Comp def initialized and finalized");
+    } catch (error: any) {
+      if (error.message?.includes("already in use")) {
+        console.log("⚠️  Comp def already initialized (skipping)");
+      } else {
+        console.error("❌ Error:", error.message || error);
+        process.exit(1);
+      }
+    }
+  }
+
+  // Step 3: Init treasury
+  console.log("\n--- Step 3: Initializing treasury ---");
+  try {
+    const sig = await program.methods
+      .initTreasury()
+      .accountsPartial({
+        authority: payer.publicKey,
+      })
+      .signers([payer])
+      .rpc({ commitment: "confirmed", preflightCommitment: "confirmed" });
+    console.log("✅ Treasury initialized:", sig);
+  } catch (error: any) {
+    if (error.message?.includes("TreasuryAlreadyInitialized")) {
+      console.log("⚠️  Treasury already initialized (skipping)");
+    } else {
+      console.error("❌ Error:", error.message || error);
+      process.exit(1);
+    }
+  }
+
+  // Step 4: Init config
+  console.log("\n--- Step 4: Initializing game config ---");
+  try {
+    const sig = await program.methods
+      .initConfig({
+        maxPayout: new anchor.BN(0),
+        promoPubkey: PublicKey.default,
+        roundInFavorOfPlayer: false,
+        entryFee: new anchor.BN(0),
+        pushEnabled: false,
+        insuranceBps: 0,
+        insurancePremiumBps: 0,
+        instantPayoutCeiling: new anchor.BN(0),
+        claimDelaySecs: new anchor.BN(0),
+        blockAuthorityPlay: false,
+        maxBet: new anchor.BN(0),
+        maxLossPerWindow: new anchor.BN(0),
+        windowSecs: new anchor.BN(0),
+        tierThresholds: [new anchor.BN(0), new anchor.BN(0), new anchor.BN(0)],
+        tierRebateBps: [0, 0, 0],
+        gameTimeoutSecs: new anchor.BN(0),
+        hotCeiling: new anchor.BN(0),
+        hotFloor: new anchor.BN(0),
+        minTreasuryAgeSecs: new anchor.BN(0),
+        minTreasuryBalance: new anchor.BN(0),
+        maxRngRange: 0,
+        maxGamesPerSlot: 0,
+        minFunding: new anchor.BN(0),
+        keeperClaimGraceSecs: new anchor.BN(0),
+        keeperFeeBps: 0,
+        allowlistOnly: false,
+        winCapByChests: new Array(9).fill(new anchor.BN(0)),
+        winningsLockSlots: new anchor.BN(0),
+        maxPlayerExposure: new anchor.BN(0),
+        payoutsFrozen: false,
+        firstGameBonusBps: 0,
+        revealRawDraw: false,
+        creditWinsToClaim: false,
+        lossRouteBps: [0, 0],
+        minConfigUpdateInterval: new anchor.BN(0),
+        mixSlotHash: false,
+        maxPendingGames: new anchor.BN(0),
+        rewardMint: PublicKey.default,
+        rewardAmount: new anchor.BN(0),
+      })
+      .accountsPartial({
+        authority: payer.publicKey,
+      })
+      .signers([payer])
+      .rpc({ commitment: "confirmed", preflightCommitment: "confirmed" });
+    console.log("✅ Config initialized:", sig);
+  } catch (error: any) {
+    if (error.message?.includes("ConfigAlreadyInitialized")) {
+      console.log("⚠️  Config already initialized (skipping)");
+    } else {
+      console.error("❌ Error:", error.message || error);
+      process.exit(1);
+    }
+  }
+
+  // Step 5: Init house stats
+  console.log("\n--- Step 5: Initializing house stats ---");
   try {
-    const baseSeedCompDefAcc = getArciumAccountBaseSeed("ComputationDefinitionAccount");
-    const offset = getCompDefAccOffset("play_chest_game");
-    const compDefPDA = PublicKey.findProgramAddressSync(
-      [baseSeedCompDefAcc, programId.toBuffer(), offset],
-      getArciumProgramId()
-    )[0];
-
-    // Get LUT address (v0.7.0+)
-    const arciumProgram = getArciumProgram(provider);
-    const mxeAccount = getMXEAccAddress(programId);
-    const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
-    const lutAddress = getLookupTableAddress(programId, mxeAcc.lutOffsetSlot);
+    const sig = await program.methods
+      .initHouseStats()
+      .accountsPartial({
+        authority: payer.publicKey,
+      })
+      .signers([payer])
+      .rpc({ commitment: "confirmed", preflightCommitment: "confirmed" });
+    console.log("✅ House stats initialized:", sig);
+  } catch (error: any) {
+    if (error.message?.includes("already in use")) {
+      console.log("⚠️  House stats already initialized (skipping)");
+    } else {
+      console.error("❌ Error:", error.message || error);
+      process.exit(1);
+    }
+  }
 
+  // Step 6: Init fee split
+  console.log("\n--- Step 6: Initializing fee split ---");
+  try {
     const sig = await program.methods
-      .initPlayChestGameCompDef()
-      .accounts({
-        compDefAccount: compDefPDA,
-        payer: payer.publicKey,
-        mxeAccount: mxeAccount,
-        addressLookupTable: lutAddress,
-      } as any)
+      .initFeeSplit()
+      .accountsPartial({
+        authority: payer.publicKey,
+      })
       .signers([payer])
       .rpc({ commitment: "confirmed", preflightCommitment: "confirmed" });
-    console.log("  Init comp def tx:", sig);
-
-    // Finalize comp def
-    const finalizeTx = await buildFinalizeCompDefTx(
-      provider,
-      Buffer.from(offset).readUInt32LE(),
-      programId
-    );
-    const latestBlockhash = await connection.getLatestBlockhash();
-    finalizeTx.recentBlockhash = latestBlockhash.blockhash;
-    finalizeTx.lastValidBlockHeight = latestBlockhash.lastValidBlockHeight;
-    finalizeTx.sign(payer);
-    await provider.sendAndConfirm(finalizeTx);
-    console.log("✅ Comp def initialized and finalized");
+    console.log("✅ Fee split initialized:", sig);
   } catch (error: any) {
     if (error.message?.includes("already in use")) {
-      console.log("⚠️  Comp def already initialized (skipping)");
+      console.log("⚠️  Fee split already initialized (skipping)");
     } else {
       console.error("❌ Error:", error.message || error);
       process.exit(1);
     }
   }
 
-  // Step 3: Init treasury
-  console.log("\n--- Step 3: Initializing treasury ---");
+  // Step 7: Init result log
+  console.log("\n--- Step 7: Initializing result log ---");
   try {
     const sig = await program.methods
-      .initTreasury()
+      .initResultLog()
       .accountsPartial({
         authority: payer.publicKey,
       })
       .signers([payer])
       .rpc({ commitment: "confirmed", preflightCommitment: "confirmed" });
-    console.log("✅ Treasury initialized:", sig);
+    console.log("✅ Result log initialized:", sig);
   } catch (error: any) {
     if (error.message?.includes("already in use")) {
-      console.log("⚠️  Treasury already initialized (skipping)");
+      console.log("⚠️  Result log already initialized (skipping)");
     } else {
       console.error("❌ Error:", error.message || error);
       process.exit(1);
     }
   }
 
-  // Step 4: Fund treasury
-  console.log("\n--- Step 4: Funding treasury ---");
+  // Step 8: Fund treasury
+  console.log("\n--- Step 8: Funding treasury ---");
   try {
     const fundAmount = new anchor.BN(10 * LAMPORTS_PER_SOL);
     const sig = await program.methods
@@ -1,8 +1,14 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
-import { PublicKey, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import {
+  PublicKey,
+  LAMPORTS_PER_SOL,
+  Ed25519Program,
+  TransactionInstruction,
+  SYSVAR_SLOT_HASHES_PUBKEY,
+} from "@solana/web3.js";
 import { VeiledChests } from "../target/types/veiled_chests";
-import { randomBytes } from "crypto";
+import { createHash, randomBytes } from "crypto";
 import {
   awaitComputationFinalization,
   getArciumEnv,
@@ -38,6 +44,8 @@ describe("VeiledChests", () => {
   const program = anchor.workspace.VeiledChests as Program<VeiledChests>;
   const provider = anchor.getProvider() as anchor.AnchorProvider;
   const arciumEnv = getArciumEnv();
+  // Clients pass the version they were built against; read it from the IDL like one would
+  const PROGRAM_VERSION = Number(program.idl.constants.find((c) => c.name === "PROGRAM_VERSION")!.value);
 
   type Event = anchor.IdlEvents<(typeof program)["idl"]>;
   const awaitEvent = async <E extends keyof Event>(
@@ -53,6 +61,23 @@ describe("VeiledChests", () => {
     return event;
   };
 
+  // Like awaitEvent, but ignores events emitted for other players
+  const awaitEventFor = async <E extends keyof Event>(
+    eventName: E,
+    player: PublicKey
+  ): Promise<Event[E]> => {
+    let listenerId: number;
+    const event = await new Promise<Event[E]>((res) => {
+      listenerId = program.addEventListener(eventName, (event: any) => {
+        if (event.player.equals(player)) {
+          res(event);
+        }
+      });
+    });
+    await program.removeEventListener(listenerId);
+    return event;
+  };
+
   /**
    * Gets the cluster account address using the cluster offset from environment.
    */
@@ -78,93 +103,292 @@ describe("VeiledChests", () => {
     )[0];
   }
 
-  it("Initializes the computation definition", async () => {
-    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+  // Helper to get the global house stats PDA
+  function getHouseStatsPDA(): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("house_stats")],
+      program.programId
+    )[0];
+  }
 
-    // Wait for MXE public key to be available first (keygen must complete)
-    console.log("Waiting for MXE keygen to complete...");
-    const mxePublicKey = await getMXEPublicKeyWithRetry(provider, program.programId);
-    console.log("MXE x25519 pubkey is", Buffer.from(mxePublicKey).toString("hex"));
+  // Helper to get a player's stats PDA
+  function getPlayerStatsPDA(player: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("player_stats"), player.toBuffer()],
+      program.programId
+    )[0];
+  }
 
-    console.log("Initializing play_chest_game computation definition...");
-    const sig = await initPlayChestGameCompDef(program, owner);
-    console.log("Comp def initialized with signature:", sig);
-  });
+  // Helper to create a fresh player funded via airdrop
+  async function newFundedPlayer(sol: number = 2): Promise<anchor.web3.Keypair> {
+    const kp = anchor.web3.Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(
+      kp.publicKey,
+      sol * LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(sig, "confirmed");
+    return kp;
+  }
 
-  it("Initializes the treasury", async () => {
-    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
-    const treasury = getTreasuryPDA();
+  type QueueOptions = {
+    numChests?: number;
+    betAmount?: anchor.BN;
+    choice?: bigint;
+    autoCancel?: boolean;
+    promo?: SignedPromo;
+    roundsTotal?: number;
+    idempotencyKey?: number[];
+    insurance?: boolean;
+    pubKey?: number[];
+    metadata?: number[];
+    winMode?: number;
+    playerSeed?: number[];
+    tip?: anchor.BN;
+    betIsBps?: boolean;
+    autoCompound?: boolean;
+    programVersion?: number;
+    computationOffset?: anchor.BN;
+    skipPreflight?: boolean;
+  };
 
-    console.log("Initializing treasury at:", treasury.toBase58());
-    
-    const sig = await program.methods
-      .initTreasury()
-      .accountsPartial({
-        authority: owner.publicKey,
-      })
+  // Retry key and tip every play path takes
+  type PlayExtras = {
+    idempotencyKey?: number[];
+    tip?: anchor.BN;
+  };
+
+  type SignedPromo = {
+    payload: { codeId: number[]; bonusBps: number; player: PublicKey; expiresAt: anchor.BN };
+    signature: Uint8Array;
+    ed25519Ix: TransactionInstruction;
+  };
+
+  // Helper to derive a promo redemption PDA
+  function getPromoPDA(codeId: number[]): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("promo"), Buffer.from(codeId)],
+      program.programId
+    )[0];
+  }
+
+  // Helper to sign a promo payload (borsh: [u8; 16] code_id + u16 bonus_bps)
+  function signPromo(
+    signer: anchor.web3.Keypair,
+    codeId: number[],
+    bonusBps: number,
+    player: PublicKey,
+    expiresAt = Math.floor(Date.now() / 1000) + 3600
+  ): SignedPromo {
+    const bonus = Buffer.alloc(2);
+    bonus.writeUInt16LE(bonusBps);
+    const expiry = Buffer.alloc(8);
+    expiry.writeBigInt64LE(BigInt(expiresAt));
+    const message = Buffer.concat([Buffer.from(codeId), bonus, player.toBuffer(), expiry]);
+    const ed25519Ix = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: signer.secretKey,
+      message,
+    });
+    // The signature follows the 16-byte offsets header and the 32-byte pubkey
+    const signature = ed25519Ix.data.subarray(48, 112);
+    return {
+      payload: { codeId, bonusBps, player, expiresAt: new anchor.BN(expiresAt) },
+      signature,
+      ed25519Ix,
+    };
+  }
+
+  // Helper to update a subset of the game config, keeping the other fields
+  async function updateConfig(changes: Record<string, unknown>) {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    const { authority, bump, ...current } = await program.account.gameConfig.fetch(configPda);
+    await program.methods
+      .updateConfig({ ...current, ...changes } as any)
+      .accountsPartial({ authority: owner.publicKey })
       .signers([owner])
-      .rpc({ commitment: "confirmed", preflightCommitment: "confirmed" });
-    
-    console.log("Treasury initialized with signature:", sig);
-  });
+      .rpc({ commitment: "confirmed" });
+  }
 
-  it("Funds the treasury", async () => {
+  async function setPayoutsFrozen(frozen: boolean) {
     const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
-    const treasury = getTreasuryPDA();
-    
-    // Fund with 10 SOL
-    const fundAmount = new anchor.BN(10 * LAMPORTS_PER_SOL);
-    
+    await program.methods
+      .setPayoutsFrozen(frozen)
+      .accountsPartial({ authority: owner.publicKey })
+      .signers([owner])
+      .rpc({ commitment: "confirmed" });
+  }
+
+  // Helper to encrypt a chest choice and queue a game for a player
+  async function queueChestGame(
+    player: anchor.web3.Keypair,
+    opts: QueueOptions = {}
+  ) {
+    const numChests = opts.numChests ?? 3;
+    const betAmount = opts.betAmount ?? new anchor.BN(0.1 * LAMPORTS_PER_SOL);
+    const choice = opts.choice ?? BigInt(0);
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(provider, program.programId);
+    const privateKey = x25519.utils.randomSecretKey();
+    const publicKey = x25519.getPublicKey(privateKey);
+    const cipher = new RescueCipher(x25519.getSharedSecret(privateKey, mxePublicKey));
+    const nonce = randomBytes(16);
+    const encryptedChoice = cipher.encrypt([choice], nonce);
+    const computationOffset = opts.computationOffset ?? new anchor.BN(randomBytes(8), "hex");
+
     const sig = await program.methods
-      .fundTreasury(fundAmount)
+      .playChestGame(
+        computationOffset,
+        numChests,
+        betAmount,
+        Array.from(encryptedChoice[0]) as any,
+        (opts.pubKey ?? Array.from(publicKey)) as any,
+        new anchor.BN(deserializeLE(nonce).toString()),
+        opts.autoCancel ?? false,
+        opts.promo?.payload ?? null,
+        opts.promo ? Array.from(opts.promo.signature) : null,
+        opts.roundsTotal ?? 1,
+        opts.idempotencyKey ?? new Array(16).fill(0),
+        opts.insurance ?? false,
+        opts.metadata ?? new Array(32).fill(0),
+        opts.winMode ?? 0,
+        opts.playerSeed ?? new Array(32).fill(0),
+        opts.tip ?? new anchor.BN(0),
+        opts.betIsBps ?? false,
+        opts.autoCompound ?? false,
+        opts.programVersion ?? PROGRAM_VERSION
+      )
       .accountsPartial({
-        funder: owner.publicKey,
+        player: player.publicKey,
+        gameAccount: getGamePDA(player.publicKey),
+        treasury: getTreasuryPDA(),
+        computationAccount: getComputationAccAddress(arciumEnv.arciumClusterOffset, computationOffset),
+        clusterAccount: clusterAccount,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(arciumEnv.arciumClusterOffset),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("play_chest_game")).readUInt32LE()
+        ),
+        promoRedemption: opts.promo ? getPromoPDA(opts.promo.payload.codeId) : null,
       })
-      .signers([owner])
-      .rpc({ commitment: "confirmed", preflightCommitment: "confirmed" });
-    
-    console.log("Treasury funded with 10 SOL, signature:", sig);
-  });
+      .preInstructions(opts.promo ? [opts.promo.ed25519Ix] : [])
+      .signers([player])
+      .rpc({ skipPreflight: opts.skipPreflight ?? true, commitment: "confirmed" });
 
-  it("Plays a chest game", async () => {
-    const player = readKpJson(`${os.homedir()}/.config/solana/id.json`);
-    
-    // Get MXE public key for encryption (already available from init)
-    const mxePublicKey = await getMXEPublicKeyWithRetry(provider, program.programId);
-    console.log("MXE x25519 pubkey is", Buffer.from(mxePublicKey).toString("hex"));
+    return { sig, computationOffset, numChests, betAmount, publicKey, nonce };
+  }
 
-    // Generate keypair for encryption
+  // Helper to encrypt a choice together with the chest count and queue a hidden chest game
+  async function queueHiddenChestGame(
+    player: anchor.web3.Keypair,
+    numChests: number,
+    choice: bigint = BigInt(0),
+    betAmount: anchor.BN = new anchor.BN(0.1 * LAMPORTS_PER_SOL),
+    extras: PlayExtras = {}
+  ) {
+    const mxePublicKey = await getMXEPublicKeyWithRetry(provider, program.programId);
     const privateKey = x25519.utils.randomSecretKey();
     const publicKey = x25519.getPublicKey(privateKey);
-    const sharedSecret = x25519.getSharedSecret(privateKey, mxePublicKey);
-    const cipher = new RescueCipher(sharedSecret);
+    const cipher = new RescueCipher(x25519.getSharedSecret(privateKey, mxePublicKey));
+    const nonce = randomBytes(16);
+    const encryptedGame = cipher.encrypt([choice, BigInt(numChests)], nonce);
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
 
-    // Game parameters
-    const numChests = 3;  // 3 chests = 3x multiplier
-    const betAmount = new anchor.BN(0.1 * LAMPORTS_PER_SOL);  // 0.1 SOL bet
-    const playerChoice = BigInt(1);  // Player chooses chest 1 (0-indexed)
+    await program.methods
+      .playHiddenChestGame(
+        computationOffset,
+        betAmount,
+        encryptedGame.map((c) => Array.from(c)) as any,
+        Array.from(publicKey) as any,
+        new anchor.BN(deserializeLE(nonce).toString()),
+        extras.idempotencyKey ?? new Array(16).fill(0),
+        extras.tip ?? new anchor.BN(0)
+      )
+      .accountsPartial({
+        player: player.publicKey,
+        gameAccount: getGamePDA(player.publicKey),
+        treasury: getTreasuryPDA(),
+        computationAccount: getComputationAccAddress(arciumEnv.arciumClusterOffset, computationOffset),
+        clusterAccount: clusterAccount,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(arciumEnv.arciumClusterOffset),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("play_hidden_chest_game")).readUInt32LE()
+        ),
+      })
+      .signers([player])
+      .rpc({ skipPreflight: true, commitment: "confirmed" });
 
-    // Encrypt player's choice
-    const nonce = randomBytes(16);
-    const encryptedChoice = cipher.encrypt([playerChoice], nonce);
+    return { computationOffset, betAmount };
+  }
 
-    // Generate computation offset
+  // Helper to encrypt a side of the roll and queue an odds game
+  async function queueOddsGame(
+    player: anchor.web3.Keypair,
+    winProbabilityBps: number,
+    choice: bigint = BigInt(0),
+    betAmount: anchor.BN = new anchor.BN(0.1 * LAMPORTS_PER_SOL),
+    skipPreflight = true,
+    extras: PlayExtras = {}
+  ) {
+    const mxePublicKey = await getMXEPublicKeyWithRetry(provider, program.programId);
+    const privateKey = x25519.utils.randomSecretKey();
+    const publicKey = x25519.getPublicKey(privateKey);
+    const cipher = new RescueCipher(x25519.getSharedSecret(privateKey, mxePublicKey));
+    const nonce = randomBytes(16);
+    const encryptedChoice = cipher.encrypt([choice], nonce);
     const computationOffset = new anchor.BN(randomBytes(8), "hex");
 
-    console.log("Playing chest game...");
-    console.log(`  - Num chests: ${numChests}`);
-    console.log(`  - Bet: ${betAmount.toNumber() / LAMPORTS_PER_SOL} SOL`);
-    console.log(`  - Player choice: chest ${playerChoice}`);
+    await program.methods
+      .playOddsGame(
+        computationOffset,
+        betAmount,
+        winProbabilityBps,
+        Array.from(encryptedChoice[0]) as any,
+        Array.from(publicKey) as any,
+        new anchor.BN(deserializeLE(nonce).toString()),
+        extras.idempotencyKey ?? new Array(16).fill(0),
+        extras.tip ?? new anchor.BN(0)
+      )
+      .accountsPartial({
+        player: player.publicKey,
+        gameAccount: getGamePDA(player.publicKey),
+        treasury: getTreasuryPDA(),
+        computationAccount: getComputationAccAddress(arciumEnv.arciumClusterOffset, computationOffset),
+        clusterAccount: clusterAccount,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(arciumEnv.arciumClusterOffset),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("play_odds_game")).readUInt32LE()
+        ),
+      })
+      .signers([player])
+      .rpc({ skipPreflight, commitment: "confirmed" });
 
-    // Listen for the game result event
-    const gameResultPromise = awaitEvent("gameResultEvent");
+    return { computationOffset, betAmount };
+  }
 
-    // Play the game
-    const queueSig = await program.methods
-      .playChestGame(
+  // Helper to encrypt a fresh choice and queue the next round of a series
+  async function queueNextRound(player: anchor.web3.Keypair, choice: bigint = BigInt(0)) {
+    const mxePublicKey = await getMXEPublicKeyWithRetry(provider, program.programId);
+    const privateKey = x25519.utils.randomSecretKey();
+    const publicKey = x25519.getPublicKey(privateKey);
+    const cipher = new RescueCipher(x25519.getSharedSecret(privateKey, mxePublicKey));
+    const nonce = randomBytes(16);
+    const encryptedChoice = cipher.encrypt([choice], nonce);
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+
+    await program.methods
+      .playNextRound(
         computationOffset,
-        numChests,
-        betAmount,
         Array.from(encryptedChoice[0]) as any,
         Array.from(publicKey) as any,
         new anchor.BN(deserializeLE(nonce).toString())
@@ -186,60 +410,2899 @@ describe("VeiledChests", () => {
       .signers([player])
       .rpc({ skipPreflight: true, commitment: "confirmed" });
 
-    console.log("Game queued with signature:", queueSig);
+    return { computationOffset };
+  }
 
-    // Wait for computation to finalize
-    console.log("Waiting for MPC computation...");
-    const finalizeSig = await awaitComputationFinalization(
-      provider,
-      computationOffset,
-      program.programId,
-      "confirmed"
-    );
-    console.log("Computation finalized with signature:", finalizeSig);
+  // Helper to play a best-of-N series to completion and return its result event
+  async function playSeries(numChests: number, roundsTotal: number) {
+    const player = await newFundedPlayer();
+    const betAmount = new anchor.BN(0.05 * LAMPORTS_PER_SOL);
+    const resultPromise = awaitEventFor("gameResultEvent", player.publicKey);
 
-    // Get the result
-    const gameResult = await gameResultPromise;
-    console.log("\n🎲 GAME RESULT:");
-    console.log(`  - Player won: ${gameResult.playerWon}`);
-    console.log(`  - Winning chest: ${gameResult.winningChest}`);
-    console.log(`  - Bet amount: ${gameResult.betAmount.toNumber() / LAMPORTS_PER_SOL} SOL`);
-    console.log(`  - Payout: ${gameResult.payout.toNumber() / LAMPORTS_PER_SOL} SOL`);
+    let { computationOffset } = await queueChestGame(player, { numChests, betAmount, roundsTotal });
+    for (;;) {
+      await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+      const game = await program.account.gameAccount.fetch(getGamePDA(player.publicKey));
+      if (game.status !== 4) {
+        return { player, betAmount, game, result: await resultPromise };
+      }
+      ({ computationOffset } = await queueNextRound(player));
+    }
+  }
 
-    if (gameResult.playerWon) {
-      console.log("🎉 Congratulations! You won!");
-      expect(gameResult.payout.toNumber()).to.equal(betAmount.toNumber() * numChests);
-    } else {
-      console.log("😢 Better luck next time!");
-      expect(gameResult.payout.toNumber()).to.equal(0);
+  // Helper asserting that a transaction fails with the given Anchor error code
+  async function expectError(promise: Promise<unknown>, errorCode: string) {
+    let error: any;
+    try {
+      await promise;
+    } catch (err) {
+      error = err;
     }
-  });
+    expect(error, `expected ${errorCode}`).to.not.be.undefined;
+    expect(error.error?.errorCode?.code).to.equal(errorCode);
+  }
 
-  async function initPlayChestGameCompDef(
-    program: Program<VeiledChests>,
-    owner: anchor.web3.Keypair
-  ): Promise<string> {
-    const baseSeedCompDefAcc = getArciumAccountBaseSeed("ComputationDefinitionAccount");
-    const offset = getCompDefAccOffset("play_chest_game");
+  it("Initializes the computation definition", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
 
-    const compDefPDA = PublicKey.findProgramAddressSync(
-      [baseSeedCompDefAcc, program.programId.toBuffer(), offset],
-      getArciumProgramId()
-    )[0];
+    // Wait for MXE public key to be available first (keygen must complete)
+    console.log("Waiting for MXE keygen to complete...");
+    const mxePublicKey = await getMXEPublicKeyWithRetry(provider, program.programId);
+    console.log("MXE x25519 pubkey is", Buffer.from(mxePublicKey).toString("hex"));
 
-    console.log("Comp def PDA:", compDefPDA.toBase58());
+    console.log("Initializing play_chest_game computation definition...");
+    const sig = await initPlayChestGameCompDef(program, owner);
+    console.log("Comp def initialized with signature:", sig);
+  });
 
-    // Get LUT address (v0.7.0)
-    const arciumProgram = getArciumProgram(provider);
-    const mxeAccount = getMXEAccAddress(program.programId);
-    const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
-    const lutAddress = getLookupTableAddress(program.programId, mxeAcc.lutOffsetSlot);
+  it("Initializes the hidden chest game computation definition", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const sig = await initPlayChestGameCompDef(program, owner, "play_hidden_chest_game");
+    console.log("Hidden chest game comp def initialized with signature:", sig);
+  });
 
-    console.log("LUT address:", lutAddress.toBase58());
+  it("Initializes the odds game computation definition", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const sig = await initPlayChestGameCompDef(program, owner, "play_odds_game");
+    console.log("Odds game comp def initialized with signature:", sig);
+  });
 
-    // Initialize the comp def
+  it("Initializes the pot game computation definition", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const sig = await initPlayChestGameCompDef(program, owner, "play_pot_game");
+    console.log("Pot game comp def initialized with signature:", sig);
+  });
+
+  it("Initializes the treasury", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const treasury = getTreasuryPDA();
+
+    console.log("Initializing treasury at:", treasury.toBase58());
+    
     const sig = await program.methods
-      .initPlayChestGameCompDef()
+      .initTreasury()
+      .accountsPartial({
+        authority: owner.publicKey,
+      })
+      .signers([owner])
+      .rpc({ commitment: "confirmed", preflightCommitment: "confirmed" });
+    
+    console.log("Treasury initialized with signature:", sig);
+  });
+
+  it("Initializes the game config", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+
+    const sig = await program.methods
+      .initConfig({
+        maxPayout: new anchor.BN(0),
+        promoPubkey: PublicKey.default,
+        roundInFavorOfPlayer: false,
+        entryFee: new anchor.BN(0),
+        pushEnabled: false,
+        insuranceBps: 0,
+        insurancePremiumBps: 0,
+        instantPayoutCeiling: new anchor.BN(0),
+        claimDelaySecs: new anchor.BN(0),
+        blockAuthorityPlay: false,
+        maxBet: new anchor.BN(0),
+        maxLossPerWindow: new anchor.BN(0),
+        windowSecs: new anchor.BN(0),
+        tierThresholds: [new anchor.BN(0), new anchor.BN(0), new anchor.BN(0)],
+        tierRebateBps: [0, 0, 0],
+        gameTimeoutSecs: new anchor.BN(0),
+        hotCeiling: new anchor.BN(0),
+        hotFloor: new anchor.BN(0),
+        minTreasuryAgeSecs: new anchor.BN(0),
+        minTreasuryBalance: new anchor.BN(0),
+        maxRngRange: 0,
+        maxGamesPerSlot: 0,
+        minFunding: new anchor.BN(0),
+        keeperClaimGraceSecs: new anchor.BN(0),
+        keeperFeeBps: 0,
+        allowlistOnly: false,
+        winCapByChests: new Array(9).fill(new anchor.BN(0)),
+        winningsLockSlots: new anchor.BN(0),
+        maxPlayerExposure: new anchor.BN(0),
+        firstGameBonusBps: 0,
+        revealRawDraw: false,
+        creditWinsToClaim: false,
+        lossRouteBps: [0, 0],
+        minConfigUpdateInterval: new anchor.BN(0),
+        mixSlotHash: false,
+        maxPendingGames: new anchor.BN(0),
+        rewardMint: PublicKey.default,
+        rewardAmount: new anchor.BN(0),
+        houseEdgeBps: 0,
+        maxRakePerGame: new anchor.BN(0),
+      })
+      .accountsPartial({
+        authority: owner.publicKey,
+      })
+      .signers([owner])
+      .rpc({ commitment: "confirmed", preflightCommitment: "confirmed" });
+
+    console.log("Config initialized with signature:", sig);
+  });
+
+  it("Initializes the house stats", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+
+    const sig = await program.methods
+      .initHouseStats()
+      .accountsPartial({
+        authority: owner.publicKey,
+      })
+      .signers([owner])
+      .rpc({ commitment: "confirmed", preflightCommitment: "confirmed" });
+
+    console.log("House stats initialized with signature:", sig);
+  });
+
+  it("Initializes the fee split", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+
+    const sig = await program.methods
+      .initFeeSplit()
+      .accountsPartial({
+        authority: owner.publicKey,
+      })
+      .signers([owner])
+      .rpc({ commitment: "confirmed", preflightCommitment: "confirmed" });
+
+    console.log("Fee split initialized with signature:", sig);
+  });
+
+  it("Initializes the result log", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+
+    const sig = await program.methods
+      .initResultLog()
+      .accountsPartial({
+        authority: owner.publicKey,
+      })
+      .signers([owner])
+      .rpc({ commitment: "confirmed", preflightCommitment: "confirmed" });
+
+    console.log("Result log initialized with signature:", sig);
+  });
+
+  it("Rejects initializing the treasury or config twice", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("config")], program.programId);
+    const { authority, bump, ...params } = await program.account.gameConfig.fetch(configPda);
+
+    await expectError(
+      program.methods
+        .initTreasury()
+        .accountsPartial({ authority: owner.publicKey })
+        .signers([owner])
+        .rpc({ commitment: "confirmed" }),
+      "TreasuryAlreadyInitialized"
+    );
+    await expectError(
+      program.methods
+        .initConfig(params as any)
+        .accountsPartial({ authority: owner.publicKey })
+        .signers([owner])
+        .rpc({ commitment: "confirmed" }),
+      "ConfigAlreadyInitialized"
+    );
+  });
+
+  it("Funds the treasury", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const treasury = getTreasuryPDA();
+    
+    // Fund with 10 SOL
+    const fundAmount = new anchor.BN(10 * LAMPORTS_PER_SOL);
+    
+    const fundedPromise = awaitEvent("treasuryFundedEvent");
+    const sig = await program.methods
+      .fundTreasury(fundAmount)
+      .accountsPartial({
+        funder: owner.publicKey,
+      })
+      .signers([owner])
+      .rpc({ commitment: "confirmed", preflightCommitment: "confirmed" });
+    const funded = await fundedPromise;
+    
+    console.log("Treasury funded with 10 SOL, signature:", sig);
+
+    expect(funded.funder.equals(owner.publicKey)).to.be.true;
+    expect(funded.amount.eq(fundAmount)).to.be.true;
+    const balance = await provider.connection.getBalance(treasury, "confirmed");
+    expect(funded.newBalance.toNumber()).to.equal(balance);
+  });
+
+  it("Rejects zero and dust funding", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const fund = (amount: number) =>
+      program.methods
+        .fundTreasury(new anchor.BN(amount))
+        .accountsPartial({ funder: owner.publicKey })
+        .signers([owner])
+        .rpc({ commitment: "confirmed" });
+
+    await expectError(fund(0), "FundingTooSmall");
+    const minFunding = program.idl.constants.find((c) => c.name === "MIN_FUNDING");
+    await expectError(fund(Number(minFunding.value) - 1), "FundingTooSmall");
+  });
+
+  it("Sweeps the hot treasury above the ceiling to cold storage", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const treasury = getTreasuryPDA();
+    const [coldTreasury] = PublicKey.findProgramAddressSync(
+      [Buffer.from("cold_treasury")],
+      program.programId
+    );
+    const sweepAmount = 0.5 * LAMPORTS_PER_SOL;
+    const rentFloor = await provider.connection.getMinimumBalanceForRentExemption(
+      (await provider.connection.getAccountInfo(treasury)).data.length
+    );
+    const available = (await provider.connection.getBalance(treasury, "confirmed")) - rentFloor;
+    await updateConfig({ hotCeiling: new anchor.BN(available - sweepAmount) });
+    try {
+      const rebalancedPromise = awaitEvent("treasuryRebalancedEvent");
+      await program.methods
+        .rebalanceTreasury()
+        .accountsPartial({ authority: owner.publicKey })
+        .signers([owner])
+        .rpc({ commitment: "confirmed" });
+      const rebalanced = await rebalancedPromise;
+      expect(rebalanced.swept.toNumber()).to.equal(sweepAmount);
+      expect(rebalanced.hotBalance.toNumber()).to.equal(rentFloor + available - sweepAmount);
+      expect(await provider.connection.getBalance(coldTreasury, "confirmed")).to.equal(
+        rebalanced.coldBalance.toNumber()
+      );
+
+      // Put the bankroll back for the games below
+      await program.methods
+        .refillTreasury(new anchor.BN(sweepAmount))
+        .accountsPartial({ authority: owner.publicKey })
+        .signers([owner])
+        .rpc({ commitment: "confirmed" });
+      expect(await provider.connection.getBalance(treasury, "confirmed")).to.equal(rentFloor + available);
+    } finally {
+      await updateConfig({ hotCeiling: new anchor.BN(0) });
+    }
+  });
+
+  it("Blocks rebalancing while the hot treasury is below the floor", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const balance = await provider.connection.getBalance(getTreasuryPDA(), "confirmed");
+    await updateConfig({ hotFloor: new anchor.BN(balance + LAMPORTS_PER_SOL) });
+    try {
+      await expectError(
+        program.methods
+          .rebalanceTreasury()
+          .accountsPartial({ authority: owner.publicKey })
+          .signers([owner])
+          .rpc({ commitment: "confirmed" }),
+        "TreasuryBelowFloor"
+      );
+    } finally {
+      await updateConfig({ hotFloor: new anchor.BN(0) });
+    }
+  });
+
+  it("Timelocks an emergency drain of the treasury", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const destination = anchor.web3.Keypair.generate().publicKey;
+    const scheduled = awaitEvent("emergencyDrainScheduledEvent");
+    await program.methods
+      .scheduleEmergencyDrain(destination)
+      .accountsPartial({ authority: owner.publicKey })
+      .signers([owner])
+      .rpc({ commitment: "confirmed" });
+    const event = await scheduled;
+    expect(event.destination.equals(destination)).to.be.true;
+
+    const [drainPda] = PublicKey.findProgramAddressSync([Buffer.from("emergency_drain")], program.programId);
+    const drain = await program.account.emergencyDrain.fetch(drainPda);
+    expect(drain.executableAt.sub(drain.scheduledAt).toNumber()).to.equal(48 * 60 * 60);
+    expect(drain.executableAt.eq(event.executableAt)).to.be.true;
+
+    const before = await provider.connection.getBalance(getTreasuryPDA(), "confirmed");
+    await expectError(
+      program.methods
+        .executeEmergencyDrain()
+        .accountsPartial({ authority: owner.publicKey, destination })
+        .signers([owner])
+        .rpc({ commitment: "confirmed", skipPreflight: false }),
+      "DrainTimelocked"
+    );
+    expect(await provider.connection.getBalance(getTreasuryPDA(), "confirmed")).to.equal(before);
+  });
+
+  it("Blocks play until the treasury is old enough", async () => {
+    const treasuryAccount = await program.account.treasury.fetch(getTreasuryPDA());
+    expect(treasuryAccount.createdAt.toNumber()).to.be.greaterThan(0);
+    const player = await newFundedPlayer();
+
+    await updateConfig({ minTreasuryAgeSecs: new anchor.BN(365 * 24 * 60 * 60) });
+    try {
+      await expectError(queueChestGame(player, { skipPreflight: false }), "TreasuryNotReady");
+
+      // Any age the treasury has already reached lets the game through
+      await updateConfig({ minTreasuryAgeSecs: new anchor.BN(1) });
+      const resultPromise = awaitEventFor("gameResultEvent", player.publicKey);
+      const { computationOffset } = await queueChestGame(player);
+      await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+      await resultPromise;
+    } finally {
+      await updateConfig({ minTreasuryAgeSecs: new anchor.BN(0) });
+    }
+  });
+
+  it("Plays a chest game", async () => {
+    const player = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    
+    // Get MXE public key for encryption (already available from init)
+    const mxePublicKey = await getMXEPublicKeyWithRetry(provider, program.programId);
+    console.log("MXE x25519 pubkey is", Buffer.from(mxePublicKey).toString("hex"));
+
+    // Generate keypair for encryption
+    const privateKey = x25519.utils.randomSecretKey();
+    const publicKey = x25519.getPublicKey(privateKey);
+    const sharedSecret = x25519.getSharedSecret(privateKey, mxePublicKey);
+    const cipher = new RescueCipher(sharedSecret);
+
+    // Game parameters
+    const numChests = 3;  // 3 chests = 3x multiplier
+    const betAmount = new anchor.BN(0.1 * LAMPORTS_PER_SOL);  // 0.1 SOL bet
+    const playerChoice = BigInt(1);  // Player chooses chest 1 (0-indexed)
+
+    // Encrypt player's choice
+    const nonce = randomBytes(16);
+    const encryptedChoice = cipher.encrypt([playerChoice], nonce);
+
+    // Generate computation offset
+    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+
+    console.log("Playing chest game...");
+    console.log(`  - Num chests: ${numChests}`);
+    console.log(`  - Bet: ${betAmount.toNumber() / LAMPORTS_PER_SOL} SOL`);
+    console.log(`  - Player choice: chest ${playerChoice}`);
+
+    // Listen for the game result event
+    const gameResultPromise = awaitEvent("gameResultEvent");
+
+    // Play the game
+    const queueSig = await program.methods
+      .playChestGame(
+        computationOffset,
+        numChests,
+        betAmount,
+        Array.from(encryptedChoice[0]) as any,
+        Array.from(publicKey) as any,
+        new anchor.BN(deserializeLE(nonce).toString()),
+        false,
+        null,
+        null,
+        1,
+        new Array(16).fill(0),
+        false,
+        0,
+        new Array(32).fill(0),
+        new Array(32).fill(0),
+        0,
+        0,
+        new Array(32).fill(0),
+        new anchor.BN(0),
+        false,
+        false,
+        PROGRAM_VERSION
+      )
+      .accountsPartial({
+        player: player.publicKey,
+        gameAccount: getGamePDA(player.publicKey),
+        treasury: getTreasuryPDA(),
+        computationAccount: getComputationAccAddress(arciumEnv.arciumClusterOffset, computationOffset),
+        clusterAccount: clusterAccount,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+        executingPool: getExecutingPoolAccAddress(arciumEnv.arciumClusterOffset),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("play_chest_game")).readUInt32LE()
+        ),
+      })
+      .signers([player])
+      .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+    console.log("Game queued with signature:", queueSig);
+
+    // Wait for computation to finalize
+    console.log("Waiting for MPC computation...");
+    const finalizeSig = await awaitComputationFinalization(
+      provider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
+    console.log("Computation finalized with signature:", finalizeSig);
+
+    // Get the result
+    const gameResult = await gameResultPromise;
+    console.log("\n🎲 GAME RESULT:");
+    console.log(`  - Player won: ${gameResult.playerWon}`);
+    console.log(`  - Winning chest: ${gameResult.winningChest}`);
+    console.log(`  - Bet amount: ${gameResult.betAmount.toNumber() / LAMPORTS_PER_SOL} SOL`);
+    console.log(`  - Payout: ${gameResult.payout.toNumber() / LAMPORTS_PER_SOL} SOL`);
+
+    if (gameResult.playerWon) {
+      console.log("🎉 Congratulations! You won!");
+      expect(gameResult.payout.toNumber()).to.equal(betAmount.toNumber() * numChests);
+    } else {
+      console.log("😢 Better luck next time!");
+      expect(gameResult.payout.toNumber()).to.equal(0);
+    }
+  });
+
+  it("Emits the encryption context in the started event", async () => {
+    const player = await newFundedPlayer();
+    const startedPromise = awaitEventFor("gameStartedEvent", player.publicKey);
+
+    const { computationOffset, publicKey, nonce } = await queueChestGame(player);
+
+    const started = await startedPromise;
+    expect(started.player.toBase58()).to.equal(player.publicKey.toBase58());
+    expect(started.computationOffset.eq(computationOffset)).to.be.true;
+    expect(Buffer.from(started.pubKey).equals(Buffer.from(publicKey))).to.be.true;
+    expect(started.nonce.toString()).to.equal(deserializeLE(nonce).toString());
+  });
+
+  it("Caps the payout of a large winning game", async () => {
+    const player = await newFundedPlayer();
+    const maxPayout = new anchor.BN(0.15 * LAMPORTS_PER_SOL);
+
+    await updateConfig({ maxPayout });
+
+    try {
+      const resultPromise = awaitEventFor("gameResultEvent", player.publicKey);
+      // 5 chests at 0.1 SOL would pay 0.5 SOL uncapped
+      const { computationOffset } = await queueChestGame(player, { numChests: 5 });
+      await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+
+      const result = await resultPromise;
+      if (result.playerWon) {
+        expect(result.payout.toNumber()).to.equal(maxPayout.toNumber());
+      } else {
+        expect(result.payout.toNumber()).to.equal(0);
+      }
+    } finally {
+      await updateConfig({ maxPayout: new anchor.BN(0) });
+    }
+  });
+
+  it("Reports a fair RTP without a house edge", async () => {
+    for (const numChests of [2, 3, 5]) {
+      expect(await program.methods.rtp(numChests, new anchor.BN(0)).view()).to.equal(10_000);
+    }
+    await updateConfig({ pushEnabled: true });
+    try {
+      expect(await program.methods.rtp(3, new anchor.BN(0)).view()).to.equal(10_000);
+    } finally {
+      await updateConfig({ pushEnabled: false });
+    }
+  });
+
+  it("Accepts house edges at the bounds and rejects anything above", async () => {
+    const maxEdge = 1_000;
+    try {
+      for (const edge of [0, maxEdge]) {
+        await updateConfig({ houseEdgeBps: edge });
+        expect(await program.methods.rtp(3, new anchor.BN(0)).view()).to.equal(10_000 - edge);
+      }
+      await expectError(updateConfig({ houseEdgeBps: maxEdge + 1 }), "HouseEdgeOutOfBounds");
+      await expectError(updateConfig({ houseEdgeBps: 9_000 }), "HouseEdgeOutOfBounds");
+    } finally {
+      await updateConfig({ houseEdgeBps: 0 });
+    }
+  });
+
+  it("Caps the edge on a large win at max_rake_per_game", async () => {
+    const bet = new anchor.BN(LAMPORTS_PER_SOL / 10);
+    const maxRake = new anchor.BN(LAMPORTS_PER_SOL / 1_000);
+    await updateConfig({ houseEdgeBps: 500, maxRakePerGame: maxRake });
+    try {
+      // 5% of a 2x win on 0.1 SOL is 0.01 SOL, well over the 0.001 SOL cap
+      const quote = await program.methods
+        .quoteGame(2, bet)
+        .accountsPartial({ treasury: getTreasuryPDA() })
+        .view();
+      expect(quote.payout.toString()).to.equal(bet.muln(2).sub(maxRake).toString());
+      expect(await program.methods.rtp(2, bet).view()).to.equal(9_950);
+      expect(await program.methods.rtp(2, new anchor.BN(0)).view()).to.equal(9_500);
+    } finally {
+      await updateConfig({ houseEdgeBps: 0, maxRakePerGame: new anchor.BN(0) });
+    }
+  });
+
+  it("Quotes the same payout the callback pays", async () => {
+    const player = await newFundedPlayer();
+    const numChests = 3;
+    const betAmount = new anchor.BN(0.1 * LAMPORTS_PER_SOL);
+    const entryFee = new anchor.BN(0.001 * LAMPORTS_PER_SOL);
+
+    await updateConfig({ entryFee });
+
+    try {
+      const quote = await program.methods
+        .quoteGame(numChests, betAmount)
+        .accountsPartial({ treasury: getTreasuryPDA() })
+        .view();
+      expect(quote.entryFee.eq(entryFee)).to.be.true;
+      expect(quote.netWinnings.eq(quote.maxPayout.sub(betAmount).sub(entryFee))).to.be.true;
+      expect(quote.treasuryCanCover).to.be.true;
+
+      const resultPromise = awaitEventFor("gameResultEvent", player.publicKey);
+      const { computationOffset } = await queueChestGame(player, { numChests, betAmount });
+      await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+
+      const result = await resultPromise;
+      if (result.playerWon) {
+        expect(result.payout.eq(quote.maxPayout)).to.be.true;
+      } else {
+        expect(result.payout.toNumber()).to.equal(0);
+      }
+    } finally {
+      await updateConfig({ entryFee: new anchor.BN(0) });
+    }
+  });
+
+  it("Logs the resolved multiplier in the callback", async () => {
+    const player = await newFundedPlayer();
+    const { computationOffset } = await queueChestGame(player, { numChests: 4 });
+    const finalizeSig = await awaitComputationFinalization(
+      provider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
+
+    const tx = await provider.connection.getTransaction(finalizeSig, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const line = tx?.meta?.logMessages?.find((l) => l.includes("multiplier="));
+    expect(line, "multiplier log line").to.not.be.undefined;
+    expect(line).to.match(/multiplier=(4\.0000|0\.0000), gross=\d+, net=\d+/);
+  });
+
+  it("Keeps the game account rent-exempt after settlement", async () => {
+    const player = await newFundedPlayer();
+    const gamePda = getGamePDA(player.publicKey);
+    const { computationOffset } = await queueChestGame(player, { numChests: 2 });
+    await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+
+    const game = await program.account.gameAccount.fetch(gamePda);
+    expect(game.status).to.equal(2);
+    const info = await provider.connection.getAccountInfo(gamePda);
+    const rentFloor = await provider.connection.getMinimumBalanceForRentExemption(info!.data.length);
+    expect(info!.lamports).to.be.at.least(rentFloor);
+  });
+
+  it("Aggregates house stats across several games", async () => {
+    const before = await program.account.houseStats.fetch(getHouseStatsPDA());
+
+    let wagered = 0;
+    let paidOut = 0;
+    for (const numChests of [2, 3, 4]) {
+      const player = await newFundedPlayer();
+      const resultPromise = awaitEventFor("gameResultEvent", player.publicKey);
+      const { computationOffset, betAmount } = await queueChestGame(player, { numChests });
+      await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+      const result = await resultPromise;
+      wagered += betAmount.toNumber();
+      paidOut += result.payout.toNumber();
+    }
+
+    const after = await program.account.houseStats.fetch(getHouseStatsPDA());
+    expect(after.totalGames.sub(before.totalGames).toNumber()).to.equal(3);
+    expect(after.totalWagered.sub(before.totalWagered).toNumber()).to.equal(wagered);
+    expect(after.totalPaidOut.sub(before.totalPaidOut).toNumber()).to.equal(paidOut);
+    expect(after.houseProfit.sub(before.houseProfit).toNumber()).to.equal(wagered - paidOut);
+  });
+
+  it("Ignores a retried play with the same idempotency key", async () => {
+    const player = await newFundedPlayer();
+    const idempotencyKey = Array.from(randomBytes(16));
+
+    const first = await queueChestGame(player, { idempotencyKey });
+    await queueChestGame(player, { idempotencyKey, skipPreflight: false });
+
+    const game = await program.account.gameAccount.fetch(getGamePDA(player.publicKey));
+    expect(game.computationOffset.eq(first.computationOffset)).to.be.true;
+    expect(game.betAmount.eq(first.betAmount)).to.be.true;
+
+    // Once the game has settled, the same key starts a new one
+    await awaitComputationFinalization(provider, first.computationOffset, program.programId, "confirmed");
+    const second = await queueChestGame(player, { idempotencyKey, skipPreflight: false });
+    const next = await program.account.gameAccount.fetch(getGamePDA(player.publicKey));
+    expect(next.computationOffset.eq(second.computationOffset)).to.be.true;
+    expect(next.status).to.equal(1);
+  });
+
+  it("Dedupes retried odds and hidden plays and collects their tips", async () => {
+    const [tipVault] = PublicKey.findProgramAddressSync([Buffer.from("tip_vault")], program.programId);
+    const tip = new anchor.BN(1_000_000);
+    const betAmount = new anchor.BN(0.1 * LAMPORTS_PER_SOL);
+
+    for (const queue of [
+      (player: anchor.web3.Keypair, extras: PlayExtras) =>
+        queueOddsGame(player, 5_000, BigInt(0), betAmount, false, extras),
+      (player: anchor.web3.Keypair, extras: PlayExtras) =>
+        queueHiddenChestGame(player, 3, BigInt(0), betAmount, extras),
+    ]) {
+      const player = await newFundedPlayer();
+      const idempotencyKey = Array.from(randomBytes(16));
+      const tipsBefore = await provider.connection.getBalance(tipVault);
+
+      const first = await queue(player, { idempotencyKey, tip });
+      const balance = await provider.connection.getBalance(player.publicKey);
+      await queue(player, { idempotencyKey, tip });
+
+      const game = await program.account.gameAccount.fetch(getGamePDA(player.publicKey));
+      expect(game.computationOffset.eq(first.computationOffset)).to.be.true;
+      expect((await provider.connection.getBalance(tipVault)) - tipsBefore).to.equal(tip.toNumber());
+      // The retry only cost its transaction fee
+      expect(balance - (await provider.connection.getBalance(player.publicKey))).to.be.lessThan(10_000);
+    }
+  });
+
+  describe("Input validation", () => {
+    it("Exports the economic defaults as IDL constants", () => {
+      const constants = Object.fromEntries(
+        (program.idl.constants ?? []).map((c) => [c.name, { type: c.type, value: c.value }])
+      );
+      expect(constants.MIN_BET_LAMPORTS).to.deep.equal({ type: "u64", value: "10000000" });
+      expect(constants.PROGRAM_VERSION).to.deep.equal({ type: "u16", value: "1" });
+      expect(constants.MIN_CHESTS).to.deep.equal({ type: "u8", value: "2" });
+      expect(constants.MAX_CHESTS).to.deep.equal({ type: "u8", value: "5" });
+      expect(constants.MAX_SERIES_ROUNDS).to.deep.equal({ type: "u8", value: "9" });
+      expect(constants.GAME_TIMEOUT_SECS).to.deep.equal({ type: "i64", value: "60" });
+      expect(constants.MIN_FUNDING).to.deep.equal({ type: "u64", value: "1000000" });
+      expect(constants.MIN_WIN_PROBABILITY_BPS).to.deep.equal({ type: "u16", value: "2000" });
+      expect(constants.MIN_HOUSE_EDGE_BPS).to.deep.equal({ type: "u16", value: "0" });
+      expect(constants.MAX_HOUSE_EDGE_BPS).to.deep.equal({ type: "u16", value: "1000" });
+      expect(constants.EMERGENCY_DRAIN_TIMELOCK_SECS).to.deep.equal({ type: "i64", value: "172800" });
+      for (const circuit of ["play_chest_game", "play_hidden_chest_game", "play_odds_game", "play_pot_game"]) {
+        const offset = Buffer.from(getCompDefAccOffset(circuit)).readUInt32LE();
+        expect(constants[`COMP_DEF_OFFSET_${circuit.toUpperCase()}`]).to.deep.equal({ type: "u32", value: offset.toString() });
+      }
+    });
+
+    const cases: [string, QueueOptions, string][] = [
+      ["too few chests", { numChests: 1 }, "ChestCountTooLow"],
+      ["too many chests", { numChests: 6 }, "ChestCountTooHigh"],
+      ["a series longer than best of 9", { roundsTotal: 11 }, "RoundCountTooHigh"],
+      ["a bet under the minimum", { betAmount: new anchor.BN(1_000) }, "BetTooSmall"],
+      ["an all-zero encryption key", { pubKey: new Array(32).fill(0) }, "PubkeyMalformed"],
+      ["a low-order encryption key", { pubKey: [1, ...new Array(31).fill(0)] }, "PubkeyMalformed"],
+      ["a client built for an older program version", { programVersion: PROGRAM_VERSION - 1 }, "VersionMismatch"],
+    ];
+
+    for (const [name, opts, errorCode] of cases) {
+      it(`Rejects ${name} with ${errorCode}`, async () => {
+        const player = await newFundedPlayer();
+        await expectError(queueChestGame(player, { ...opts, skipPreflight: false }), errorCode);
+      });
+    }
+
+    it("Rejects a bet the player can't cover with InsufficientPlayerBalance", async () => {
+      // Enough for the new accounts' rent, but not for the bet on top of it
+      const betAmount = new anchor.BN(0.1 * LAMPORTS_PER_SOL);
+      const player = await newFundedPlayer(0.1);
+      await expectError(queueChestGame(player, { betAmount, skipPreflight: false }), "InsufficientPlayerBalance");
+    });
+
+    it("Rejects a game whose draw range exceeds max_rng_range", async () => {
+      await updateConfig({ maxRngRange: 3 });
+      try {
+        const player = await newFundedPlayer();
+        await expectError(queueChestGame(player, { numChests: 5, skipPreflight: false }), "RngRangeTooLarge");
+      } finally {
+        await updateConfig({ maxRngRange: 0 });
+      }
+    });
+  });
+
+  describe("Best-of-N series", () => {
+    // Best of 3 with 2 chests is a 50% shot, so the fair aggregate multiplier is 2x
+    const numChests = 2;
+    const roundsTotal = 3;
+
+    it("Pays the aggregate multiplier once a series is won", async function () {
+      for (let attempt = 0; attempt < 5; attempt++) {
+        const { betAmount, game, result } = await playSeries(numChests, roundsTotal);
+        if (!result.playerWon) {
+          continue;
+        }
+        expect(game.status).to.equal(2);
+        expect(game.roundsWon).to.be.at.least(2);
+        expect(result.payout.toNumber()).to.equal(betAmount.toNumber() * 2);
+        return;
+      }
+      this.skip();
+    });
+
+    it("Keeps the bet once a series is lost", async function () {
+      for (let attempt = 0; attempt < 5; attempt++) {
+        const { game, result } = await playSeries(numChests, roundsTotal);
+        if (result.playerWon) {
+          continue;
+        }
+        expect(game.status).to.equal(2);
+        expect(game.roundsPlayed - game.roundsWon).to.be.at.least(2);
+        expect(result.payout.toNumber()).to.equal(0);
+        return;
+      }
+      this.skip();
+    });
+
+    it("Rejects an even series length", async () => {
+      const player = await newFundedPlayer();
+      await expectError(
+        queueChestGame(player, { roundsTotal: 2, skipPreflight: false }),
+        "RoundCountEven"
+      );
+    });
+  });
+
+  describe("Push outcome", () => {
+    // 3 chests plus the push chest make a push a 25% shot per game
+    const numChests = 3;
+
+    before(async () => {
+      await updateConfig({ pushEnabled: true });
+    });
+
+    after(async () => {
+      await updateConfig({ pushEnabled: false });
+    });
+
+    it("Refunds the bet with no house edge on a push", async function () {
+      for (let attempt = 0; attempt < 8; attempt++) {
+        const player = await newFundedPlayer();
+        const resultPromise = awaitEventFor("gameResultEvent", player.publicKey);
+        const { computationOffset, betAmount } = await queueChestGame(player, { numChests });
+        const balanceBefore = await provider.connection.getBalance(player.publicKey);
+        await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+
+        const result = await resultPromise;
+        if (!result.pushed) {
+          continue;
+        }
+        expect(result.playerWon).to.be.false;
+        expect(result.payout.eq(betAmount)).to.be.true;
+        const balanceAfter = await provider.connection.getBalance(player.publicKey);
+        expect(balanceAfter - balanceBefore).to.equal(betAmount.toNumber());
+        const game = await program.account.gameAccount.fetch(getGamePDA(player.publicKey));
+        expect(game.status).to.equal(2);
+        return;
+      }
+      this.skip();
+    });
+
+    it("Replays a pushed round within a series", async function () {
+      for (let attempt = 0; attempt < 8; attempt++) {
+        const player = await newFundedPlayer();
+        const roundPromise = awaitEventFor("roundResultEvent", player.publicKey);
+        const { computationOffset } = await queueChestGame(player, { numChests, roundsTotal: 3 });
+        await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+
+        const round = await roundPromise;
+        if (!round.pushed) {
+          continue;
+        }
+        expect(round.round).to.equal(1);
+        expect(round.roundsPlayed).to.equal(0);
+        const game = await program.account.gameAccount.fetch(getGamePDA(player.publicKey));
+        expect(game.status).to.equal(4);
+        return;
+      }
+      this.skip();
+    });
+  });
+
+  describe("Insurance", () => {
+    const insuranceBps = 5_000;
+    const insurancePremiumBps = 300;
+    const betAmount = new anchor.BN(0.1 * LAMPORTS_PER_SOL);
+
+    before(async () => {
+      await updateConfig({ insuranceBps, insurancePremiumBps });
+    });
+
+    after(async () => {
+      await updateConfig({ insuranceBps: 0, insurancePremiumBps: 0 });
+    });
+
+    // Plays insured 2-chest games until one settles with the wanted outcome
+    async function playInsured(wantWin: boolean) {
+      for (let attempt = 0; attempt < 6; attempt++) {
+        const player = await newFundedPlayer();
+        const balanceBefore = await provider.connection.getBalance(player.publicKey);
+        const resultPromise = awaitEventFor("gameResultEvent", player.publicKey);
+        const { computationOffset } = await queueChestGame(player, {
+          numChests: 2,
+          betAmount,
+          insurance: true,
+        });
+        await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+        const result = await resultPromise;
+        if (result.playerWon === wantWin) {
+          return { player, balanceBefore, result };
+        }
+      }
+      return null;
+    }
+
+    it("Pays a normal win on an insured bet", async function () {
+      const played = await playInsured(true);
+      if (!played) {
+        this.skip();
+      }
+      expect(played.result.payout.toNumber()).to.equal(betAmount.toNumber() * 2);
+    });
+
+    it("Refunds the insured share of a losing bet", async function () {
+      const played = await playInsured(false);
+      if (!played) {
+        this.skip();
+      }
+      const refund = (betAmount.toNumber() * insuranceBps) / 10_000;
+      const premium = (betAmount.toNumber() * insurancePremiumBps) / 10_000;
+      expect(played.result.payout.toNumber()).to.equal(refund);
+
+      // Net of the bet and premium; the game PDA rent and tx fees come out of the player too
+      const game = await program.account.gameAccount.fetch(getGamePDA(played.player.publicKey));
+      expect(game.insured).to.be.true;
+      const balanceAfter = await provider.connection.getBalance(played.player.publicKey);
+      expect(played.balanceBefore - balanceAfter).to.be.at.least(betAmount.toNumber() + premium - refund);
+    });
+
+    it("Rejects insurance when it is not offered", async () => {
+      await updateConfig({ insuranceBps: 0 });
+      try {
+        const player = await newFundedPlayer();
+        await expectError(
+          queueChestGame(player, { insurance: true, skipPreflight: false }),
+          "InsuranceDisabled"
+        );
+      } finally {
+        await updateConfig({ insuranceBps });
+      }
+    });
+  });
+
+  describe("Delayed payouts", () => {
+    const betAmount = new anchor.BN(0.1 * LAMPORTS_PER_SOL);
+
+    function getPendingClaimPDA(player: PublicKey): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("pending_claim"), player.toBuffer()],
+        program.programId
+      )[0];
+    }
+
+    // Plays 2-chest games under the given ceiling until one is won
+    async function playUntilWin(instantPayoutCeiling: number, claimDelaySecs: number) {
+      await updateConfig({
+        instantPayoutCeiling: new anchor.BN(instantPayoutCeiling),
+        claimDelaySecs: new anchor.BN(claimDelaySecs),
+      });
+      try {
+        for (let attempt = 0; attempt < 6; attempt++) {
+          const player = await newFundedPlayer();
+          const resultPromise = awaitEventFor("gameResultEvent", player.publicKey);
+          const { computationOffset } = await queueChestGame(player, { numChests: 2, betAmount });
+          await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+          const result = await resultPromise;
+          if (result.playerWon) {
+            return { player, result };
+          }
+        }
+      } finally {
+        await updateConfig({ instantPayoutCeiling: new anchor.BN(0), claimDelaySecs: new anchor.BN(0) });
+      }
+      return null;
+    }
+
+    it("Pays a win under the ceiling instantly", async function () {
+      const played = await playUntilWin(LAMPORTS_PER_SOL, 3600);
+      if (!played) {
+        this.skip();
+      }
+      const claim = await program.account.pendingClaim.fetch(getPendingClaimPDA(played.player.publicKey));
+      expect(claim.amount.toNumber()).to.equal(0);
+    });
+
+    it("Escrows a win over the ceiling until the claim delay passes", async function () {
+      const played = await playUntilWin(0.15 * LAMPORTS_PER_SOL, 3600);
+      if (!played) {
+        this.skip();
+      }
+      const claimPda = getPendingClaimPDA(played.player.publicKey);
+      const claim = await program.account.pendingClaim.fetch(claimPda);
+      expect(claim.amount.eq(played.result.payout)).to.be.true;
+      expect(claim.claimableAt.toNumber()).to.be.greaterThan(Date.now() / 1000 + 3000);
+
+      await expectError(
+        program.methods
+          .claimWinnings()
+          .accountsPartial({ player: played.player.publicKey })
+          .signers([played.player])
+          .rpc({ commitment: "confirmed" }),
+        "ClaimNotReady"
+      );
+    });
+
+    it("Releases escrowed winnings once claimable", async function () {
+      const played = await playUntilWin(0.15 * LAMPORTS_PER_SOL, 0);
+      if (!played) {
+        this.skip();
+      }
+      const balanceBefore = await provider.connection.getBalance(played.player.publicKey);
+      const claimedPromise = awaitEventFor("winningsClaimedEvent", played.player.publicKey);
+      await program.methods
+        .claimWinnings()
+        .accountsPartial({ player: played.player.publicKey })
+        .signers([played.player])
+        .rpc({ commitment: "confirmed" });
+      const claimed = await claimedPromise;
+
+      expect(claimed.amount.eq(played.result.payout)).to.be.true;
+      const balanceAfter = await provider.connection.getBalance(played.player.publicKey);
+      expect(balanceAfter - balanceBefore).to.be.at.least(played.result.payout.toNumber() - 10_000);
+    });
+
+    it("Lets a keeper claim abandoned winnings after the grace period", async function () {
+      const played = await playUntilWin(0.15 * LAMPORTS_PER_SOL, 0);
+      if (!played) {
+        this.skip();
+      }
+      const keeper = await newFundedPlayer(1);
+      const claimFor = () =>
+        program.methods
+          .claimWinningsFor(played.player.publicKey)
+          .accountsPartial({
+            keeper: keeper.publicKey,
+            playerWallet: played.player.publicKey,
+            pendingClaim: getPendingClaimPDA(played.player.publicKey),
+          })
+          .signers([keeper])
+          .rpc({ commitment: "confirmed" });
+
+      await expectError(claimFor(), "KeeperClaimsDisabled");
+      await updateConfig({ keeperClaimGraceSecs: new anchor.BN(3600), keeperFeeBps: 100 });
+      try {
+        await expectError(claimFor(), "ClaimNotReady");
+
+        await updateConfig({ keeperClaimGraceSecs: new anchor.BN(1) });
+        await new Promise((resolve) => setTimeout(resolve, 2000));
+        const balanceBefore = await provider.connection.getBalance(played.player.publicKey);
+        const claimedPromise = awaitEventFor("winningsClaimedForEvent", played.player.publicKey);
+        await claimFor();
+        const claimed = await claimedPromise;
+
+        const keeperFee = played.result.payout.muln(100).divn(10_000);
+        expect(claimed.keeper.equals(keeper.publicKey)).to.be.true;
+        expect(claimed.keeperFee.eq(keeperFee)).to.be.true;
+        expect(claimed.amount.eq(played.result.payout.sub(keeperFee))).to.be.true;
+        const balanceAfter = await provider.connection.getBalance(played.player.publicKey);
+        expect(balanceAfter - balanceBefore).to.equal(claimed.amount.toNumber());
+        await expectError(claimFor(), "NothingToClaim");
+      } finally {
+        await updateConfig({ keeperClaimGraceSecs: new anchor.BN(0), keeperFeeBps: 0 });
+      }
+    });
+  });
+
+  describe("Fee split", () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+
+    async function updateFeeSplit(shares: { recipient: PublicKey; bps: number }[]) {
+      await program.methods
+        .updateFeeSplit(shares)
+        .accountsPartial({ authority: owner.publicKey })
+        .signers([owner])
+        .rpc({ commitment: "confirmed" });
+    }
+
+    it("Routes each beneficiary's share of a losing bet", async function () {
+      // Recipients need to be rent-exempt already to receive a share
+      const alice = await newFundedPlayer(1);
+      const bob = await newFundedPlayer(1);
+      await updateFeeSplit([
+        { recipient: alice.publicKey, bps: 1_000 },
+        { recipient: bob.publicKey, bps: 2_500 },
+      ]);
+
+      try {
+        for (let attempt = 0; attempt < 6; attempt++) {
+          const player = await newFundedPlayer();
+          const aliceBefore = await provider.connection.getBalance(alice.publicKey);
+          const bobBefore = await provider.connection.getBalance(bob.publicKey);
+          const resultPromise = awaitEventFor("gameResultEvent", player.publicKey);
+          const { computationOffset, betAmount } = await queueChestGame(player, { numChests: 2 });
+          await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+
+          const result = await resultPromise;
+          const aliceDelta = (await provider.connection.getBalance(alice.publicKey)) - aliceBefore;
+          const bobDelta = (await provider.connection.getBalance(bob.publicKey)) - bobBefore;
+          if (result.playerWon) {
+            expect(aliceDelta).to.equal(0);
+            expect(bobDelta).to.equal(0);
+            continue;
+          }
+          expect(aliceDelta).to.equal((betAmount.toNumber() * 1_000) / 10_000);
+          expect(bobDelta).to.equal((betAmount.toNumber() * 2_500) / 10_000);
+          return;
+        }
+        this.skip();
+      } finally {
+        await updateFeeSplit([]);
+      }
+    });
+
+    it("Rejects shares summing to more than 10000 bps", async () => {
+      await expectError(
+        updateFeeSplit([
+          { recipient: anchor.web3.Keypair.generate().publicKey, bps: 6_000 },
+          { recipient: anchor.web3.Keypair.generate().publicKey, bps: 4_001 },
+        ]),
+        "InvalidFeeSplit"
+      );
+    });
+  });
+
+  describe("Player seeds", () => {
+    it("Records a fairness proof at settlement", async () => {
+      const player = await newFundedPlayer();
+      const playerSeed = Array.from(randomBytes(32));
+      const resultPromise = awaitEventFor("gameResultEvent", player.publicKey);
+      const { computationOffset } = await queueChestGame(player, { playerSeed, numChests: 4 });
+      await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+      const result = await resultPromise;
+
+      const [proofPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fairness_proof"), player.publicKey.toBuffer()],
+        program.programId
+      );
+      const proof = await program.account.fairnessProof.fetch(proofPda);
+      expect(proof.version).to.equal(4);
+      expect(proof.computationOffset.eq(computationOffset)).to.be.true;
+      expect(proof.numChests).to.equal(4);
+      expect(proof.winningChest).to.equal(result.winningChest);
+      expect(proof.winningChest).to.be.lessThan(proof.drawRange);
+      expect(proof.playerSeed).to.deep.equal(playerSeed);
+    });
+
+    it("Mixes the player's seed into the draw and reveals it", async () => {
+      const player = await newFundedPlayer();
+      const numChests = 3;
+      const playerSeed = Array.from(randomBytes(32));
+      const resultPromise = awaitEventFor("gameResultEvent", player.publicKey);
+      const { computationOffset } = await queueChestGame(player, { numChests, playerSeed });
+
+      const pending = await program.account.gameAccount.fetch(getGamePDA(player.publicKey));
+      expect(pending.playerSeed).to.deep.equal(playerSeed);
+
+      await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+      const result = await resultPromise;
+      expect(result.playerSeed).to.deep.equal(playerSeed);
+      expect(result.numChests).to.equal(numChests);
+      expect(result.winningChest).to.be.lessThan(numChests);
+      expect(result.playerWon).to.equal(result.winningChest === 0);
+    });
+
+    it("Reveals the raw draw behind the winning chest when configured", async () => {
+      await updateConfig({ revealRawDraw: true });
+      try {
+        const player = await newFundedPlayer();
+        const numChests = 4;
+        const revealed = awaitEventFor("rawDrawRevealedEvent", player.publicKey);
+        const resultPromise = awaitEventFor("gameResultEvent", player.publicKey);
+        const { computationOffset } = await queueChestGame(player, { numChests });
+        await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+
+        const { rawDraw, entropyOffset, drawRange, winningChest } = await revealed;
+        expect(drawRange).to.equal(numChests);
+        expect(rawDraw).to.be.lessThan(drawRange);
+        expect((rawDraw + entropyOffset) % drawRange).to.equal(winningChest);
+        expect((await resultPromise).winningChest).to.equal(winningChest);
+      } finally {
+        await updateConfig({ revealRawDraw: false });
+      }
+    });
+
+    it("Mixes a recent slot hash into the draw and reveals it for replay", async () => {
+      await updateConfig({ mixSlotHash: true, revealRawDraw: true });
+      try {
+        const player = await newFundedPlayer();
+        const numChests = 4;
+        const playerSeed = Array.from(randomBytes(32));
+        const revealed = awaitEventFor("rawDrawRevealedEvent", player.publicKey);
+        const resultPromise = awaitEventFor("gameResultEvent", player.publicKey);
+        const { computationOffset } = await queueChestGame(player, { numChests, playerSeed });
+
+        // The game took the sysvar's entry for the slot it was queued in
+        const pending = await program.account.gameAccount.fetch(getGamePDA(player.publicKey));
+        const sysvar = (await provider.connection.getAccountInfo(SYSVAR_SLOT_HASHES_PUBKEY))!.data;
+        const entries = Number(sysvar.readBigUInt64LE(0));
+        const entry = [...Array(entries).keys()]
+          .map((i) => 8 + i * 40)
+          .find((at) => sysvar.readBigUInt64LE(at) === BigInt(pending.entropySlot.toString()));
+        expect(entry).to.not.be.undefined;
+        expect(pending.slotHash).to.deep.equal(Array.from(sysvar.subarray(entry! + 8, entry! + 40)));
+
+        await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+        const { rawDraw, entropyOffset, drawRange, winningChest } = await revealed;
+        const result = await resultPromise;
+        expect(result.entropySlot.eq(pending.entropySlot)).to.be.true;
+        expect(result.slotHash).to.deep.equal(pending.slotHash);
+
+        // The revealed seed and slot hash alone reproduce the shift
+        const range = BigInt(drawRange);
+        const playerEntropy = createHash("sha256").update(Buffer.from(playerSeed)).digest().readBigUInt64LE(0);
+        const slotEntropy = Buffer.from(result.slotHash).readBigUInt64LE(0);
+        expect(Number(((playerEntropy % range) + (slotEntropy % range)) % range)).to.equal(entropyOffset);
+        expect((rawDraw + entropyOffset) % drawRange).to.equal(winningChest);
+      } finally {
+        await updateConfig({ mixSlotHash: false, revealRawDraw: false });
+      }
+    });
+  });
+
+  describe("Hidden chest count", () => {
+    it("Settles a game whose chest count is only revealed in the result", async () => {
+      const player = await newFundedPlayer();
+      const startedPromise = awaitEventFor("gameStartedEvent", player.publicKey);
+      const resultPromise = awaitEventFor("gameResultEvent", player.publicKey);
+      const { computationOffset, betAmount } = await queueHiddenChestGame(player, 4, BigInt(2));
+
+      const started = await startedPromise;
+      expect(started.numChests).to.equal(0);
+      const pending = await program.account.gameAccount.fetch(getGamePDA(player.publicKey));
+      expect(pending.numChests).to.equal(0);
+      expect(pending.hiddenChests).to.be.true;
+
+      await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+      const result = await resultPromise;
+      expect(result.numChests).to.equal(4);
+      expect(result.winningChest).to.be.lessThan(4);
+      expect(result.playerWon).to.equal(result.winningChest === 2);
+      expect(result.payout.toNumber()).to.equal(result.playerWon ? betAmount.toNumber() * 4 : 0);
+
+      const settled = await program.account.gameAccount.fetch(getGamePDA(player.publicKey));
+      expect(settled.numChests).to.equal(4);
+      expect(settled.status).to.equal(2);
+    });
+
+    it("Refunds a game whose encrypted chest count is out of range", async () => {
+      const player = await newFundedPlayer();
+      const refundedPromise = awaitEventFor("gameRefundedEvent", player.publicKey);
+      const { computationOffset, betAmount } = await queueHiddenChestGame(player, 7);
+      const balanceBefore = await provider.connection.getBalance(player.publicKey);
+
+      await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+      const refunded = await refundedPromise;
+      expect(refunded.betAmount.eq(betAmount)).to.be.true;
+
+      // A failed settlement is Refunded (5), not the Cancelled (3) of a timeout
+      const game = await program.account.gameAccount.fetch(getGamePDA(player.publicKey));
+      expect(game.status).to.equal(5);
+      expect(game.numChests).to.equal(0);
+      const balanceAfter = await provider.connection.getBalance(player.publicKey);
+      expect(balanceAfter - balanceBefore).to.equal(betAmount.toNumber());
+    });
+  });
+
+  describe("Odds games", () => {
+    it("Pays the inverse probability on a 50% roll", async () => {
+      const player = await newFundedPlayer();
+      const rolledPromise = awaitEventFor("oddsGameRolledEvent", player.publicKey);
+      const resultPromise = awaitEventFor("gameResultEvent", player.publicKey);
+      const { computationOffset, betAmount } = await queueOddsGame(player, 5_000, BigInt(1));
+
+      const pending = await program.account.gameAccount.fetch(getGamePDA(player.publicKey));
+      expect(pending.winProbabilityBps).to.equal(5_000);
+      expect(pending.numChests).to.equal(0);
+
+      await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+      const rolled = await rolledPromise;
+      const result = await resultPromise;
+      expect(rolled.winProbabilityBps).to.equal(5_000);
+      expect(rolled.roll).to.be.lessThan(10_000);
+      // Choice 1 takes the top of the range
+      expect(result.playerWon).to.equal(rolled.roll >= 5_000);
+      expect(result.payout.toNumber()).to.equal(result.playerWon ? betAmount.toNumber() * 2 : 0);
+    });
+
+    it("Rejects win probabilities outside the supported range", async () => {
+      const player = await newFundedPlayer();
+      for (const bps of [1_999, 10_000]) {
+        await expectError(
+          queueOddsGame(player, bps, BigInt(0), new anchor.BN(0.1 * LAMPORTS_PER_SOL), false),
+          "InvalidWinProbability"
+        );
+      }
+    });
+  });
+
+  describe("Session keys", () => {
+    function getSessionPDA(player: PublicKey): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("session"), player.toBuffer()],
+        program.programId
+      )[0];
+    }
+
+    async function createSession(player: anchor.web3.Keypair, ttlSecs: number, spendCap: number) {
+      const sessionKey = await newFundedPlayer(1);
+      const expiresAt = Math.floor(Date.now() / 1000) + ttlSecs;
+      await program.methods
+        .createSession(sessionKey.publicKey, new anchor.BN(expiresAt), new anchor.BN(spendCap))
+        .accountsPartial({ player: player.publicKey })
+        .signers([player])
+        .rpc({ commitment: "confirmed" });
+      return sessionKey;
+    }
+
+    async function playWithSession(
+      player: PublicKey,
+      sessionKey: anchor.web3.Keypair,
+      betAmount: anchor.BN,
+      extras: PlayExtras = {}
+    ) {
+      const mxePublicKey = await getMXEPublicKeyWithRetry(provider, program.programId);
+      const privateKey = x25519.utils.randomSecretKey();
+      const publicKey = x25519.getPublicKey(privateKey);
+      const cipher = new RescueCipher(x25519.getSharedSecret(privateKey, mxePublicKey));
+      const nonce = randomBytes(16);
+      const encryptedChoice = cipher.encrypt([BigInt(0)], nonce);
+      const computationOffset = new anchor.BN(randomBytes(8), "hex");
+
+      await program.methods
+        .playChestGameSession(
+          computationOffset,
+          3,
+          betAmount,
+          Array.from(encryptedChoice[0]) as any,
+          Array.from(publicKey) as any,
+          new anchor.BN(deserializeLE(nonce).toString()),
+          extras.idempotencyKey ?? new Array(16).fill(0),
+          extras.tip ?? new anchor.BN(0)
+        )
+        .accountsPartial({
+          sessionSigner: sessionKey.publicKey,
+          player,
+          gameAccount: getGamePDA(player),
+          treasury: getTreasuryPDA(),
+          computationAccount: getComputationAccAddress(arciumEnv.arciumClusterOffset, computationOffset),
+          clusterAccount: clusterAccount,
+          mxeAccount: getMXEAccAddress(program.programId),
+          mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+          executingPool: getExecutingPoolAccAddress(arciumEnv.arciumClusterOffset),
+          compDefAccount: getCompDefAccAddress(
+            program.programId,
+            Buffer.from(getCompDefAccOffset("play_chest_game")).readUInt32LE()
+          ),
+        })
+        .signers([sessionKey])
+        .rpc({ commitment: "confirmed" });
+
+      return computationOffset;
+    }
+
+    it("Bets from the session escrow when the session key signs", async () => {
+      const player = await newFundedPlayer();
+      const sessionKey = await createSession(player, 300, 0.5 * LAMPORTS_PER_SOL);
+      const betAmount = new anchor.BN(0.1 * LAMPORTS_PER_SOL);
+      const playerBefore = await provider.connection.getBalance(player.publicKey);
+
+      const computationOffset = await playWithSession(player.publicKey, sessionKey, betAmount);
+
+      const session = await program.account.sessionKey.fetch(getSessionPDA(player.publicKey));
+      expect(session.spent.eq(betAmount)).to.be.true;
+      const game = await program.account.gameAccount.fetch(getGamePDA(player.publicKey));
+      expect(game.player.equals(player.publicKey)).to.be.true;
+      expect(game.betAmount.eq(betAmount)).to.be.true;
+      // The player's wallet is untouched; the escrow covered the bet
+      expect(await provider.connection.getBalance(player.publicKey)).to.equal(playerBefore);
+
+      await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+    });
+
+    it("Rejects bets after the session expires", async () => {
+      const player = await newFundedPlayer();
+      const sessionKey = await createSession(player, 2, 0.5 * LAMPORTS_PER_SOL);
+      await new Promise((resolve) => setTimeout(resolve, 4000));
+
+      await expectError(
+        playWithSession(player.publicKey, sessionKey, new anchor.BN(0.1 * LAMPORTS_PER_SOL)),
+        "SessionExpired"
+      );
+    });
+
+    it("Rejects a bet above the remaining spend cap", async () => {
+      const player = await newFundedPlayer();
+      const sessionKey = await createSession(player, 300, 0.1 * LAMPORTS_PER_SOL);
+
+      await expectError(
+        playWithSession(player.publicKey, sessionKey, new anchor.BN(0.2 * LAMPORTS_PER_SOL)),
+        "SessionCapExceeded"
+      );
+    });
+  });
+
+  it("Accepts a max bet the treasury can cover", async () => {
+    const maxBet = new anchor.BN(0.1 * LAMPORTS_PER_SOL);
+    await updateConfig({ maxBet });
+    try {
+      const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("config")], program.programId);
+      const config = await program.account.gameConfig.fetch(configPda);
+      expect(config.maxBet.eq(maxBet)).to.be.true;
+
+      const player = await newFundedPlayer();
+      await expectError(
+        queueChestGame(player, { betAmount: maxBet.addn(1), skipPreflight: false }),
+        "BetTooLarge"
+      );
+    } finally {
+      await updateConfig({ maxBet: new anchor.BN(0) });
+    }
+  });
+
+  it("Spaces config updates by min_config_update_interval", async () => {
+    const intervalSecs = 3;
+    await updateConfig({ minConfigUpdateInterval: new anchor.BN(intervalSecs) });
+    // The wait set by the current config applies, even to an update lifting it
+    await expectError(updateConfig({ minConfigUpdateInterval: new anchor.BN(0) }), "ConfigUpdateTooSoon");
+
+    await new Promise((resolve) => setTimeout(resolve, (intervalSecs + 1) * 1000));
+    await updateConfig({ minConfigUpdateInterval: new anchor.BN(0) });
+    const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("config")], program.programId);
+    expect((await program.account.gameConfig.fetch(configPda)).minConfigUpdateInterval.toNumber()).to.equal(0);
+  });
+
+  it("Caps min_config_update_interval at a week", async () => {
+    const week = 7 * 24 * 60 * 60;
+    await expectError(updateConfig({ minConfigUpdateInterval: new anchor.BN(week + 1) }), "InvalidConfig");
+  });
+
+  it("Freezes payouts while a config update is still held back", async () => {
+    const intervalSecs = 3;
+    const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("config")], program.programId);
+    await updateConfig({ minConfigUpdateInterval: new anchor.BN(intervalSecs) });
+    try {
+      await expectError(updateConfig({ minConfigUpdateInterval: new anchor.BN(0) }), "ConfigUpdateTooSoon");
+      // Safe mode skips the interval and leaves the update clock alone
+      const before = (await program.account.gameConfig.fetch(configPda)).lastConfigUpdate.toNumber();
+      await setPayoutsFrozen(true);
+      const config = await program.account.gameConfig.fetch(configPda);
+      expect(config.payoutsFrozen).to.equal(true);
+      expect(config.lastConfigUpdate.toNumber()).to.equal(before);
+    } finally {
+      await setPayoutsFrozen(false);
+      await new Promise((resolve) => setTimeout(resolve, (intervalSecs + 1) * 1000));
+      await updateConfig({ minConfigUpdateInterval: new anchor.BN(0) });
+    }
+  });
+
+  it("Rejects a max bet whose worst-case payout exceeds the treasury", async () => {
+    const treasuryBalance = await provider.connection.getBalance(getTreasuryPDA());
+    // A best-of-9 on 5 chests pays about 51x, more than the whole treasury at this bet
+    const maxBet = new anchor.BN(Math.ceil(treasuryBalance / 50) + LAMPORTS_PER_SOL);
+    await expectError(updateConfig({ maxBet }), "ConfigWouldBeInsolvent");
+  });
+
+  it("Holds a treasury reserve back only while the max bet stays covered", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const setReserve = (reserve: number) =>
+      program.methods
+        .setReserve(new anchor.BN(reserve))
+        .accountsPartial({ authority: owner.publicKey })
+        .signers([owner])
+        .rpc({ commitment: "confirmed" });
+
+    await updateConfig({ maxBet: new anchor.BN(0.1 * LAMPORTS_PER_SOL) });
+    try {
+      await setReserve(LAMPORTS_PER_SOL);
+      expect((await program.account.treasury.fetch(getTreasuryPDA())).reserve.toNumber()).to.equal(LAMPORTS_PER_SOL);
+
+      // Reserving the whole balance leaves nothing to cover a max bet with
+      const treasuryBalance = await provider.connection.getBalance(getTreasuryPDA());
+      await expectError(setReserve(treasuryBalance), "ConfigWouldBeInsolvent");
+    } finally {
+      await setReserve(0);
+      await updateConfig({ maxBet: new anchor.BN(0) });
+    }
+  });
+
+  it("Blocks the treasury authority from playing only when configured", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+
+    await updateConfig({ blockAuthorityPlay: true });
+    try {
+      await expectError(queueChestGame(owner, { skipPreflight: false }), "AuthorityCannotPlay");
+    } finally {
+      await updateConfig({ blockAuthorityPlay: false });
+    }
+
+    const { computationOffset } = await queueChestGame(owner, { skipPreflight: false });
+    await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+  });
+
+  it("Round-trips game metadata through settlement", async () => {
+    const player = await newFundedPlayer();
+    const metadata = Array.from(randomBytes(32));
+
+    const startedPromise = awaitEventFor("gameStartedEvent", player.publicKey);
+    const resultPromise = awaitEventFor("gameResultEvent", player.publicKey);
+    const { computationOffset } = await queueChestGame(player, { metadata });
+    const started = await startedPromise;
+    expect(started.metadata).to.deep.equal(metadata);
+
+    await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+    const result = await resultPromise;
+    expect(result.metadata).to.deep.equal(metadata);
+
+    const game = await program.account.gameAccount.fetch(getGamePDA(player.publicKey));
+    expect(game.metadata).to.deep.equal(metadata);
+  });
+
+  it("Rejects a top-up once the round is queued or the game has settled", async () => {
+    const player = await newFundedPlayer();
+    const topUp = () =>
+      program.methods
+        .topUpBet(new anchor.BN(0.01 * LAMPORTS_PER_SOL))
+        .accountsPartial({ player: player.publicKey })
+        .signers([player])
+        .rpc({ commitment: "confirmed" });
+
+    const { computationOffset } = await queueChestGame(player);
+    await expectError(topUp(), "TopUpAfterQueue");
+
+    await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+    await expectError(topUp(), "SeriesNotInProgress");
+  });
+
+  describe("Settled games", () => {
+    const betAmount = new anchor.BN(0.05 * LAMPORTS_PER_SOL);
+
+    // Queues a 2-chest game and waits for the cluster to settle it
+    async function playRound(player: anchor.web3.Keypair, opts: QueueOptions = {}) {
+      const resultPromise = awaitEventFor("gameResultEvent", player.publicKey);
+      const queued = await queueChestGame(player, { numChests: 2, betAmount, ...opts });
+      await awaitComputationFinalization(provider, queued.computationOffset, program.programId, "confirmed");
+      return { ...queued, result: await resultPromise };
+    }
+
+    // Plays rounds until one is won (a win the program pushed back counts) or
+    // lost, as asked; null if six draws never came out that way
+    async function playUntil(player: anchor.web3.Keypair, won: boolean, opts: QueueOptions = {}) {
+      for (let attempt = 0; attempt < 6; attempt++) {
+        const round = await playRound(player, opts);
+        if ((round.result.playerWon || round.result.pushed) === won) {
+          return round;
+        }
+      }
+      return null;
+    }
+
+    it("Wraps the result log once it is full", async () => {
+      const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+      const [resultLogPda] = PublicKey.findProgramAddressSync([Buffer.from("result_log")], program.programId);
+      const capacity = 16;
+
+      await program.methods
+        .clearResultLog()
+        .accountsPartial({ authority: owner.publicKey })
+        .signers([owner])
+        .rpc({ commitment: "confirmed" });
+
+      // Fill the log and overwrite the oldest entry with distinct bets
+      const player = await newFundedPlayer(10);
+      const bets = [];
+      for (let i = 0; i <= capacity; i++) {
+        const bet = new anchor.BN(0.01 * LAMPORTS_PER_SOL + i);
+        await playRound(player, { betAmount: bet });
+        bets.push(bet);
+      }
+
+      const log = await program.account.resultLog.fetch(resultLogPda);
+      expect(log.len).to.equal(capacity);
+      expect(log.head).to.equal(1);
+      // Slot 0 now holds the newest game, slot 1 the oldest survivor
+      expect(log.entries[0].betAmount.eq(bets[capacity])).to.be.true;
+      expect(log.entries[1].betAmount.eq(bets[1])).to.be.true;
+      expect(log.entries[0].player.equals(player.publicKey)).to.be.true;
+    });
+
+    it("Returns the bet and the game account's rent when cancelling with close", async function () {
+      const player = await newFundedPlayer();
+      const gamePda = getGamePDA(player.publicKey);
+      const queued = await queueChestGame(player);
+      const computationAccount = getComputationAccAddress(arciumEnv.arciumClusterOffset, queued.computationOffset);
+
+      // Only a game still pending once its computation has left the queue can be closed
+      await new Promise((resolve) => setTimeout(resolve, 61_000));
+      const game = await program.account.gameAccount.fetch(gamePda);
+      const info = await provider.connection.getAccountInfo(computationAccount);
+      if (game.status !== 1 || (info && info.data[110] === 0)) {
+        this.skip();
+      }
+      const cancel = (closeAccount: boolean, withProof: boolean) =>
+        program.methods
+          .cancelGame(closeAccount)
+          .accountsPartial({
+            player: player.publicKey,
+            gameAccount: gamePda,
+            mxeAccount: withProof ? getMXEAccAddress(program.programId) : null,
+            computationAccount: withProof ? computationAccount : null,
+          })
+          .rpc({ commitment: "confirmed" });
+
+      // Without proof the computation might still land, so the account can't be closed
+      await expectError(cancel(true, false), "ComputationStillQueued");
+
+      const rent = await provider.connection.getBalance(gamePda, "confirmed") - queued.betAmount.toNumber();
+      const balanceBefore = await provider.connection.getBalance(player.publicKey, "confirmed");
+      await cancel(true, true);
+      const balanceAfter = await provider.connection.getBalance(player.publicKey, "confirmed");
+      expect(balanceAfter - balanceBefore).to.equal(queued.betAmount.toNumber() + rent);
+      expect(await provider.connection.getAccountInfo(gamePda, "confirmed")).to.be.null;
+    });
+
+    it("Rejects plays past the pending-game ceiling", async function () {
+      const pendingGames = async () =>
+        (await program.account.houseStats.fetch(getHouseStatsPDA())).activePendingGames.toNumber();
+      const before = await pendingGames();
+      await updateConfig({ maxPendingGames: new anchor.BN(before + 1) });
+      try {
+        const first = await newFundedPlayer();
+        const { computationOffset } = await queueChestGame(first, { numChests: 2, betAmount });
+        // The ceiling only bites while the first game is still waiting on the cluster
+        if ((await pendingGames()) !== before + 1) {
+          this.skip();
+        }
+
+        const second = await newFundedPlayer();
+        await expectError(
+          queueChestGame(second, { betAmount, skipPreflight: false }),
+          "TooManyPendingGames"
+        );
+
+        // Settling the first game frees its slot
+        await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+        expect(await pendingGames()).to.equal(before);
+        await playRound(second);
+      } finally {
+        await updateConfig({ maxPendingGames: new anchor.BN(0) });
+      }
+    });
+
+    it("Trips the loss circuit breaker and resets after the window", async function () {
+      const windowSecs = 5;
+      // Room for exactly one win's net payout per window
+      await updateConfig({ maxLossPerWindow: betAmount, windowSecs: new anchor.BN(windowSecs) });
+      try {
+        const player = await newFundedPlayer(3);
+        if (!(await playUntil(player, true))) {
+          this.skip();
+        }
+
+        const tripped = awaitEventFor("circuitBreakerTrippedEvent", player.publicKey);
+        const pushed = await playUntil(player, true);
+        if (!pushed) {
+          this.skip();
+        }
+        const event = await tripped;
+        expect(event.blockedPayout.toNumber()).to.equal(betAmount.toNumber() * 2);
+        expect(event.windowNetLoss.eq(betAmount)).to.be.true;
+        // Only the bet came back
+        expect(pushed.result.pushed).to.be.true;
+        expect(pushed.result.payout.eq(betAmount)).to.be.true;
+
+        await new Promise((resolve) => setTimeout(resolve, (windowSecs + 1) * 1000));
+        const won = await playUntil(player, true);
+        if (!won) {
+          this.skip();
+        }
+        expect(won.result.playerWon).to.be.true;
+        expect(won.result.payout.toNumber()).to.equal(betAmount.toNumber() * 2);
+      } finally {
+        await updateConfig({ maxLossPerWindow: new anchor.BN(0), windowSecs: new anchor.BN(0) });
+      }
+    });
+
+    it("Locks winnings until winnings_lock_slots have passed", async function () {
+      const lockSlots = 20;
+      await updateConfig({ winningsLockSlots: new anchor.BN(lockSlots) });
+      try {
+        const player = await newFundedPlayer();
+        const escrowed = awaitEventFor("winningsEscrowedEvent", player.publicKey);
+        if (!(await playUntil(player, true))) {
+          this.skip();
+        }
+        const { claimableSlot, amount } = await escrowed;
+        expect(amount.toNumber()).to.equal(betAmount.toNumber() * 2);
+
+        const claim = () =>
+          program.methods
+            .claimWinnings()
+            .accountsPartial({ player: player.publicKey })
+            .signers([player])
+            .rpc({ commitment: "confirmed" });
+        await expectError(claim(), "ClaimNotReady");
+
+        while ((await provider.connection.getSlot("confirmed")) < claimableSlot.toNumber()) {
+          await new Promise((resolve) => setTimeout(resolve, 400));
+        }
+        const claimed = awaitEventFor("winningsClaimedEvent", player.publicKey);
+        await claim();
+        expect((await claimed).amount.eq(amount)).to.be.true;
+      } finally {
+        await updateConfig({ winningsLockSlots: new anchor.BN(0) });
+      }
+    });
+
+    it("Credits wins to the pending claim for a later sweep", async function () {
+      await updateConfig({ creditWinsToClaim: true });
+      try {
+        const player = await newFundedPlayer();
+        const escrowed = awaitEventFor("winningsEscrowedEvent", player.publicKey);
+        const before = await provider.connection.getBalance(player.publicKey, "confirmed");
+        if (!(await playUntil(player, true))) {
+          this.skip();
+        }
+        const { amount, owed } = await escrowed;
+        expect(amount.toNumber()).to.equal(betAmount.toNumber() * 2);
+        expect(owed.toNumber()).to.equal(0);
+        // Settlement delivered nothing to the wallet itself
+        expect(await provider.connection.getBalance(player.publicKey, "confirmed")).to.be.below(before);
+
+        const claimed = awaitEventFor("winningsClaimedEvent", player.publicKey);
+        await program.methods
+          .claimWinnings()
+          .accountsPartial({ player: player.publicKey })
+          .signers([player])
+          .rpc({ commitment: "confirmed" });
+        expect((await claimed).amount.eq(amount)).to.be.true;
+      } finally {
+        await updateConfig({ creditWinsToClaim: false });
+      }
+    });
+
+    it("Rejects games past the player's max_player_exposure", async function () {
+      const player = await newFundedPlayer();
+      const [statsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("player_stats"), player.publicKey.toBuffer()],
+        program.programId
+      );
+      // A 2-chest win pays 2x, so the game's worst-case winnings are one bet
+      await updateConfig({ maxPlayerExposure: betAmount.subn(1) });
+      try {
+        await expectError(
+          queueChestGame(player, { numChests: 2, betAmount, skipPreflight: false }),
+          "PlayerExposureExceeded"
+        );
+
+        await updateConfig({ maxPlayerExposure: betAmount });
+        const { computationOffset } = await queueChestGame(player, { numChests: 2, betAmount });
+        let stats = await program.account.playerStats.fetch(statsPda);
+        const game = await program.account.gameAccount.fetch(getGamePDA(player.publicKey));
+        // The reservation is only visible while the game waits on the cluster
+        if (game.status !== 1) {
+          this.skip();
+        }
+        expect(stats.outstandingExposure.eq(betAmount)).to.be.true;
+
+        await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+        stats = await program.account.playerStats.fetch(statsPda);
+        expect(stats.outstandingExposure.toNumber()).to.equal(0);
+      } finally {
+        await updateConfig({ maxPlayerExposure: new anchor.BN(0) });
+      }
+    });
+
+    it("Batch-reads player stats and serves the same fields through get_stats", async () => {
+      const players = [await newFundedPlayer(), await newFundedPlayer()];
+      for (const player of players) {
+        await playRound(player);
+      }
+      const statsPdas = players.map(
+        (player) =>
+          PublicKey.findProgramAddressSync(
+            [Buffer.from("player_stats"), player.publicKey.toBuffer()],
+            program.programId
+          )[0]
+      );
+
+      // One getMultipleAccounts round trip for the whole leaderboard page
+      const accounts = await program.account.playerStats.fetchMultiple(statsPdas);
+      for (const [i, stats] of accounts.entries()) {
+        expect(stats.statsVersion).to.equal(1);
+        expect(stats.player.equals(players[i].publicKey)).to.be.true;
+        expect(stats.gamesPlayed.toNumber()).to.equal(1);
+
+        const view = await program.methods.getStats().accountsPartial({ playerStats: statsPdas[i] }).view();
+        expect(view.statsVersion).to.equal(stats.statsVersion);
+        expect(view.player.equals(stats.player)).to.be.true;
+        expect(view.gamesPlayed.eq(stats.gamesPlayed)).to.be.true;
+        expect(view.totalWagered.eq(stats.totalWagered)).to.be.true;
+        expect(view.totalWon.eq(stats.totalWon)).to.be.true;
+        expect(view.netPnl.eq(stats.totalWon.sub(stats.totalWagered))).to.be.true;
+        expect(view.outstandingExposure.eq(stats.outstandingExposure)).to.be.true;
+      }
+    });
+
+    it("Compounds an auto_compound win into the next round's bet", async function () {
+      const player = await newFundedPlayer();
+      const gamePda = getGamePDA(player.publicKey);
+      const compounded = awaitEventFor("winningsCompoundedEvent", player.publicKey);
+      if (!(await playUntil(player, true, { autoCompound: true }))) {
+        this.skip();
+      }
+
+      const event = await compounded;
+      expect(event.payout.eq(betAmount.muln(2))).to.be.true;
+      expect(event.nextBet.eq(event.payout)).to.be.true;
+      // The winnings moved into the game instead of the player's wallet
+      const info = await provider.connection.getAccountInfo(gamePda);
+      const rent = await provider.connection.getMinimumBalanceForRentExemption(info.data.length);
+      expect(info.lamports - rent).to.equal(event.nextBet.toNumber());
+
+      const game = await program.account.gameAccount.fetch(gamePda);
+      expect(game.status).to.equal(4); // SeriesInProgress, waiting for play_next_round
+      expect(game.betAmount.eq(event.nextBet)).to.be.true;
+      expect(game.roundsPlayed).to.equal(0);
+    });
+
+    it("Tops up a compounded bet before its next round is queued", async function () {
+      const player = await newFundedPlayer();
+      const won = await playUntil(player, true, { autoCompound: true });
+      if (!won) {
+        this.skip();
+      }
+      const gamePda = getGamePDA(player.publicKey);
+      const before = await program.account.gameAccount.fetch(gamePda);
+      const additional = new anchor.BN(0.01 * LAMPORTS_PER_SOL);
+
+      const toppedUp = awaitEventFor("betToppedUpEvent", player.publicKey);
+      await program.methods
+        .topUpBet(additional)
+        .accountsPartial({ player: player.publicKey })
+        .signers([player])
+        .rpc({ commitment: "confirmed" });
+      expect((await toppedUp).betAmount.eq(before.betAmount.add(additional))).to.be.true;
+
+      const game = await program.account.gameAccount.fetch(gamePda);
+      expect(game.betAmount.eq(before.betAmount.add(additional))).to.be.true;
+
+      // Once the next round is queued the bet is fixed
+      await queueNextRound(player);
+      await expectError(
+        program.methods
+          .topUpBet(additional)
+          .accountsPartial({ player: player.publicKey })
+          .signers([player])
+          .rpc({ commitment: "confirmed" }),
+        "TopUpAfterQueue"
+      );
+    });
+
+    it("Cashes out a compounded win instead of playing its next round", async function () {
+      const player = await newFundedPlayer();
+      const won = await playUntil(player, true, { autoCompound: true });
+      if (!won) {
+        this.skip();
+      }
+      const gamePda = getGamePDA(player.publicKey);
+      const { betAmount: stake } = await program.account.gameAccount.fetch(gamePda);
+      const before = await provider.connection.getBalance(player.publicKey);
+
+      const cashedOut = awaitEventFor("gameCashedOutEvent", player.publicKey);
+      await program.methods
+        .cashOut()
+        .accountsPartial({ player: player.publicKey })
+        .signers([player])
+        .rpc({ commitment: "confirmed" });
+      expect((await cashedOut).amount.eq(stake)).to.be.true;
+
+      const after = await provider.connection.getBalance(player.publicKey);
+      expect(after - before).to.be.greaterThan(stake.toNumber() - 10_000); // less the tx fee
+      const game = await program.account.gameAccount.fetch(gamePda);
+      expect(game.status).to.equal(2);
+      const stats = await program.account.playerStats.fetch(getPlayerStatsPDA(player.publicKey));
+      expect(stats.outstandingExposure.toNumber()).to.equal(0);
+    });
+
+    it("Escrows an auto_compound win the config would lock instead of compounding it", async function () {
+      const player = await newFundedPlayer();
+      await updateConfig({ winningsLockSlots: new anchor.BN(5) });
+      try {
+        const won = await playUntil(player, true, { autoCompound: true });
+        if (!won) {
+          this.skip();
+        }
+        const game = await program.account.gameAccount.fetch(getGamePDA(player.publicKey));
+        expect(game.status).to.equal(2);
+        const [claimPda] = PublicKey.findProgramAddressSync(
+          [Buffer.from("pending_claim"), player.publicKey.toBuffer()],
+          program.programId
+        );
+        const claim = await program.account.pendingClaim.fetch(claimPda);
+        expect(claim.amount.eq(won.result.payout)).to.be.true;
+      } finally {
+        await updateConfig({ winningsLockSlots: new anchor.BN(0) });
+      }
+    });
+
+    it("Pays out an auto_compound win whose next bet would pass max_bet", async function () {
+      const player = await newFundedPlayer();
+      await updateConfig({ maxBet: betAmount.muln(2).subn(1) });
+      try {
+        const won = await playUntil(player, true, { autoCompound: true });
+        if (!won) {
+          this.skip();
+        }
+        expect(won.result.payout.eq(betAmount.muln(2))).to.be.true;
+        const game = await program.account.gameAccount.fetch(getGamePDA(player.publicKey));
+        expect(game.status).to.equal(2);
+      } finally {
+        await updateConfig({ maxBet: new anchor.BN(0) });
+      }
+    });
+
+    it("Routes a kept losing bet across the hot and cold treasuries", async function () {
+      const player = await newFundedPlayer(3);
+      const treasury = getTreasuryPDA();
+      const [coldTreasury] = PublicKey.findProgramAddressSync([Buffer.from("cold_treasury")], program.programId);
+
+      await expectError(updateConfig({ lossRouteBps: [7_000, 2_000] }), "InvalidLossRouting");
+      await updateConfig({ lossRouteBps: [7_000, 3_000] });
+      try {
+        for (let attempt = 0; attempt < 6; attempt++) {
+          const hotBefore = await provider.connection.getBalance(treasury);
+          const coldBefore = await provider.connection.getBalance(coldTreasury);
+          const { result } = await playRound(player);
+          if (result.playerWon) {
+            continue;
+          }
+          expect((await provider.connection.getBalance(treasury)) - hotBefore).to.equal(betAmount.toNumber() * 0.7);
+          expect((await provider.connection.getBalance(coldTreasury)) - coldBefore).to.equal(
+            betAmount.toNumber() * 0.3
+          );
+          return;
+        }
+        this.skip();
+      } finally {
+        await updateConfig({ lossRouteBps: [0, 0] });
+      }
+    });
+
+    it("Mints the loyalty token on wins but not on losses", async function () {
+      const player = await newFundedPlayer(3);
+      const tokenProgram = new PublicKey("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+      const associatedTokenProgram = new PublicKey("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+      const [rewardAuthority] = PublicKey.findProgramAddressSync([Buffer.from("reward_authority")], program.programId);
+
+      // A mint whose authority is the program's PDA, and the player's token account for it
+      const mint = anchor.web3.Keypair.generate();
+      const [rewardAccount] = PublicKey.findProgramAddressSync(
+        [player.publicKey.toBuffer(), tokenProgram.toBuffer(), mint.publicKey.toBuffer()],
+        associatedTokenProgram
+      );
+      const setup = new anchor.web3.Transaction().add(
+        anchor.web3.SystemProgram.createAccount({
+          fromPubkey: player.publicKey,
+          newAccountPubkey: mint.publicKey,
+          lamports: await provider.connection.getMinimumBalanceForRentExemption(82),
+          space: 82,
+          programId: tokenProgram,
+        }),
+        // InitializeMint2: 0 decimals, no freeze authority
+        new TransactionInstruction({
+          programId: tokenProgram,
+          keys: [{ pubkey: mint.publicKey, isSigner: false, isWritable: true }],
+          data: Buffer.concat([Buffer.from([20, 0]), rewardAuthority.toBuffer(), Buffer.from([0])]),
+        }),
+        // CreateIdempotent
+        new TransactionInstruction({
+          programId: associatedTokenProgram,
+          keys: [
+            { pubkey: player.publicKey, isSigner: true, isWritable: true },
+            { pubkey: rewardAccount, isSigner: false, isWritable: true },
+            { pubkey: player.publicKey, isSigner: false, isWritable: false },
+            { pubkey: mint.publicKey, isSigner: false, isWritable: false },
+            { pubkey: anchor.web3.SystemProgram.programId, isSigner: false, isWritable: false },
+            { pubkey: tokenProgram, isSigner: false, isWritable: false },
+          ],
+          data: Buffer.from([1]),
+        })
+      );
+      await provider.sendAndConfirm(setup, [player, mint], { commitment: "confirmed" });
+      const rewardBalance = async () =>
+        (await provider.connection.getTokenAccountBalance(rewardAccount, "confirmed")).value.amount;
+
+      await updateConfig({ rewardMint: mint.publicKey, rewardAmount: new anchor.BN(5) });
+      try {
+        const minted = awaitEventFor("rewardMintedEvent", player.publicKey);
+        if (!(await playUntil(player, true))) {
+          this.skip();
+        }
+        const event = await minted;
+        expect(event.mint.equals(mint.publicKey)).to.be.true;
+        expect(event.amount.toNumber()).to.equal(5);
+        expect(await rewardBalance()).to.equal("5");
+
+        if (!(await playUntil(player, false))) {
+          this.skip();
+        }
+        expect(await rewardBalance()).to.equal("5");
+      } finally {
+        await updateConfig({ rewardMint: PublicKey.default, rewardAmount: new anchor.BN(0) });
+      }
+    });
+
+    it("Serves a player's game, stats and config in one get_full_state call", async () => {
+      const player = await newFundedPlayer();
+      const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("config")], program.programId);
+      const [statsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("player_stats"), player.publicKey.toBuffer()],
+        program.programId
+      );
+
+      // Before the first game there is only the config
+      let view = await program.methods
+        .getFullState()
+        .accountsPartial({ player: player.publicKey, gameAccount: null, playerStats: null })
+        .view();
+      expect(view.game).to.be.null;
+      expect(view.stats).to.be.null;
+
+      await playRound(player);
+      view = await program.methods
+        .getFullState()
+        .accountsPartial({ player: player.publicKey, gameAccount: getGamePDA(player.publicKey), playerStats: statsPda })
+        .view();
+      const [game, stats, config] = await Promise.all([
+        program.account.gameAccount.fetch(getGamePDA(player.publicKey)),
+        program.account.playerStats.fetch(statsPda),
+        program.account.gameConfig.fetch(configPda),
+      ]);
+      expect(JSON.stringify(view.game)).to.equal(JSON.stringify(game));
+      expect(view.stats.totalWagered.eq(stats.totalWagered)).to.be.true;
+      expect(view.stats.gamesPlayed.eq(stats.gamesPlayed)).to.be.true;
+      expect(JSON.stringify(view.config)).to.equal(JSON.stringify(config));
+      expect(game.status).to.equal(2);
+    });
+
+    it("Refunds wins and settles losses while payouts are frozen", async function () {
+      const player = await newFundedPlayer(3);
+      const gamePda = getGamePDA(player.publicKey);
+      await setPayoutsFrozen(true);
+      try {
+        // A won game ends Refunded (5) with its bet back, a lost one Completed (2)
+        const seen = new Set<number>();
+        for (let attempt = 0; attempt < 8 && seen.size < 2; attempt++) {
+          const { computationOffset } = await queueChestGame(player, { numChests: 2, betAmount });
+          await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+          seen.add((await program.account.gameAccount.fetch(gamePda)).status);
+        }
+        if (seen.size < 2) {
+          this.skip();
+        }
+        expect([...seen].sort()).to.deep.equal([2, 5]);
+      } finally {
+        await setPayoutsFrozen(false);
+      }
+    });
+
+    it("Pays the first-game bonus on a player's first game only", async function () {
+      const fair = betAmount.toNumber() * 2;
+      await updateConfig({ firstGameBonusBps: 500 });
+      try {
+        // Only a player whose very first game is won sees the bonus
+        for (let attempt = 0; attempt < 6; attempt++) {
+          const player = await newFundedPlayer(3);
+          const { result } = await playRound(player);
+          if (!result.playerWon) {
+            continue;
+          }
+          expect(result.payout.toNumber()).to.equal((fair * 10500) / 10000);
+
+          const second = await playUntil(player, true);
+          if (!second) {
+            this.skip();
+          }
+          expect(second.result.payout.toNumber()).to.equal(fair);
+          return;
+        }
+        this.skip();
+      } finally {
+        await updateConfig({ firstGameBonusBps: 0 });
+      }
+    });
+
+    it("Routes a play-time tip to the tip vault", async () => {
+      const player = await newFundedPlayer();
+      const tip = new anchor.BN(0.01 * LAMPORTS_PER_SOL);
+      const [tipVault] = PublicKey.findProgramAddressSync([Buffer.from("tip_vault")], program.programId);
+      const before = (await provider.connection.getAccountInfo(tipVault))?.lamports ?? 0;
+
+      const started = awaitEventFor("gameStartedEvent", player.publicKey);
+      await queueChestGame(player, { numChests: 2, tip, skipPreflight: false });
+      expect((await started).tip.eq(tip)).to.be.true;
+
+      // The first tip also pays the vault's rent, so only count it once the vault exists
+      const after = await provider.connection.getBalance(tipVault);
+      const rent = before === 0 ? await provider.connection.getMinimumBalanceForRentExemption(9) : 0;
+      expect(after - before - rent).to.equal(tip.toNumber());
+    });
+
+    it("Sizes a percentage bet from the player's balance", async () => {
+      const player = await newFundedPlayer(2);
+      const rent = await provider.connection.getMinimumBalanceForRentExemption(0);
+      const spendable = 2 * LAMPORTS_PER_SOL - rent;
+
+      const started = awaitEventFor("gameStartedEvent", player.publicKey);
+      await queueChestGame(player, { numChests: 2, betAmount: new anchor.BN(500), betIsBps: true });
+      // New game accounts are paid for before the bet is sized, so it lands a little under 5%
+      const bet = (await started).betAmount.toNumber();
+      expect(bet).to.be.at.most(Math.floor(spendable / 20));
+      expect(bet).to.be.above(Math.floor(spendable / 20) - 0.01 * LAMPORTS_PER_SOL);
+
+      // The whole balance can't be bet once the game accounts have taken their rent
+      const broke = await newFundedPlayer(0.011);
+      await expectError(
+        queueChestGame(broke, { numChests: 2, betAmount: new anchor.BN(10_000), betIsBps: true, skipPreflight: false }),
+        "InsufficientFunds"
+      );
+    });
+
+    it("Reports each chest count's win probability when a game starts", async () => {
+      const player = await newFundedPlayer(2);
+      const bet = new anchor.BN(0.01 * LAMPORTS_PER_SOL);
+      for (const [numChests, bps] of [[2, 5000], [3, 3333], [4, 2500], [5, 2000]]) {
+        const started = awaitEventFor("gameStartedEvent", player.publicKey);
+        const { computationOffset } = await queueChestGame(player, { numChests, betAmount: bet });
+        expect((await started).winProbabilityBps).to.equal(bps);
+        await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+      }
+    });
+
+    it("Pushes a win once its chest count hits the window's win cap", async function () {
+      // Room for one 2-chest win per window, 3 chests uncapped
+      const caps = new Array(9).fill(new anchor.BN(0));
+      caps[2] = betAmount;
+      await updateConfig({ winCapByChests: caps, windowSecs: new anchor.BN(600) });
+      try {
+        const player = await newFundedPlayer(3);
+        if (!(await playUntil(player, true))) {
+          this.skip();
+        }
+
+        const tripped = awaitEventFor("circuitBreakerTrippedEvent", player.publicKey);
+        const pushed = await playUntil(player, true);
+        if (!pushed) {
+          this.skip();
+        }
+        expect((await tripped).blockedPayout.toNumber()).to.equal(betAmount.toNumber() * 2);
+        expect(pushed.result.pushed).to.be.true;
+
+        const won = await playUntil(player, true, { numChests: 3 });
+        if (!won) {
+          this.skip();
+        }
+        expect(won.result.playerWon).to.be.true;
+
+        const treasury = await program.account.treasury.fetch(getTreasuryPDA());
+        expect(treasury.windowWinsByChests[2].eq(betAmount)).to.be.true;
+      } finally {
+        await updateConfig({ winCapByChests: new Array(9).fill(new anchor.BN(0)), windowSecs: new anchor.BN(0) });
+      }
+    });
+
+    it("Pays a near-miss win on a neighbouring chest at the reduced multiplier", async function () {
+      const player = await newFundedPlayer(3);
+      const numChests = 5;
+      const bet = new anchor.BN(0.03 * LAMPORTS_PER_SOL);
+      // Picking chest 0 wins on chests 4, 0 and 1
+      const won = await playUntil(player, true, { numChests, betAmount: bet, winMode: 1 });
+      if (!won) {
+        this.skip();
+      }
+      expect([4, 0, 1]).to.include(won.result.winningChest);
+      // Three of five chests win, so the fair multiplier is 5/3
+      expect(won.result.payout.toNumber()).to.equal(Math.floor((bet.toNumber() * numChests) / 3));
+    });
+
+    it("Keeps exact mode paying only the drawn chest", async function () {
+      const player = await newFundedPlayer(3);
+      const numChests = 5;
+      // Play until a neighbour of chest 0 is drawn, which near-miss mode would have paid
+      for (let attempt = 0; attempt < 6; attempt++) {
+        const { result } = await playRound(player, { numChests });
+        expect(result.playerWon).to.equal(result.winningChest === 0);
+        if (result.winningChest === 1 || result.winningChest === 4) {
+          expect(result.payout.toNumber()).to.equal(0);
+          return;
+        }
+      }
+      this.skip();
+    });
+
+    it("Rejects near-miss mode on too few chests", async () => {
+      const player = await newFundedPlayer();
+      await expectError(
+        queueChestGame(player, { numChests: 3, winMode: 1, skipPreflight: false }),
+        "InvalidWinMode"
+      );
+    });
+
+    it("Rebates the entry fee once a player crosses a loyalty tier", async () => {
+      const entryFee = new anchor.BN(0.01 * LAMPORTS_PER_SOL);
+      const zero = new anchor.BN(0);
+      await updateConfig({
+        entryFee,
+        tierThresholds: [betAmount, zero, zero],
+        tierRebateBps: [5_000, 0, 0],
+      });
+      try {
+        const player = await newFundedPlayer();
+        // Queueing sends the bet to the game account, so the treasury moves by the fee only
+        const playAndMeasureFee = async () => {
+          const before = await provider.connection.getBalance(getTreasuryPDA());
+          const { computationOffset } = await queueChestGame(player, { betAmount });
+          const fee = (await provider.connection.getBalance(getTreasuryPDA())) - before;
+          await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+          return fee;
+        };
+
+        expect(await playAndMeasureFee()).to.equal(entryFee.toNumber());
+        // The first bet reached the tier, so the next game pays half the fee
+        expect(await playAndMeasureFee()).to.equal(entryFee.toNumber() / 2);
+
+        const [statsPda] = PublicKey.findProgramAddressSync(
+          [Buffer.from("player_stats"), player.publicKey.toBuffer()],
+          program.programId
+        );
+        const stats = await program.account.playerStats.fetch(statsPda);
+        expect(stats.totalWagered.eq(betAmount.muln(2))).to.be.true;
+      } finally {
+        await updateConfig({ entryFee: zero, tierThresholds: [zero, zero, zero], tierRebateBps: [0, 0, 0] });
+      }
+    });
+
+    it("Reports the running net P&L in each result", async () => {
+      const player = await newFundedPlayer(2);
+      let expectedPnl = 0;
+      for (let i = 0; i < 3; i++) {
+        const { result } = await playRound(player);
+        expectedPnl += result.payout.toNumber() - betAmount.toNumber();
+        expect(result.playerNetPnl.toNumber()).to.equal(expectedPnl);
+      }
+    });
+
+    it("Reports a game as cancellable only when cancel_game would accept it", async function () {
+      const player = await newFundedPlayer();
+      const queued = await queueChestGame(player);
+      const computationAccount = getComputationAccAddress(arciumEnv.arciumClusterOffset, queued.computationOffset);
+      const isCancellable = (withProof: boolean) =>
+        program.methods
+          .isCancellable()
+          .accountsPartial({
+            player: player.publicKey,
+            mxeAccount: withProof ? getMXEAccAddress(program.programId) : null,
+            computationAccount: withProof ? computationAccount : null,
+          })
+          .view();
+
+      expect(await isCancellable(true)).to.be.false;
+      await new Promise((resolve) => setTimeout(resolve, 61_000));
+      // If the cluster settled the game meanwhile there is nothing left to cancel
+      const game = await program.account.gameAccount.fetch(getGamePDA(player.publicKey));
+      if (game.status !== 1) {
+        this.skip();
+      }
+      // Past settle_by but short of the hard timeout, cancel_game wants proof the computation left the queue
+      expect(await isCancellable(false)).to.be.false;
+      await expectError(
+        program.methods
+          .cancelGame(false)
+          .accountsPartial({ player: player.publicKey, gameAccount: getGamePDA(player.publicKey) })
+          .rpc({ commitment: "confirmed" }),
+        "ComputationStillQueued"
+      );
+      const info = await provider.connection.getAccountInfo(computationAccount);
+      const leftQueue = !info || info.data[110] !== 0;
+      expect(await isCancellable(true)).to.equal(leftQueue);
+    });
+
+    it("Raises and resolves disputes on stuck games", async function () {
+      const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+      const [refunded, forfeited] = await Promise.all([newFundedPlayer(), newFundedPlayer()]);
+      const bets = [];
+      const computations = [];
+      for (const player of [refunded, forfeited]) {
+        const queued = await queueChestGame(player);
+        bets.push(queued.betAmount);
+        computations.push(getComputationAccAddress(arciumEnv.arciumClusterOffset, queued.computationOffset));
+      }
+      const flagDispute = (player: anchor.web3.Keypair, reasonCode: number) =>
+        program.methods
+          .flagDispute(reasonCode)
+          .accountsPartial({ player: player.publicKey })
+          .signers([player])
+          .rpc({ commitment: "confirmed" });
+      const resolveDispute = (authority: anchor.web3.Keypair, player: PublicKey, refund: boolean) =>
+        program.methods
+          .resolveDispute(refund)
+          .accountsPartial({
+            authority: authority.publicKey,
+            player,
+            mxeAccount: getMXEAccAddress(program.programId),
+            computationAccount: computations[player.equals(refunded.publicKey) ? 0 : 1],
+          })
+          .signers([authority])
+          .rpc({ commitment: "confirmed" });
+
+      await expectError(flagDispute(refunded, 1), "GameNotTimedOut");
+      await new Promise((resolve) => setTimeout(resolve, 61_000));
+      // Only games the cluster never settled are stuck, and only once their
+      // computations have left the queue can they be resolved
+      for (const [i, player] of [refunded, forfeited].entries()) {
+        const info = await provider.connection.getAccountInfo(computations[i]);
+        if (
+          (await program.account.gameAccount.fetch(getGamePDA(player.publicKey))).status !== 1 ||
+          (info && info.data[110] === 0)
+        ) {
+          this.skip();
+        }
+      }
+
+      const raisedPromise = awaitEventFor("disputeRaisedEvent", refunded.publicKey);
+      await flagDispute(refunded, 1);
+      expect((await raisedPromise).reasonCode).to.equal(1);
+      await flagDispute(forfeited, 2);
+      const disputed = await program.account.gameAccount.fetch(getGamePDA(refunded.publicKey));
+      expect(disputed.disputed).to.be.true;
+      expect(disputed.disputeReason).to.equal(1);
+      await expectError(flagDispute(refunded, 3), "GameAlreadyDisputed");
+
+      // Only the config authority resolves, either way
+      await expectError(resolveDispute(refunded, refunded.publicKey, true), "Unauthorized");
+
+      const playerBefore = await provider.connection.getBalance(refunded.publicKey);
+      const resolvedPromise = awaitEventFor("disputeResolvedEvent", refunded.publicKey);
+      await resolveDispute(owner, refunded.publicKey, true);
+      const resolved = await resolvedPromise;
+      expect(resolved.refunded).to.be.true;
+      expect(resolved.betAmount.eq(bets[0])).to.be.true;
+      expect((await provider.connection.getBalance(refunded.publicKey)) - playerBefore).to.equal(
+        bets[0].toNumber()
+      );
+      const cancelled = await program.account.gameAccount.fetch(getGamePDA(refunded.publicKey));
+      expect(cancelled.status).to.equal(3);
+      expect(cancelled.disputed).to.be.false;
+
+      const treasuryBefore = await provider.connection.getBalance(getTreasuryPDA());
+      await resolveDispute(owner, forfeited.publicKey, false);
+      expect((await provider.connection.getBalance(getTreasuryPDA())) - treasuryBefore).to.equal(
+        bets[1].toNumber()
+      );
+      expect((await program.account.gameAccount.fetch(getGamePDA(forfeited.publicKey))).status).to.equal(2);
+      await expectError(resolveDispute(owner, forfeited.publicKey, true), "GameNotDisputed");
+    });
+  });
+
+  describe("Payout rounding", () => {
+    // Best of 3 with 3 chests pays 27/7x: 0.05 SOL * 27 / 7 = 192_857_142.857 lamports
+    const numChests = 3;
+    const roundsTotal = 3;
+
+    async function expectWinningPayout(this: Mocha.Context, roundInFavorOfPlayer: boolean, expected: number) {
+      await updateConfig({ roundInFavorOfPlayer });
+      try {
+        for (let attempt = 0; attempt < 5; attempt++) {
+          const { result } = await playSeries(numChests, roundsTotal);
+          if (result.playerWon) {
+            expect(result.payout.toNumber()).to.equal(expected);
+            return;
+          }
+        }
+      } finally {
+        await updateConfig({ roundInFavorOfPlayer: false });
+      }
+      this.skip();
+    }
+
+    it("Rounds fractional lamports down by default", async function () {
+      await expectWinningPayout.call(this, false, 192_857_142);
+    });
+
+    it("Rounds fractional lamports up in the player's favor", async function () {
+      await expectWinningPayout.call(this, true, 192_857_143);
+    });
+  });
+
+  describe("Promo codes", () => {
+    const promoSigner = anchor.web3.Keypair.generate();
+    const codeId = Array.from(randomBytes(16));
+
+    before(async () => {
+      await updateConfig({ promoPubkey: promoSigner.publicKey });
+    });
+
+    after(async () => {
+      await updateConfig({ promoPubkey: PublicKey.default });
+    });
+
+    it("Applies a validly signed promo code", async () => {
+      const player = await newFundedPlayer();
+      const promo = signPromo(promoSigner, codeId, 1000, player.publicKey);
+
+      await queueChestGame(player, { promo });
+
+      const game = await program.account.gameAccount.fetch(getGamePDA(player.publicKey));
+      expect(game.promoBonusBps).to.equal(1000);
+      const redemption = await program.account.promoRedemption.fetch(getPromoPDA(codeId));
+      expect(redemption.player.toBase58()).to.equal(player.publicKey.toBase58());
+    });
+
+    it("Rejects a promo code signed by the wrong key", async () => {
+      const player = await newFundedPlayer();
+      const promo = signPromo(
+        anchor.web3.Keypair.generate(),
+        Array.from(randomBytes(16)),
+        1000,
+        player.publicKey
+      );
+
+      await expectError(
+        queueChestGame(player, { promo, skipPreflight: false }),
+        "InvalidPromoSignature"
+      );
+    });
+
+    it("Rejects redeeming the same promo code twice", async () => {
+      const player = await newFundedPlayer();
+      const promo = signPromo(promoSigner, codeId, 1000, player.publicKey);
+
+      await expectError(
+        queueChestGame(player, { promo, skipPreflight: false }),
+        "PromoAlreadyRedeemed"
+      );
+    });
+
+    it("Rejects a promo code issued to another player", async () => {
+      const player = await newFundedPlayer();
+      const promo = signPromo(promoSigner, Array.from(randomBytes(16)), 1000, PublicKey.unique());
+
+      await expectError(
+        queueChestGame(player, { promo, skipPreflight: false }),
+        "PromoWrongPlayer"
+      );
+    });
+
+    it("Rejects an expired promo code", async () => {
+      const player = await newFundedPlayer();
+      const expiresAt = Math.floor(Date.now() / 1000) - 60;
+      const promo = signPromo(promoSigner, Array.from(randomBytes(16)), 1000, player.publicKey, expiresAt);
+
+      await expectError(
+        queueChestGame(player, { promo, skipPreflight: false }),
+        "PromoExpired"
+      );
+    });
+  });
+
+  describe("Pot games", () => {
+    // Every player encrypts their own choice under their own key, and all of them sign
+    async function queuePotGame(
+      players: anchor.web3.Keypair[],
+      choices: number[],
+      numChests: number,
+      betAmount: anchor.BN,
+      skipPreflight = true
+    ) {
+      const mxePublicKey = await getMXEPublicKeyWithRetry(provider, program.programId);
+      const entries = choices.map((choice) => {
+        const privateKey = x25519.utils.randomSecretKey();
+        const cipher = new RescueCipher(x25519.getSharedSecret(privateKey, mxePublicKey));
+        const nonce = randomBytes(16);
+        return {
+          choice: Array.from(cipher.encrypt([BigInt(choice)], nonce)[0]),
+          pubKey: Array.from(x25519.getPublicKey(privateKey)),
+          nonce: new anchor.BN(deserializeLE(nonce).toString()),
+        };
+      });
+      const computationOffset = new anchor.BN(randomBytes(8), "hex");
+      const playerAccess = (player: PublicKey) =>
+        PublicKey.findProgramAddressSync([Buffer.from("player_access"), player.toBuffer()], program.programId)[0];
+
+      await program.methods
+        .playPotGame(computationOffset, numChests, betAmount, entries as any)
+        .accountsPartial({
+          player0: players[0].publicKey,
+          player1: players[1].publicKey,
+          player2: players[2]?.publicKey ?? null,
+          playerAccess2: players[2] ? playerAccess(players[2].publicKey) : null,
+          computationAccount: getComputationAccAddress(arciumEnv.arciumClusterOffset, computationOffset),
+          clusterAccount: clusterAccount,
+          mxeAccount: getMXEAccAddress(program.programId),
+          mempoolAccount: getMempoolAccAddress(arciumEnv.arciumClusterOffset),
+          executingPool: getExecutingPoolAccAddress(arciumEnv.arciumClusterOffset),
+          compDefAccount: getCompDefAccAddress(
+            program.programId,
+            Buffer.from(getCompDefAccOffset("play_pot_game")).readUInt32LE()
+          ),
+        })
+        .signers(players)
+        .rpc({ skipPreflight, commitment: "confirmed" });
+
+      return computationOffset;
+    }
+
+    async function playPot(choices: number[], numChests: number) {
+      const players = await Promise.all(choices.map(() => newFundedPlayer()));
+      const betAmount = new anchor.BN(0.1 * LAMPORTS_PER_SOL);
+      const before = await Promise.all(players.map((p) => provider.connection.getBalance(p.publicKey)));
+      const resultPromise = awaitEvent("potGameResultEvent");
+      const computationOffset = await queuePotGame(players, choices, numChests, betAmount);
+      const result = await resultPromise;
+      await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+      const after = await Promise.all(players.map((p) => provider.connection.getBalance(p.publicKey)));
+      return { players, betAmount, result, before, after };
+    }
+
+    it("Pays the whole pot to the one player of two who picked the winning chest", async () => {
+      // Different chests out of two: exactly one player wins
+      const { betAmount, result, before, after } = await playPot([0, 1], 2);
+      expect(result.sampled).to.be.true;
+      const winner = result.winningChest;
+      expect(result.winners.slice(0, 2)).to.deep.equal([winner === 0, winner === 1]);
+      expect(result.payouts[winner].toNumber()).to.equal(betAmount.toNumber() * 2);
+      expect(result.payouts[1 - winner].toNumber()).to.equal(0);
+      // The loser is down their bet plus fees, the winner up by about one bet
+      expect(after[1 - winner]).to.be.below(before[1 - winner] - betAmount.toNumber());
+      expect(after[winner]).to.be.above(before[winner]);
+    });
+
+    it("Splits the pot between the winners of a three-player game", async () => {
+      // Players 0 and 1 share a chest, player 2 takes the other
+      const { betAmount, result } = await playPot([0, 0, 1], 2);
+      expect(result.sampled).to.be.true;
+      const pot = betAmount.toNumber() * 3;
+      const paid = result.payouts.map((p: anchor.BN) => p.toNumber());
+      expect(paid.reduce((a: number, b: number) => a + b, 0)).to.equal(pot);
+      if (result.winningChest === 0) {
+        expect(result.winners).to.deep.equal([true, true, false]);
+        expect(paid[0] + paid[1]).to.equal(pot);
+        expect(paid[2]).to.equal(0);
+      } else {
+        expect(result.winners).to.deep.equal([false, false, true]);
+        expect(paid).to.deep.equal([0, 0, pot]);
+      }
+    });
+
+    it("Rejects a pot game with one entry per two players", async () => {
+      const players = [await newFundedPlayer(), await newFundedPlayer()];
+      await expectError(
+        queuePotGame(players, [0], 2, new anchor.BN(0.1 * LAMPORTS_PER_SOL), false),
+        "InvalidPotPlayers"
+      );
+    });
+  });
+
+  describe("Player access", () => {
+    const owner = () => readKpJson(`${os.homedir()}/.config/solana/id.json`);
+
+    function setPlayerBlocked(player: PublicKey, blocked: boolean) {
+      return program.methods
+        .setPlayerBlocked(player, blocked)
+        .accountsPartial({ authority: owner().publicKey })
+        .signers([owner()])
+        .rpc({ commitment: "confirmed" });
+    }
+
+    function setPlayerAllowed(player: PublicKey, allowed: boolean) {
+      return program.methods
+        .setPlayerAllowed(player, allowed)
+        .accountsPartial({ authority: owner().publicKey })
+        .signers([owner()])
+        .rpc({ commitment: "confirmed" });
+    }
+
+    it("Blocks and unblocks a player", async () => {
+      const player = await newFundedPlayer();
+      await setPlayerBlocked(player.publicKey, true);
+      await expectError(queueChestGame(player, { skipPreflight: false }), "PlayerBlocked");
+
+      await setPlayerBlocked(player.publicKey, false);
+      const resultPromise = awaitEventFor("gameResultEvent", player.publicKey);
+      const { computationOffset } = await queueChestGame(player);
+      await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+      await resultPromise;
+    });
+
+    it("Still settles a pending game after its player is blocked", async () => {
+      const player = await newFundedPlayer();
+      const resultPromise = awaitEventFor("gameResultEvent", player.publicKey);
+      const { computationOffset } = await queueChestGame(player);
+      await setPlayerBlocked(player.publicKey, true);
+      try {
+        await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+        await resultPromise;
+        expect((await program.account.gameAccount.fetch(getGamePDA(player.publicKey))).status).to.equal(2);
+      } finally {
+        await setPlayerBlocked(player.publicKey, false);
+      }
+    });
+
+    it("Only lets allowlisted players in allowlist-only mode", async () => {
+      const [allowed, stranger] = await Promise.all([newFundedPlayer(), newFundedPlayer()]);
+      await setPlayerAllowed(allowed.publicKey, true);
+      await updateConfig({ allowlistOnly: true });
+      try {
+        await expectError(queueChestGame(stranger, { skipPreflight: false }), "PlayerNotAllowlisted");
+
+        const resultPromise = awaitEventFor("gameResultEvent", allowed.publicKey);
+        const { computationOffset } = await queueChestGame(allowed);
+        await awaitComputationFinalization(provider, computationOffset, program.programId, "confirmed");
+        await resultPromise;
+      } finally {
+        await updateConfig({ allowlistOnly: false });
+      }
+      const access = await program.account.playerAccess.fetch(
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("player_access"), allowed.publicKey.toBuffer()],
+          program.programId
+        )[0]
+      );
+      expect(access.allowed).to.be.true;
+      expect(access.blocked).to.be.false;
+    });
+  });
+
+  describe("Treasury staking", () => {
+    const owner = () => readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    const stakePool = PublicKey.findProgramAddressSync([Buffer.from("stake_pool")], program.programId)[0];
+
+    function stake(staker: anchor.web3.Keypair, amount: number) {
+      return program.methods
+        .stake(new anchor.BN(amount))
+        .accountsPartial({ staker: staker.publicKey })
+        .signers([staker])
+        .rpc({ commitment: "confirmed" });
+    }
+
+    function distributeProfits(authority: anchor.web3.Keypair, amount: number) {
+      return program.methods
+        .distributeProfits(new anchor.BN(amount))
+        .accountsPartial({ authority: authority.publicKey })
+        .signers([authority])
+        .rpc({ commitment: "confirmed" });
+    }
+
+    async function unstake(staker: anchor.web3.Keypair, amount: number) {
+      const unstakedPromise = awaitEvent("unstakedEvent");
+      await program.methods
+        .unstake(new anchor.BN(amount))
+        .accountsPartial({ staker: staker.publicKey })
+        .signers([staker])
+        .rpc({ commitment: "confirmed" });
+      return unstakedPromise;
+    }
+
+    it("Splits distributed profits pro rata and returns stakes on unstake", async () => {
+      const [small, large] = await Promise.all([newFundedPlayer(), newFundedPlayer(4)]);
+      const treasuryBefore = await provider.connection.getBalance(getTreasuryPDA());
+      await stake(small, LAMPORTS_PER_SOL);
+      await stake(large, 3 * LAMPORTS_PER_SOL);
+      expect((await provider.connection.getBalance(getTreasuryPDA())) - treasuryBefore).to.equal(
+        4 * LAMPORTS_PER_SOL
+      );
+      const pool = await program.account.stakePool.fetch(stakePool);
+      expect(pool.totalStaked.toNumber()).to.equal(4 * LAMPORTS_PER_SOL);
+
+      await expectError(distributeProfits(small, 1_000), "Unauthorized");
+      await distributeProfits(owner(), 0.4 * LAMPORTS_PER_SOL);
+
+      await expectError(unstake(small, 2 * LAMPORTS_PER_SOL), "InvalidStakeAmount");
+      const smallExit = await unstake(small, LAMPORTS_PER_SOL);
+      expect(smallExit.amount.toNumber()).to.equal(LAMPORTS_PER_SOL);
+      expect(smallExit.profit.toNumber()).to.equal(0.1 * LAMPORTS_PER_SOL);
+
+      // Claiming without unstaking leaves the stake in place
+      const largeClaim = await unstake(large, 0);
+      expect(largeClaim.profit.toNumber()).to.equal(0.3 * LAMPORTS_PER_SOL);
+      expect(largeClaim.totalStaked.toNumber()).to.equal(3 * LAMPORTS_PER_SOL);
+      const largeExit = await unstake(large, 3 * LAMPORTS_PER_SOL);
+      expect(largeExit.profit.toNumber()).to.equal(0);
+      expect(largeExit.totalStaked.toNumber()).to.equal(0);
+    });
+
+    it("Rejects a distribution with nobody staked", async () => {
+      await expectError(distributeProfits(owner(), 1_000), "NoStakers");
+    });
+  });
+
+  it("Rejects keeper cancellation when auto-cancel is off", async () => {
+    const player = await newFundedPlayer();
+    const keeper = await newFundedPlayer(1);
+
+    await queueChestGame(player, { autoCancel: false });
+
+    await expectError(
+      program.methods
+        .cancelExpiredGame()
+        .accountsPartial({
+          keeper: keeper.publicKey,
+          player: player.publicKey,
+          gameAccount: getGamePDA(player.publicKey),
+        })
+        .signers([keeper])
+        .rpc({ commitment: "confirmed" }),
+      "AutoCancelNotEnabled"
+    );
+  });
+
+  it("Leaves a current game account alone and only lets its player migrate it", async () => {
+    const player = await newFundedPlayer();
+    const stranger = await newFundedPlayer(1);
+    const gamePda = getGamePDA(player.publicKey);
+    await queueChestGame(player);
+    const before = await provider.connection.getAccountInfo(gamePda);
+
+    await expectError(
+      program.methods
+        .migrateGameAccount()
+        .accountsPartial({ signer: stranger.publicKey, gameAccount: gamePda })
+        .signers([stranger])
+        .rpc({ commitment: "confirmed" }),
+      "Unauthorized"
+    );
+
+    await program.methods
+      .migrateGameAccount()
+      .accountsPartial({ signer: player.publicKey, gameAccount: gamePda })
+      .signers([player])
+      .rpc({ commitment: "confirmed" });
+    const after = await provider.connection.getAccountInfo(gamePda);
+    expect(after.data.length).to.equal(before.data.length);
+  });
+
+  it("Stores the settlement deadline from the config timeout", async () => {
+    const player = await newFundedPlayer();
+    const gamePda = getGamePDA(player.publicKey);
+
+    // Defaults to the 60s timeout
+    await queueChestGame(player);
+    let game = await program.account.gameAccount.fetch(gamePda);
+    expect(game.settleBy.sub(game.createdAt).toNumber()).to.equal(60);
+
+    await updateConfig({ gameTimeoutSecs: new anchor.BN(300) });
+    try {
+      const other = await newFundedPlayer();
+      await queueChestGame(other);
+      game = await program.account.gameAccount.fetch(getGamePDA(other.publicKey));
+      expect(game.settleBy.sub(game.createdAt).toNumber()).to.equal(300);
+    } finally {
+      await updateConfig({ gameTimeoutSecs: new anchor.BN(0) });
+    }
+  });
+
+  it("Lets a keeper cancel another player's expired auto-cancel game", async function () {
+    const player = await newFundedPlayer();
+    const keeper = await newFundedPlayer(1);
+    const gamePda = getGamePDA(player.publicKey);
+
+    const { betAmount } = await queueChestGame(player, { autoCancel: true });
+
+    // Wait out the 60s timeout; if the MPC settled in the meantime there is nothing to cancel
+    await new Promise((resolve) => setTimeout(resolve, 61_000));
+    const game = await program.account.gameAccount.fetch(gamePda);
+    if (game.status !== 1) {
+      this.skip();
+    }
+
+    const balanceBefore = await provider.connection.getBalance(player.publicKey);
+    await program.methods
+      .cancelExpiredGame()
+      .accountsPartial({
+        keeper: keeper.publicKey,
+        player: player.publicKey,
+        gameAccount: gamePda,
+      })
+      .signers([keeper])
+      .rpc({ commitment: "confirmed" });
+
+    const balanceAfter = await provider.connection.getBalance(player.publicKey);
+    expect(balanceAfter - balanceBefore).to.equal(betAmount.toNumber());
+    const cancelled = await program.account.gameAccount.fetch(gamePda);
+    expect(cancelled.status).to.equal(3);
+  });
+
+  it("Counts cancelled games in the house stats", async function () {
+    const player = await newFundedPlayer();
+    const keeper = await newFundedPlayer(1);
+    const gamePda = getGamePDA(player.publicKey);
+    const before = await program.account.houseStats.fetch(getHouseStatsPDA());
+
+    const { computationOffset, betAmount } = await queueChestGame(player, { autoCancel: true });
+
+    await new Promise((resolve) => setTimeout(resolve, 61_000));
+    const game = await program.account.gameAccount.fetch(gamePda);
+    if (game.status !== 1) {
+      this.skip();
+    }
+
+    const cancelledPromise = awaitEventFor("gameCancelledEvent", player.publicKey);
+    await program.methods
+      .cancelExpiredGame()
+      .accountsPartial({
+        keeper: keeper.publicKey,
+        player: player.publicKey,
+        gameAccount: gamePda,
+      })
+      .signers([keeper])
+      .rpc({ commitment: "confirmed" });
+
+    const event = await cancelledPromise;
+    expect(event.betAmount.eq(betAmount)).to.be.true;
+    expect(event.computationOffset.eq(computationOffset)).to.be.true;
+    expect(event.createdAt.eq(game.createdAt)).to.be.true;
+
+    const after = await program.account.houseStats.fetch(getHouseStatsPDA());
+    expect(after.totalCancelled.sub(before.totalCancelled).toNumber()).to.equal(1);
+    expect(after.totalGames.eq(before.totalGames)).to.be.true;
+  });
+
+  it("Cancels a batch of expired games and skips ineligible ones", async function () {
+    const keeper = await newFundedPlayer(1);
+    const expired = await Promise.all([newFundedPlayer(), newFundedPlayer(), newFundedPlayer()]);
+    const ineligible = await newFundedPlayer();
+
+    const bets = [];
+    for (const player of expired) {
+      bets.push((await queueChestGame(player, { autoCancel: true })).betAmount);
+    }
+    await queueChestGame(ineligible, { autoCancel: false });
+
+    await new Promise((resolve) => setTimeout(resolve, 61_000));
+    for (const player of expired) {
+      const game = await program.account.gameAccount.fetch(getGamePDA(player.publicKey));
+      if (game.status !== 1) {
+        this.skip();
+      }
+    }
+
+    const players = [...expired, ineligible];
+    const balancesBefore = await Promise.all(
+      players.map((p) => provider.connection.getBalance(p.publicKey))
+    );
+    const statsBefore = await program.account.playerStats.fetchMultiple(
+      expired.map((p) => getPlayerStatsPDA(p.publicKey))
+    );
+    await program.methods
+      .cancelExpiredGamesBatch(players.map((p) => p.publicKey))
+      .accountsPartial({ keeper: keeper.publicKey })
+      .remainingAccounts(
+        players.flatMap((p) => [
+          { pubkey: getGamePDA(p.publicKey), isSigner: false, isWritable: true },
+          { pubkey: p.publicKey, isSigner: false, isWritable: true },
+          { pubkey: getPlayerStatsPDA(p.publicKey), isSigner: false, isWritable: true },
+        ])
+      )
+      .signers([keeper])
+      .rpc({ commitment: "confirmed" });
+
+    for (let i = 0; i < expired.length; i++) {
+      const game = await program.account.gameAccount.fetch(getGamePDA(expired[i].publicKey));
+      expect(game.status).to.equal(3);
+      const balanceAfter = await provider.connection.getBalance(expired[i].publicKey);
+      expect(balanceAfter - balancesBefore[i]).to.equal(bets[i].toNumber());
+      // The refund is recorded and the game's reservation released
+      const stats = await program.account.playerStats.fetch(getPlayerStatsPDA(expired[i].publicKey));
+      expect(stats.totalWon.sub(statsBefore[i].totalWon).eq(bets[i])).to.be.true;
+      expect(stats.outstandingExposure.toNumber()).to.equal(0);
+      expect(game.exposure.toNumber()).to.equal(0);
+    }
+    // Auto-cancel was off for the last game, so it is left alone
+    const skipped = await program.account.gameAccount.fetch(getGamePDA(ineligible.publicKey));
+    expect(skipped.status).to.not.equal(3);
+    expect(await provider.connection.getBalance(ineligible.publicKey)).to.equal(balancesBefore[3]);
+  });
+
+  it("Blocks cancellation while the computation is still queued", async function () {
+    const player = await newFundedPlayer();
+    const gamePda = getGamePDA(player.publicKey);
+    const { computationOffset } = await queueChestGame(player);
+    const computationAccount = getComputationAccAddress(arciumEnv.arciumClusterOffset, computationOffset);
+
+    // Wait out the soft timeout; only a game whose computation is still queued is interesting here
+    await new Promise((resolve) => setTimeout(resolve, 61_000));
+    const game = await program.account.gameAccount.fetch(gamePda);
+    const info = await provider.connection.getAccountInfo(computationAccount);
+    // ComputationAccount.status sits after discriminator, payer, mxe, offset, fees, slot and counter
+    if (game.status !== 1 || !info || info.data[110] !== 0) {
+      this.skip();
+    }
+
+    await expectError(
+      program.methods
+        .cancelGame(false)
+        .accountsPartial({
+          player: player.publicKey,
+          gameAccount: gamePda,
+          mxeAccount: getMXEAccAddress(program.programId),
+          computationAccount,
+        })
+        .rpc({ commitment: "confirmed" }),
+      "ComputationStillQueued"
+    );
+  });
+
+  async function initPlayChestGameCompDef(
+    program: Program<VeiledChests>,
+    owner: anchor.web3.Keypair,
+    circuit: "play_chest_game" | "play_hidden_chest_game" | "play_odds_game" | "play_pot_game" = "play_chest_game"
+  ): Promise<string> {
+    const baseSeedCompDefAcc = getArciumAccountBaseSeed("ComputationDefinitionAccount");
+    const offset = getCompDefAccOffset(circuit);
+
+    const compDefPDA = PublicKey.findProgramAddressSync(
+      [baseSeedCompDefAcc, program.programId.toBuffer(), offset],
+      getArciumProgramId()
+    )[0];
+
+    console.log("Comp def PDA:", compDefPDA.toBase58());
+
+    // Get LUT address (v0.7.0)
+    const arciumProgram = getArciumProgram(provider);
+    const mxeAccount = getMXEAccAddress(program.programId);
+    const mxeAcc = await arciumProgram.account.mxeAccount.fetch(mxeAccount);
+    const lutAddress = getLookupTableAddress(program.programId, mxeAcc.lutOffsetSlot);
+
+    console.log("LUT address:", lutAddress.toBase58());
+
+    // Initialize the comp def
+    const init =
+      circuit === "play_chest_game"
+        ? program.methods.initPlayChestGameCompDef()
+        : circuit === "play_hidden_chest_game"
+          ? program.methods.initPlayHiddenChestGameCompDef()
+          : circuit === "play_odds_game"
+            ? program.methods.initPlayOddsGameCompDef()
+            : program.methods.initPlayPotGameCompDef();
+    const sig = await init
       .accounts({
         compDefAccount: compDefPDA,
         payer: owner.publicKey,